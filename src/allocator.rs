@@ -0,0 +1,109 @@
+use super::*;
+use crate::encoder::encoded_len;
+use allocator_api2::alloc::Allocator;
+use allocator_api2::vec::Vec;
+
+/// Encodes data following the SLIP protocol into a `Vec` allocated in
+/// `alloc`.
+///
+/// This works just like `encode`, but lets the caller choose where the
+/// encoded frame lives, for example in an arena along with the rest of the
+/// data it's being built from. On nightly, `allocator_api2` uses the
+/// standard library's `Allocator` trait, so any allocator works.
+///
+/// # Example:
+///
+/// ```rust
+/// use allocator_api2::alloc::Global;
+/// use simple_slip::encode_in;
+///
+/// let result = encode_in(&[0x01, 0xDB, 0x49, 0xC0, 0x15], Global).unwrap();
+///
+/// assert_eq!(result, [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0]);
+/// ```
+pub fn encode_in<A: Allocator>(raw_buffer: &[u8], alloc: A) -> Result<Vec<u8, A>, SlipError> {
+  let mut encoded_buffer: Vec<u8, A> = Vec::with_capacity_in(encoded_len(raw_buffer), alloc);
+
+  encode_to(raw_buffer, &mut encoded_buffer)?;
+
+  Ok(encoded_buffer)
+}
+
+/// Decodes data following the SLIP protocol into a `Vec` allocated in
+/// `alloc`.
+///
+/// This works just like `decode`, but lets the caller choose where the
+/// decoded frame lives, so that a capture analysis tool can, for example,
+/// keep every frame in one arena and free them all at once.
+///
+/// # Example:
+///
+/// ```rust
+/// use allocator_api2::alloc::Global;
+/// use simple_slip::decode_in;
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+///
+/// let result = decode_in(&input, Global).unwrap();
+///
+/// assert_eq!(result, [0x01, 0xDB, 0x49, 0xC0, 0x15]);
+/// ```
+pub fn decode_in<A: Allocator>(encoded_buffer: &[u8], alloc: A) -> Result<Vec<u8, A>, SlipError> {
+  let mut decoded_buffer: Vec<u8, A> = Vec::new_in(alloc);
+
+  decode_to(encoded_buffer, &mut decoded_buffer)?;
+
+  Ok(decoded_buffer)
+}
+
+impl<A: Allocator> ByteSink for Vec<u8, A> {
+  type Error = SlipError;
+
+  fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SlipError> {
+    self.extend_from_slice(bytes);
+    Ok(())
+  }
+
+  fn reserve(&mut self, additional: usize) {
+    Vec::reserve(self, additional);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use allocator_api2::alloc::{AllocError, Global, Layout};
+  use core::cell::Cell;
+  use core::ptr::NonNull;
+
+  #[derive(Default)]
+  struct Counting {
+    allocations: Cell<usize>,
+  }
+
+  // SAFETY: Every call is forwarded to `Global`.
+  unsafe impl Allocator for &Counting {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+      self.allocations.set(self.allocations.get() + 1);
+      Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+      // SAFETY: `ptr` was allocated by `Global` in `allocate`.
+      unsafe { Global.deallocate(ptr, layout) }
+    }
+  }
+
+  #[test]
+  fn round_trips_in_the_given_allocator() {
+    let counting = Counting::default();
+    let input: [u8; 5] = [0x01, ESC, 0x49, END, 0x15];
+
+    let encoded = encode_in(&input, &counting).unwrap();
+    let decoded = decode_in(&encoded, &counting).unwrap();
+
+    assert_eq!(encoded.as_slice(), encode(input).unwrap());
+    assert_eq!(decoded, input);
+    assert_eq!(counting.allocations.get(), 2);
+  }
+}
@@ -0,0 +1,88 @@
+use super::*;
+use crate::reader::READ_BUFFER_SIZE;
+use std::io;
+use std::task::{ready, Poll};
+
+/// The framing logic shared by every async integration, independent of
+/// which runtime's IO traits are used to drive it.
+#[derive(Debug)]
+pub(crate) struct FrameReader {
+  decoder: SlipDecoder,
+  buffer: Vec<u8>,
+  done: bool,
+}
+
+impl FrameReader {
+  pub(crate) fn new(decoder: SlipDecoder) -> Self {
+    Self {
+      decoder,
+      buffer: vec![0; READ_BUFFER_SIZE],
+      done: false,
+    }
+  }
+
+  /// Polls for the next frame, reading more data with `poll_read` as
+  /// needed. `None` means the reader has reached its end.
+  pub(crate) fn poll_frame<F>(&mut self, mut poll_read: F) -> Poll<Option<io::Result<Vec<u8>>>>
+  where
+    F: FnMut(&mut [u8]) -> Poll<io::Result<usize>>,
+  {
+    loop {
+      if let Some(frame) = self.decoder.next_frame() {
+        return Poll::Ready(Some(frame.map_err(io::Error::from)));
+      } else if self.done {
+        return Poll::Ready(None);
+      }
+
+      match ready!(poll_read(&mut self.buffer)) {
+        Ok(0) => self.done = true,
+        Ok(read) => self.decoder.push(&self.buffer[..read]),
+        Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+        Err(error) => return Poll::Ready(Some(Err(error))),
+      }
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct FrameWriter {
+  buffer: Vec<u8>,
+  written: usize,
+}
+
+impl FrameWriter {
+  /// Encodes `payload` onto the end of the write buffer.
+  pub(crate) fn push(&mut self, payload: &[u8]) {
+    encode_append(payload, &mut self.buffer);
+  }
+
+  /// Returns the number of encoded bytes waiting to be written.
+  pub(crate) fn len(&self) -> usize {
+    self.buffer.len() - self.written
+  }
+
+  /// Writes buffered data with `poll_write` until no more than `keep`
+  /// bytes are left.
+  pub(crate) fn poll_write<F>(&mut self, keep: usize, mut poll_write: F) -> Poll<io::Result<()>>
+  where
+    F: FnMut(&[u8]) -> Poll<io::Result<usize>>,
+  {
+    while self.len() > keep {
+      match ready!(poll_write(&self.buffer[self.written..])) {
+        Ok(0) => {
+          return Poll::Ready(Err(io::Error::new(
+            io::ErrorKind::WriteZero,
+            "failed to write buffered frames",
+          )))
+        }
+        Ok(written) => self.written += written,
+        Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+        Err(error) => return Poll::Ready(Err(error)),
+      }
+    }
+
+    self.buffer.drain(..self.written);
+    self.written = 0;
+    Poll::Ready(Ok(()))
+  }
+}
@@ -0,0 +1,224 @@
+use crate::async_core::FrameWriter;
+use futures_io::AsyncWrite;
+use futures_sink::Sink;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+const DEFAULT_SINK_CAPACITY: usize = 4096;
+
+/// A `Sink` encoding payloads as frames onto any `futures_io::AsyncWrite`.
+///
+/// Encoded frames are collected in a bounded internal write buffer, 4096
+/// bytes by default or set with `with_capacity`. While the buffer is full
+/// `poll_ready` writes to the inner writer and returns `Poll::Pending` until
+/// there's room again, so a slow writer pushes back on its producers rather
+/// than the buffer growing. `poll_flush` writes out everything that's
+/// buffered.
+///
+/// A frame is accepted whenever the buffer isn't full, so the buffer can
+/// exceed its capacity by at most the encoded size of one frame.
+///
+/// # Example:
+///
+/// ```rust
+/// use futures::executor::block_on;
+/// use futures::SinkExt;
+/// use simple_slip::SlipFrameSink;
+///
+/// let mut sink = SlipFrameSink::new(Vec::new());
+///
+/// block_on(sink.send(vec![0x01, 0xC0])).unwrap();
+///
+/// assert_eq!(sink.get_ref(), &[0xC0, 0x01, 0xDB, 0xDC, 0xC0]);
+/// ```
+#[derive(Debug)]
+pub struct SlipFrameSink<W> {
+  inner: W,
+  writer: FrameWriter,
+  capacity: usize,
+}
+
+impl<W: AsyncWrite + Unpin> SlipFrameSink<W> {
+  /// Creates a new sink writing frames to `inner`.
+  pub fn new(inner: W) -> Self {
+    Self::with_capacity(inner, DEFAULT_SINK_CAPACITY)
+  }
+
+  /// Creates a new sink writing frames to `inner`, buffering up to
+  /// `capacity` encoded bytes before `poll_ready` applies backpressure.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `capacity` is zero.
+  pub fn with_capacity(inner: W, capacity: usize) -> Self {
+    assert!(capacity > 0, "sink capacity must be greater than zero");
+
+    Self {
+      inner,
+      writer: FrameWriter::default(),
+      capacity,
+    }
+  }
+
+  /// Returns the number of encoded bytes waiting to be written.
+  pub fn buffered_len(&self) -> usize {
+    self.writer.len()
+  }
+
+  /// Returns a reference to the inner writer.
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner writer.
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.inner
+  }
+
+  /// Consumes the sink, returning the inner writer.
+  ///
+  /// Any buffered data that hasn't been flushed is lost.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+
+  fn poll_write_buffer(&mut self, cx: &mut Context<'_>, keep: usize) -> Poll<io::Result<()>> {
+    let Self { inner, writer, .. } = self;
+
+    writer.poll_write(keep, |buffer| Pin::new(&mut *inner).poll_write(cx, buffer))
+  }
+}
+
+impl<W: AsyncWrite + Unpin> Sink<Vec<u8>> for SlipFrameSink<W> {
+  type Error = io::Error;
+
+  fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    let keep = this.capacity - 1;
+
+    this.poll_write_buffer(cx, keep)
+  }
+
+  fn start_send(self: Pin<&mut Self>, item: Vec<u8>) -> io::Result<()> {
+    self.get_mut().writer.push(&item);
+    Ok(())
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    ready!(this.poll_write_buffer(cx, 0))?;
+
+    Pin::new(&mut this.inner).poll_flush(cx)
+  }
+
+  fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    ready!(this.poll_write_buffer(cx, 0))?;
+
+    Pin::new(&mut this.inner).poll_close(cx)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{encode, END, ESC};
+  use futures::executor::block_on;
+  use futures::SinkExt;
+
+  #[derive(Default)]
+  struct Stingy(Vec<u8>, bool);
+
+  impl AsyncWrite for Stingy {
+    fn poll_write(
+      mut self: Pin<&mut Self>,
+      cx: &mut Context<'_>,
+      buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+      self.1 = !self.1;
+      if self.1 {
+        cx.waker().wake_by_ref();
+        return Poll::Pending;
+      }
+
+      let len = buf.len().min(3);
+      self.0.extend_from_slice(&buf[..len]);
+      Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+      Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+      Poll::Ready(Ok(()))
+    }
+  }
+
+  #[test]
+  fn writes_frames_through_slow_writer() {
+    let payloads: Vec<Vec<u8>> = vec![vec![0x01, ESC, 0x02], vec![END], vec![0x03; 10]];
+    let mut sink = SlipFrameSink::new(Stingy::default());
+
+    block_on(async {
+      for payload in &payloads {
+        sink.feed(payload.clone()).await.unwrap();
+      }
+      sink.close().await.unwrap();
+    });
+
+    let expected: Vec<u8> = payloads
+      .iter()
+      .flat_map(|payload| encode(payload).unwrap())
+      .collect();
+    assert_eq!(sink.into_inner().0, expected);
+  }
+
+  #[test]
+  fn poll_ready_waits_while_buffer_is_full() {
+    struct Blocked(Vec<u8>, bool);
+
+    impl AsyncWrite for Blocked {
+      fn poll_write(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+      ) -> Poll<io::Result<usize>> {
+        if self.1 {
+          return Poll::Pending;
+        }
+
+        self.0.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+      }
+
+      fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+      }
+
+      fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+      }
+    }
+
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    let mut sink = SlipFrameSink::with_capacity(Blocked(Vec::new(), true), 8);
+
+    assert!(Pin::new(&mut sink).poll_ready(&mut cx).is_ready());
+    Pin::new(&mut sink).start_send(vec![0x01, 0x02]).unwrap();
+    assert!(Pin::new(&mut sink).poll_ready(&mut cx).is_ready());
+    Pin::new(&mut sink).start_send(vec![0x03, 0x04]).unwrap();
+    assert_eq!(sink.buffered_len(), 8);
+    assert!(Pin::new(&mut sink).poll_ready(&mut cx).is_pending());
+    assert_eq!(sink.buffered_len(), 8);
+
+    sink.get_mut().1 = false;
+    assert!(Pin::new(&mut sink).poll_ready(&mut cx).is_ready());
+    assert_eq!(sink.buffered_len(), 0);
+    assert_eq!(
+      sink.get_ref().0,
+      [END, 0x01, 0x02, END, END, 0x03, 0x04, END]
+    );
+  }
+}
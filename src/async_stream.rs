@@ -0,0 +1,120 @@
+use super::*;
+use crate::async_core::FrameReader;
+use futures_core::Stream;
+use futures_io::AsyncRead;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A `Stream` of the frames read from any `futures_io::AsyncRead`.
+///
+/// Partial frames are kept between polls, so frames split across any
+/// number of reads are put back together. The stream ends when the reader
+/// does, dropping any partial frame.
+///
+/// # Example:
+///
+/// ```rust
+/// use futures::executor::block_on;
+/// use futures::io::Cursor;
+/// use futures::StreamExt;
+/// use simple_slip::SlipFrameStream;
+///
+/// let input = Cursor::new(vec![0xC0, 0x01, 0xC0, 0xC0, 0x02, 0xDB, 0xDC, 0xC0]);
+/// let stream = SlipFrameStream::new(input);
+///
+/// let frames: Vec<Vec<u8>> = block_on(stream.map(|frame| frame.unwrap()).collect());
+///
+/// assert_eq!(frames, [vec![0x01], vec![0x02, 0xC0]]);
+/// ```
+#[derive(Debug)]
+pub struct SlipFrameStream<R> {
+  inner: R,
+  reader: FrameReader,
+}
+
+impl<R: AsyncRead + Unpin> SlipFrameStream<R> {
+  /// Creates a new stream of the frames read from `inner`.
+  pub fn new(inner: R) -> Self {
+    Self::with_decoder(inner, SlipDecoder::new())
+  }
+
+  /// Creates a new stream of the frames read from `inner`, decoded with
+  /// `decoder`.
+  pub fn with_decoder(inner: R, decoder: SlipDecoder) -> Self {
+    Self {
+      inner,
+      reader: FrameReader::new(decoder),
+    }
+  }
+
+  /// Returns a reference to the inner reader.
+  pub fn get_ref(&self) -> &R {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader.
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.inner
+  }
+
+  /// Consumes the stream, returning the inner reader.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: AsyncRead + Unpin> Stream for SlipFrameStream<R> {
+  type Item = io::Result<Vec<u8>>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let Self { inner, reader } = self.get_mut();
+
+    reader.poll_frame(|buffer| Pin::new(&mut *inner).poll_read(cx, buffer))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use futures::executor::block_on;
+  use futures::StreamExt;
+
+  struct Trickle(Vec<u8>, bool);
+
+  impl AsyncRead for Trickle {
+    fn poll_read(
+      mut self: Pin<&mut Self>,
+      cx: &mut Context<'_>,
+      buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+      self.1 = !self.1;
+      if self.1 {
+        cx.waker().wake_by_ref();
+        return Poll::Pending;
+      } else if self.0.is_empty() {
+        return Poll::Ready(Ok(0));
+      }
+
+      buf[0] = self.0.remove(0);
+      Poll::Ready(Ok(1))
+    }
+  }
+
+  #[test]
+  fn yields_frames_across_pending_reads() {
+    let input: Vec<u8> = vec![
+      END, 0x01, ESC, ESC_END, END, 0x02, ESC, 0x03, END, 0x04, END, 0x05,
+    ];
+    let results: Vec<io::Result<Vec<u8>>> =
+      block_on(SlipFrameStream::new(Trickle(input, false)).collect());
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), &[0x01, END]);
+    assert_eq!(
+      results[1].as_ref().unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+    assert_eq!(results[2].as_ref().unwrap(), &[0x04]);
+  }
+}
@@ -0,0 +1,213 @@
+use super::*;
+use crate::decoder::decode_body;
+use alloc::vec::Vec;
+
+/// A frame borrowed, still encoded, from the buffer it was received in.
+///
+/// Nothing is unescaped until it's asked for, so a consumer that only
+/// needs to look at a header byte, or to route frames by their length,
+/// never pays for decoding the whole frame. A frame that turns out not to
+/// need unescaping at all can be used straight from `escaped`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::borrowed_frames;
+///
+/// let input: [u8; 8] = [0xC0, 0x01, 0x02, 0xC0, 0x03, 0xDB, 0xDC, 0xC0];
+/// let mut frames = borrowed_frames(&input);
+///
+/// let frame = frames.next().unwrap();
+/// assert!(!frame.needs_unescaping());
+/// assert_eq!(frame.escaped(), [0x01, 0x02]);
+///
+/// let frame = frames.next().unwrap();
+/// assert_eq!(frame.bytes().next(), Some(Ok(0x03)));
+/// assert_eq!(frame.escaped_len(), 3);
+/// assert_eq!(frame.to_vec().unwrap(), [0x03, 0xC0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowedFrame<'a> {
+  escaped: &'a [u8],
+}
+
+impl<'a> BorrowedFrame<'a> {
+  /// Wraps the encoded bytes of a single frame, without the `END` (0xC0)
+  /// delimiters around it.
+  pub fn new(escaped: &'a [u8]) -> Self {
+    Self { escaped }
+  }
+
+  /// Returns the frame's bytes as they were received, still escaped.
+  pub fn escaped(&self) -> &'a [u8] {
+    self.escaped
+  }
+
+  /// Returns the length of the frame as it was received, which is at least
+  /// as long as it is once decoded.
+  pub fn escaped_len(&self) -> usize {
+    self.escaped.len()
+  }
+
+  /// Returns `true` if the frame contains any escape sequences, and so
+  /// differs from what `escaped` returns once decoded.
+  pub fn needs_unescaping(&self) -> bool {
+    self.escaped.contains(&ESC)
+  }
+
+  /// Returns an iterator that unescapes the frame a byte at a time, as it's
+  /// advanced.
+  ///
+  /// An invalid escape sequence is returned as a
+  /// `SlipError::InvalidEscapeByte` or `SlipError::TruncatedEscape` error,
+  /// after which the iterator ends.
+  pub fn bytes(&self) -> UnescapedBytes<'a> {
+    UnescapedBytes { rest: self.escaped }
+  }
+
+  /// Decodes the frame into a new `Vec<u8>`.
+  pub fn to_vec(&self) -> Result<Vec<u8>, SlipError> {
+    let mut decoded_buffer: Vec<u8> = Vec::with_capacity(self.escaped.len());
+    decode_body(self.escaped, &mut decoded_buffer)?;
+
+    Ok(decoded_buffer)
+  }
+
+  /// Decodes the frame into the start of `decoded_buffer`, returning the
+  /// decoded length.
+  ///
+  /// If the buffer is too small to hold the decoded frame,
+  /// `SlipError::BufferTooSmall` is returned and the contents of the buffer
+  /// are unspecified.
+  pub fn copy_into(&self, decoded_buffer: &mut [u8]) -> Result<usize, SlipError> {
+    let capacity = decoded_buffer.len();
+    let mut rest = decoded_buffer;
+
+    decode_body(self.escaped, &mut rest)?;
+
+    Ok(capacity - rest.len())
+  }
+}
+
+/// An iterator over the unescaped bytes of a `BorrowedFrame`, created by
+/// `BorrowedFrame::bytes`.
+#[derive(Debug, Clone)]
+pub struct UnescapedBytes<'a> {
+  rest: &'a [u8],
+}
+
+impl Iterator for UnescapedBytes<'_> {
+  type Item = Result<u8, SlipError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let (&byte, rest) = self.rest.split_first()?;
+    self.rest = rest;
+
+    if byte != ESC {
+      return Some(Ok(byte));
+    }
+
+    match self.rest.split_first() {
+      Some((&ESC_END, rest)) => {
+        self.rest = rest;
+        Some(Ok(END))
+      }
+      Some((&ESC_ESC, rest)) => {
+        self.rest = rest;
+        Some(Ok(ESC))
+      }
+      found => {
+        self.rest = &[];
+        Some(Err(SlipError::invalid_escape(
+          found.map(|(&byte, _)| byte),
+          END,
+        )))
+      }
+    }
+  }
+}
+
+/// Splits a buffer of data following the SLIP protocol into
+/// `BorrowedFrame`s, without decoding them.
+///
+/// Anything before the first `END` (0xC0) byte is skipped, as are empty
+/// frames. Data after the last `END` byte isn't a complete frame yet, and
+/// is left for `BorrowedFrames::remainder`.
+pub fn borrowed_frames(encoded_buffer: &[u8]) -> BorrowedFrames<'_> {
+  let rest = match scan::find_end(encoded_buffer) {
+    Some(idx) => &encoded_buffer[idx..],
+    None => encoded_buffer,
+  };
+
+  BorrowedFrames { rest }
+}
+
+/// An iterator over the frames in a buffer, created by `borrowed_frames`.
+#[derive(Debug, Clone)]
+pub struct BorrowedFrames<'a> {
+  rest: &'a [u8],
+}
+
+impl<'a> BorrowedFrames<'a> {
+  /// Returns the data that hasn't been split into frames yet, starting from
+  /// the `END` (0xC0) byte before the next frame, if there is one.
+  pub fn remainder(&self) -> &'a [u8] {
+    self.rest
+  }
+}
+
+impl<'a> Iterator for BorrowedFrames<'a> {
+  type Item = BorrowedFrame<'a>;
+
+  fn next(&mut self) -> Option<BorrowedFrame<'a>> {
+    loop {
+      let body = self.rest.strip_prefix(&[END])?;
+      let end = scan::find_end(body)?;
+      self.rest = &body[end..];
+
+      if end > 0 {
+        return Some(BorrowedFrame::new(&body[..end]));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn unescapes_lazily() {
+    let input: Vec<u8> = vec![
+      0xA1, END, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END, 0x02, ESC, 0x03, END, 0x04,
+    ];
+    let mut frames = borrowed_frames(&input);
+
+    let frame = frames.next().unwrap();
+    assert!(frame.needs_unescaping());
+    assert_eq!(frame.to_vec(), decode(&input[..11]));
+    assert_eq!(
+      frame.bytes().collect::<Result<Vec<u8>, SlipError>>(),
+      frame.to_vec()
+    );
+    let mut buffer = [0u8; 5];
+    assert_eq!(frame.copy_into(&mut buffer), Ok(5));
+    assert_eq!(
+      frame.copy_into(&mut buffer[..4]),
+      Err(SlipError::BufferTooSmall)
+    );
+
+    let frame = frames.next().unwrap();
+    assert_eq!(
+      frame.bytes().collect::<Vec<_>>(),
+      [Ok(0x02), Err(SlipError::InvalidEscapeByte { found: 0x03 })]
+    );
+    assert_eq!(
+      frame.to_vec(),
+      Err(SlipError::InvalidEscapeByte { found: 0x03 })
+    );
+
+    assert_eq!(frames.next(), None);
+    assert_eq!(frames.remainder(), [END, 0x04]);
+  }
+}
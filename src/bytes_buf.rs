@@ -0,0 +1,385 @@
+use super::*;
+use crate::decoder::{decode_body, decode_packet, decoded_len_bound, frame_body, split_packets};
+use crate::encoder::encoded_len;
+use alloc::vec::Vec;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use core::ops::Deref;
+
+/// A frame decoded from a `Bytes` buffer.
+///
+/// A frame with no escape sequences in it decodes to exactly the bytes it
+/// was sent as, so it's returned as a `Bytes::slice` of the input, sharing
+/// its memory, rather than being copied. Only frames that actually needed
+/// unescaping get a buffer of their own. Either way, it dereferences to
+/// the decoded bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BytesFrame {
+  /// The frame needed no unescaping, and is a slice of the input.
+  Shared(Bytes),
+  /// The frame was unescaped into a buffer of its own.
+  Owned(Vec<u8>),
+}
+
+impl BytesFrame {
+  /// Returns `true` if the frame shares the input's memory.
+  pub fn is_shared(&self) -> bool {
+    matches!(self, BytesFrame::Shared(_))
+  }
+
+  /// Converts the frame into `Bytes`, which never copies it.
+  pub fn into_bytes(self) -> Bytes {
+    match self {
+      BytesFrame::Shared(bytes) => bytes,
+      BytesFrame::Owned(buffer) => Bytes::from(buffer),
+    }
+  }
+}
+
+impl Deref for BytesFrame {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    match self {
+      BytesFrame::Shared(bytes) => bytes,
+      BytesFrame::Owned(buffer) => buffer,
+    }
+  }
+}
+
+impl AsRef<[u8]> for BytesFrame {
+  fn as_ref(&self) -> &[u8] {
+    self
+  }
+}
+
+impl From<BytesFrame> for Bytes {
+  fn from(frame: BytesFrame) -> Bytes {
+    frame.into_bytes()
+  }
+}
+
+/// Encodes data following the SLIP protocol into a `Bytes`.
+///
+/// The exact size of the encoded frame is allocated up front, and the
+/// result can be handed straight to anything that takes `Bytes`, such as a
+/// tokio `Framed` sink, without converting from a `Vec<u8>`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_to_bytes;
+///
+/// let result = encode_to_bytes(&[0x01, 0xDB, 0x49, 0xC0, 0x15]);
+///
+/// assert_eq!(result, [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0][..]);
+/// ```
+pub fn encode_to_bytes(raw_buffer: &[u8]) -> Bytes {
+  let mut encoded_buffer = BytesMut::with_capacity(encoded_len(raw_buffer));
+
+  // Appending to a `BytesMut` never fails.
+  let _ = encode_to(raw_buffer, &mut encoded_buffer);
+
+  encoded_buffer.freeze()
+}
+
+impl ByteSink for BytesMut {
+  type Error = SlipError;
+
+  fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SlipError> {
+    self.put_slice(bytes);
+    Ok(())
+  }
+
+  fn reserve(&mut self, additional: usize) {
+    BytesMut::reserve(self, additional);
+  }
+}
+
+/// Decodes the first frame off the front of a `BytesMut` following the SLIP
+/// protocol.
+///
+/// Anything before the first `END` (0xC0) byte is dropped, then the frame
+/// is split off `buffer` with `split_to` and decoded in place, so the
+/// returned `Bytes` shares the buffer's memory rather than being copied.
+/// The terminating `END` byte is left in `buffer`, as it may also be the
+/// start of the next frame.
+///
+/// Returns `None` if `buffer` doesn't hold a complete frame yet, keeping
+/// the partial frame for when more data has been appended. A frame that
+/// isn't encoded correctly is consumed and returned as an error.
+///
+/// # Example:
+///
+/// ```rust
+/// use bytes::BytesMut;
+/// use simple_slip::decode_from_bytes;
+///
+/// let mut buffer = BytesMut::from(&[0xA1, 0xC0, 0x01, 0xDB, 0xDC, 0xC0, 0x02][..]);
+///
+/// assert_eq!(decode_from_bytes(&mut buffer).unwrap().unwrap(), [0x01, 0xC0][..]);
+/// assert!(decode_from_bytes(&mut buffer).is_none());
+///
+/// buffer.extend_from_slice(&[0x03, 0xC0]);
+/// assert_eq!(decode_from_bytes(&mut buffer).unwrap().unwrap(), [0x02, 0x03][..]);
+/// assert_eq!(&buffer[..], [0xC0]);
+/// ```
+pub fn decode_from_bytes(buffer: &mut BytesMut) -> Option<Result<Bytes, SlipError>> {
+  loop {
+    match scan::find_end(buffer) {
+      Some(start) => drop(buffer.split_to(start)),
+      None => {
+        buffer.clear();
+        return None;
+      }
+    }
+
+    let end = scan::find_end(&buffer[1..])? + 1;
+    let mut frame = buffer.split_to(end);
+    if frame.len() == 1 {
+      continue;
+    }
+
+    return Some(decode_in_place(&mut frame).map(|len| {
+      frame.truncate(len);
+      frame.freeze()
+    }));
+  }
+}
+
+/// Decodes data following the SLIP protocol held in a `Bytes`.
+///
+/// This works just like `decode`, except that a frame with nothing to
+/// unescape is returned as a `BytesFrame::Shared` slice of `encoded_buffer`
+/// instead of being copied.
+///
+/// # Example:
+///
+/// ```rust
+/// use bytes::Bytes;
+/// use simple_slip::decode_bytes;
+///
+/// let input = Bytes::from_static(&[0xC0, 0x01, 0x02, 0xC0]);
+/// let frame = decode_bytes(&input).unwrap();
+///
+/// assert!(frame.is_shared());
+/// assert_eq!(*frame, [0x01, 0x02]);
+///
+/// let input = Bytes::from_static(&[0xC0, 0x01, 0xDB, 0xDC, 0xC0]);
+/// let frame = decode_bytes(&input).unwrap();
+///
+/// assert!(!frame.is_shared());
+/// assert_eq!(*frame, [0x01, 0xC0]);
+/// ```
+pub fn decode_bytes(encoded_buffer: &Bytes) -> Result<BytesFrame, SlipError> {
+  let body = frame_body(encoded_buffer)?;
+
+  if let Some(body) = body.strip_suffix(&[END]) {
+    if scan::find_special(body).is_none() {
+      return Ok(BytesFrame::Shared(encoded_buffer.slice_ref(body)));
+    }
+  }
+
+  let mut decoded_buffer: Vec<u8> = Vec::with_capacity(decoded_len_bound(body));
+  decode_body(body, &mut decoded_buffer)?;
+
+  Ok(BytesFrame::Owned(decoded_buffer))
+}
+
+/// Decodes data following the SLIP protocol held in a `Bytes` into
+/// delimited packets.
+///
+/// This works just like `decode_packets`, except that packets with nothing
+/// to unescape are returned as `BytesFrame::Shared` slices of
+/// `encoded_buffer`, and the remainder is a slice of it too, so in the
+/// common case nothing is copied at all.
+///
+/// # Example:
+///
+/// ```rust
+/// use bytes::Bytes;
+/// use simple_slip::decode_packets_bytes;
+///
+/// let input = Bytes::from_static(&[0xC0, 0x01, 0xC0, 0xC0, 0x02, 0xDB, 0xDD, 0xC0, 0xC0, 0x03]);
+///
+/// let (packets, remainder) = decode_packets_bytes(&input);
+///
+/// assert_eq!(*packets[0], [0x01]);
+/// assert_eq!(*packets[1], [0x02, 0xDB]);
+/// assert!(packets[0].is_shared());
+/// assert_eq!(remainder, [0xC0, 0x03][..]);
+/// ```
+pub fn decode_packets_bytes(encoded_buffer: &Bytes) -> (Vec<BytesFrame>, Bytes) {
+  let mut packets: Vec<BytesFrame> = Vec::new();
+
+  let consumed = split_packets(encoded_buffer, |body| {
+    if scan::find_special(body).is_none() {
+      packets.push(BytesFrame::Shared(encoded_buffer.slice_ref(body)));
+    } else if let Ok(packet) = decode_packet(body) {
+      packets.push(BytesFrame::Owned(packet));
+    }
+  });
+
+  (packets, encoded_buffer.slice(consumed..))
+}
+
+/// Decodes data following the SLIP protocol from any `bytes::Buf`.
+///
+/// This works just like `decode`, except that the input doesn't have to be
+/// one contiguous slice. It's read a chunk at a time, so chained or
+/// rope-style buffers, such as two `Bytes` joined with `Buf::chain`, are
+/// decoded without first being flattened, including escape sequences split
+/// between chunks. All of `buffer` is consumed.
+///
+/// # Example:
+///
+/// ```rust
+/// use bytes::{Buf, Bytes};
+/// use simple_slip::decode_buf;
+///
+/// let head = Bytes::from_static(&[0xC0, 0x01, 0xDB]);
+/// let tail = Bytes::from_static(&[0xDC, 0x02, 0xC0]);
+///
+/// assert_eq!(decode_buf(head.chain(tail)).unwrap(), [0x01, 0xC0, 0x02]);
+/// ```
+pub fn decode_buf<B: Buf>(mut buffer: B) -> Result<Vec<u8>, SlipError> {
+  loop {
+    let chunk = buffer.chunk();
+    if chunk.is_empty() {
+      return Err(SlipError::NoEndDelimiter);
+    }
+
+    match scan::find_end(chunk) {
+      Some(idx) => {
+        buffer.advance(idx + 1);
+        break;
+      }
+      None => {
+        let len = chunk.len();
+        buffer.advance(len);
+      }
+    }
+  }
+
+  let mut decoded_buffer: Vec<u8> = Vec::with_capacity(buffer.remaining());
+  let mut escaped = false;
+
+  while buffer.has_remaining() {
+    let chunk = buffer.chunk();
+    let mut idx = 0;
+
+    while idx < chunk.len() {
+      if escaped {
+        escaped = false;
+        match chunk[idx] {
+          ESC_END => decoded_buffer.push(END),
+          ESC_ESC => decoded_buffer.push(ESC),
+          byte => return Err(SlipError::invalid_escape(Some(byte), END)),
+        }
+        idx += 1;
+        continue;
+      }
+
+      match chunk[idx] {
+        END => idx += 1,
+        ESC => {
+          escaped = true;
+          idx += 1;
+        }
+        _ => {
+          let rest = &chunk[idx..];
+          let run = scan::find_special(rest).unwrap_or(rest.len());
+          decoded_buffer.extend_from_slice(&rest[..run]);
+          idx += run;
+        }
+      }
+    }
+
+    let len = chunk.len();
+    buffer.advance(len);
+  }
+
+  if escaped {
+    return Err(SlipError::TruncatedEscape);
+  }
+
+  Ok(decoded_buffer)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_frames_without_copying() {
+    let mut buffer = BytesMut::new();
+    buffer.extend_from_slice(&encode_to_bytes(&[0x01, END, 0x02]));
+    buffer.extend_from_slice(&[END, END, 0x03, ESC, 0x04, END]);
+    buffer.extend_from_slice(&encode_to_bytes(&[0x05]));
+    let start = buffer.as_ptr();
+
+    let frame = decode_from_bytes(&mut buffer).unwrap().unwrap();
+    assert_eq!(frame, [0x01, END, 0x02][..]);
+    assert_eq!(frame.as_ptr(), start);
+
+    assert_eq!(
+      decode_from_bytes(&mut buffer),
+      Some(Err(SlipError::InvalidEscapeByte { found: 0x04 }))
+    );
+    assert_eq!(
+      decode_from_bytes(&mut buffer),
+      Some(Ok(Bytes::from_static(&[0x05])))
+    );
+    assert_eq!(decode_from_bytes(&mut buffer), None);
+    assert_eq!(&buffer[..], [END]);
+  }
+
+  #[test]
+  fn shares_packets_without_escapes() {
+    let mut input: Vec<u8> = Vec::new();
+    for payload in [&[0x01, 0x02][..], &[END], &[0x03]] {
+      input.extend(encode(payload).unwrap());
+    }
+    let input = Bytes::from(input);
+
+    let (packets, remainder) = decode_packets_bytes(&input);
+
+    let expected = decode_packets(&input);
+    assert_eq!(
+      packets
+        .iter()
+        .map(|packet| packet.to_vec())
+        .collect::<Vec<_>>(),
+      expected.0
+    );
+    assert_eq!(remainder, expected.1);
+    assert_eq!(
+      packets
+        .iter()
+        .map(BytesFrame::is_shared)
+        .collect::<Vec<_>>(),
+      [true, false, true]
+    );
+  }
+
+  #[test]
+  fn decodes_buf_split_at_every_position() {
+    let input: Vec<u8> = vec![
+      0xA1, END, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END,
+    ];
+
+    for split in 0..=input.len() {
+      let (head, tail) = input.split_at(split);
+      assert_eq!(
+        decode_buf(head.chain(tail)),
+        decode(&input),
+        "split at {}",
+        split
+      );
+    }
+    assert_eq!(
+      decode_buf([END, 0x01].as_slice().chain([ESC].as_slice())),
+      Err(SlipError::TruncatedEscape)
+    );
+    assert_eq!(decode_buf(&[0x01][..]), Err(SlipError::NoEndDelimiter));
+  }
+}
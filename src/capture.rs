@@ -0,0 +1,126 @@
+use super::*;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// A SLIP capture file, memory-mapped so it can be decoded without reading
+/// it into memory first.
+///
+/// Only the pages of the file that are being decoded need to be resident
+/// at any one time, which makes multi-gigabyte captures practical to work
+/// through frame by frame.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::Capture;
+///
+/// let name = format!("simple_slip_capture_doc_{}.bin", std::process::id());
+/// let path = std::env::temp_dir().join(name);
+/// std::fs::write(&path, [0xC0, 0x01, 0xC0, 0xC0, 0x02, 0xDB, 0xDD, 0xC0]).unwrap();
+///
+/// // SAFETY: The file was only just written, and nothing else modifies it.
+/// let capture = unsafe { Capture::open(&path) }.unwrap();
+/// let frames: Vec<Vec<u8>> = capture.frames().map(|frame| frame.unwrap()).collect();
+///
+/// assert_eq!(frames, [vec![0x01], vec![0x02, 0xDB]]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Capture {
+  map: Mmap,
+}
+
+impl Capture {
+  /// Memory-maps the capture file at `path`.
+  ///
+  /// # Safety
+  ///
+  /// The file must not be modified or truncated, by this process or any
+  /// other, while the capture is open. The mapped bytes are handed out as
+  /// `&[u8]`, so changing them underneath is undefined behavior, and
+  /// truncating the file can crash the process with `SIGBUS`.
+  pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+    let file = File::open(path)?;
+    // SAFETY: The map is read only, and the caller guarantees the file
+    // isn't modified while it's mapped.
+    let map = unsafe { Mmap::map(&file)? };
+
+    Ok(Self { map })
+  }
+
+  /// Returns the raw, encoded contents of the capture.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.map
+  }
+
+  /// Returns an iterator decoding each frame in the capture in turn.
+  ///
+  /// Frames that aren't encoded correctly are returned as errors, and a
+  /// frame cut off by the end of the capture is ignored.
+  pub fn frames(&self) -> CaptureFrames<'_> {
+    CaptureFrames {
+      remaining: &self.map,
+    }
+  }
+}
+
+/// An iterator over the frames of a `Capture`, created by `Capture::frames`.
+#[derive(Debug, Clone)]
+pub struct CaptureFrames<'a> {
+  remaining: &'a [u8],
+}
+
+impl Iterator for CaptureFrames<'_> {
+  type Item = Result<Vec<u8>, SlipError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match try_decode(self.remaining) {
+      DecodeStatus::Complete { frame, consumed } => {
+        self.remaining = &self.remaining[consumed..];
+        Some(Ok(frame))
+      }
+      DecodeStatus::Invalid { error, consumed } => {
+        self.remaining = &self.remaining[consumed..];
+        Some(Err(error))
+      }
+      DecodeStatus::Incomplete => {
+        self.remaining = &[];
+        None
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_frames_from_mapped_file() {
+    let path = std::env::temp_dir().join(format!(
+      "simple_slip_capture_test_{}.bin",
+      std::process::id()
+    ));
+    let mut contents: Vec<u8> = vec![0xA1];
+    for idx in 0..1000u16 {
+      contents.extend(encode(idx.to_be_bytes()).unwrap());
+    }
+    contents.extend([END, 0x01, ESC, 0x02, END, 0x03]);
+    std::fs::write(&path, &contents).unwrap();
+
+    // SAFETY: The file is private to this test and isn't modified.
+    let capture = unsafe { Capture::open(&path) }.unwrap();
+    let frames: Vec<Result<Vec<u8>, SlipError>> = capture.frames().collect();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(frames.len(), 1001);
+    assert_eq!(frames[0xDB].as_ref().unwrap(), &[0x00, ESC]);
+    assert_eq!(frames[999].as_ref().unwrap(), &999u16.to_be_bytes());
+    assert_eq!(
+      frames[1000],
+      Err(SlipError::InvalidEscapeByte { found: 0x02 })
+    );
+  }
+}
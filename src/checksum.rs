@@ -0,0 +1,273 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// A checksum or MAC appended to the payload of each frame, and verified
+/// and stripped again when it's decoded.
+///
+/// `Crc16`, `Xor8`, `Fletcher16`, and `Crc32` with the `crc` feature, are
+/// built in, and any other integrity check can be plugged into
+/// `encode_with_checksum` and `decode_with_checksum` by implementing this
+/// trait.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_with_checksum, encode_with_checksum, FrameChecksum};
+///
+/// struct Sum;
+///
+/// impl FrameChecksum for Sum {
+///   type Output = [u8; 1];
+///
+///   fn len(&self) -> usize {
+///     1
+///   }
+///
+///   fn compute(&self, payload: &[u8]) -> [u8; 1] {
+///     [payload.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))]
+///   }
+/// }
+///
+/// let encoded = encode_with_checksum(&Sum, &[0x01, 0x02]).unwrap();
+/// assert_eq!(encoded, [0xC0, 0x01, 0x02, 0x03, 0xC0]);
+/// assert_eq!(decode_with_checksum(&Sum, &encoded).unwrap(), [0x01, 0x02]);
+/// ```
+#[allow(clippy::len_without_is_empty)]
+pub trait FrameChecksum {
+  /// The checksum of a payload.
+  type Output: AsRef<[u8]>;
+
+  /// Returns the length of the checksum in bytes, which must always be the
+  /// length of what `compute` returns.
+  fn len(&self) -> usize;
+
+  /// Computes the checksum of `payload`.
+  fn compute(&self, payload: &[u8]) -> Self::Output;
+
+  /// Returns `true` if `checksum` is the right checksum for `payload`.
+  ///
+  /// This computes the checksum and compares them, which can be replaced,
+  /// for example by a constant-time comparison for a MAC.
+  fn verify(&self, payload: &[u8], checksum: &[u8]) -> bool {
+    self.compute(payload).as_ref() == checksum
+  }
+}
+
+/// The CRC-16/CCITT-FALSE checksum (polynomial 0x1021, starting from
+/// 0xFFFF), appended big-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Crc16;
+
+impl FrameChecksum for Crc16 {
+  type Output = [u8; 2];
+
+  fn len(&self) -> usize {
+    2
+  }
+
+  fn compute(&self, payload: &[u8]) -> [u8; 2] {
+    crc16(payload).to_be_bytes()
+  }
+}
+
+/// An 8-bit checksum that XORs every byte of the payload together, as
+/// appended by many simple vendor devices.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{encode_with_checksum, Xor8};
+///
+/// let encoded = encode_with_checksum(&Xor8, &[0x01, 0x02, 0x04]).unwrap();
+/// assert_eq!(encoded, [0xC0, 0x01, 0x02, 0x04, 0x07, 0xC0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Xor8;
+
+impl FrameChecksum for Xor8 {
+  type Output = [u8; 1];
+
+  fn len(&self) -> usize {
+    1
+  }
+
+  fn compute(&self, payload: &[u8]) -> [u8; 1] {
+    [payload.iter().fold(0, |xor, byte| xor ^ byte)]
+  }
+}
+
+/// The Fletcher-16 checksum, appended big-endian, so the second running sum
+/// comes before the first.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_with_checksum, encode_with_checksum, Fletcher16};
+///
+/// let encoded = encode_with_checksum(&Fletcher16, b"abcde").unwrap();
+/// assert_eq!(&encoded[6..8], [0xC8, 0xF0]);
+/// assert_eq!(decode_with_checksum(&Fletcher16, &encoded).unwrap(), b"abcde");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Fletcher16;
+
+impl FrameChecksum for Fletcher16 {
+  type Output = [u8; 2];
+
+  fn len(&self) -> usize {
+    2
+  }
+
+  fn compute(&self, payload: &[u8]) -> [u8; 2] {
+    let (mut sum1, mut sum2) = (0u16, 0u16);
+    for &byte in payload {
+      sum1 = (sum1 + u16::from(byte)) % 255;
+      sum2 = (sum2 + sum1) % 255;
+    }
+
+    [sum2 as u8, sum1 as u8]
+  }
+}
+
+/// Encodes data following the SLIP protocol, with the checksum of the
+/// payload computed by `checksum` appended before it's escaped.
+pub fn encode_with_checksum<C, T>(checksum: &C, raw_buffer: T) -> Result<Vec<u8>, SlipError>
+where
+  C: FrameChecksum + ?Sized,
+  T: AsRef<[u8]>,
+{
+  let raw_buffer = raw_buffer.as_ref();
+
+  Ok(encode_parts(&[
+    raw_buffer,
+    checksum.compute(raw_buffer).as_ref(),
+  ]))
+}
+
+/// Decodes data following the SLIP protocol that was encoded with
+/// `encode_with_checksum`, verifying and stripping the checksum.
+///
+/// Returns `SlipError::ChecksumMismatch` if `checksum` doesn't verify the
+/// payload, or the frame is too short to hold a checksum.
+pub fn decode_with_checksum<C, T>(checksum: &C, encoded_buffer: T) -> Result<Vec<u8>, SlipError>
+where
+  C: FrameChecksum + ?Sized,
+  T: AsRef<[u8]>,
+{
+  let mut decoded_buffer = decode(encoded_buffer)?;
+
+  let payload_len = decoded_buffer
+    .len()
+    .checked_sub(checksum.len())
+    .ok_or(SlipError::ChecksumMismatch)?;
+  let (payload, expected) = decoded_buffer.split_at(payload_len);
+  if !checksum.verify(payload, expected) {
+    return Err(SlipError::ChecksumMismatch);
+  }
+
+  decoded_buffer.truncate(payload_len);
+  Ok(decoded_buffer)
+}
+
+/// Encodes data following the SLIP protocol, with a CRC-16 checksum of the
+/// payload appended before it's escaped.
+///
+/// Raw SLIP has no integrity check of its own, so a bit flipped on a noisy
+/// link goes unnoticed. This is `encode_with_checksum` with `Crc16`, and
+/// the checksum is checked and stripped again by `decode_with_crc16`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_with_crc16, encode_with_crc16, SlipError};
+///
+/// let mut encoded = encode_with_crc16(&[0x01, 0xC0]).unwrap();
+/// assert_eq!(encoded, [0xC0, 0x01, 0xDB, 0xDC, 0xF7, 0x72, 0xC0]);
+/// assert_eq!(decode_with_crc16(&encoded).unwrap(), [0x01, 0xC0]);
+///
+/// encoded[1] ^= 0x04;
+/// assert_eq!(decode_with_crc16(&encoded), Err(SlipError::ChecksumMismatch));
+/// ```
+pub fn encode_with_crc16<T: AsRef<[u8]>>(raw_buffer: T) -> Result<Vec<u8>, SlipError> {
+  encode_with_checksum(&Crc16, raw_buffer)
+}
+
+/// Decodes data following the SLIP protocol that was encoded with
+/// `encode_with_crc16`, checking and stripping the checksum.
+///
+/// Returns `SlipError::ChecksumMismatch` if the checksum doesn't match the
+/// payload, or the frame is too short to hold one.
+pub fn decode_with_crc16<T: AsRef<[u8]>>(encoded_buffer: T) -> Result<Vec<u8>, SlipError> {
+  decode_with_checksum(&Crc16, encoded_buffer)
+}
+
+// Returns the CRC-16/CCITT-FALSE of `data`.
+fn crc16(data: &[u8]) -> u16 {
+  let mut crc: u16 = 0xFFFF;
+  for &byte in data {
+    crc ^= u16::from(byte) << 8;
+    for _ in 0..8 {
+      crc = if crc & 0x8000 != 0 {
+        (crc << 1) ^ 0x1021
+      } else {
+        crc << 1
+      };
+    }
+  }
+
+  crc
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn computes_the_standard_check_value() {
+    assert_eq!(crc16(b"123456789"), 0x29B1);
+    assert_eq!(crc16(&[]), 0xFFFF);
+  }
+
+  #[test]
+  fn computes_the_legacy_check_values() {
+    assert_eq!(Xor8.compute(b"123456789"), [0x31]);
+    assert_eq!(Fletcher16.compute(b"abcdef"), [0x20, 0x57]);
+    assert_eq!(Fletcher16.compute(b"abcdefgh"), [0x06, 0x27]);
+    assert_eq!(Fletcher16.compute(&[0xFF; 1024]), [0x00, 0x00]);
+
+    let encoded = encode_with_checksum(&Xor8, [END]).unwrap();
+    assert_eq!(encoded, [END, ESC, ESC_END, ESC, ESC_END, END]);
+    assert_eq!(decode_with_checksum(&Xor8, &encoded), Ok(vec![END]));
+    assert_eq!(
+      decode_with_checksum(&Fletcher16, [END, 0x01, 0x01, 0x00, END]),
+      Err(SlipError::ChecksumMismatch)
+    );
+  }
+
+  #[test]
+  fn works_through_trait_objects() {
+    let checksums: [&dyn FrameChecksum<Output = [u8; 2]>; 1] = [&Crc16];
+    let encoded = encode_with_checksum(checksums[0], [0x01]).unwrap();
+
+    assert_eq!(encoded, encode_with_crc16([0x01]).unwrap());
+    assert_eq!(decode_with_checksum(checksums[0], &encoded), Ok(vec![0x01]));
+  }
+
+  #[test]
+  fn detects_corrupt_frames() {
+    let payload: Vec<u8> = (0..=255).collect();
+    let encoded = encode_with_crc16(&payload).unwrap();
+    assert_eq!(decode_with_crc16(&encoded), Ok(payload));
+
+    for idx in 1..encoded.len() - 1 {
+      let mut corrupt = encoded.clone();
+      corrupt[idx] ^= 0x01;
+      assert!(decode_with_crc16(&corrupt).is_err(), "flipped byte {}", idx);
+    }
+    assert_eq!(
+      decode_with_crc16([END, 0x01, END]),
+      Err(SlipError::ChecksumMismatch)
+    );
+    assert_eq!(decode_with_crc16([0x01]), Err(SlipError::NoEndDelimiter));
+  }
+}
@@ -0,0 +1,320 @@
+use super::*;
+use alloc::collections::vec_deque::Drain;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+// The longest run a single COBS code byte can cover, not counting itself.
+const MAX_RUN: usize = 254;
+
+/// Encodes data using Consistent Overhead Byte Stuffing, as a single frame
+/// ending with a 0x00 delimiter.
+///
+/// Rather than escaping special bytes, COBS replaces each 0x00 byte in the
+/// payload with the distance to the next one, so the overhead is at most
+/// one byte in 254, plus the delimiter, however unlucky the payload is. A
+/// SLIP frame full of `END` and `ESC` bytes, on the other hand, comes out
+/// twice as long.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{cobs_decode, cobs_encode};
+///
+/// let encoded = cobs_encode(&[0x11, 0x22, 0x00, 0x33]);
+///
+/// assert_eq!(encoded, [0x03, 0x11, 0x22, 0x02, 0x33, 0x00]);
+/// assert_eq!(cobs_decode(&encoded).unwrap(), [0x11, 0x22, 0x00, 0x33]);
+/// ```
+pub fn cobs_encode(raw_buffer: &[u8]) -> Vec<u8> {
+  let mut encoded_buffer: Vec<u8> =
+    Vec::with_capacity(raw_buffer.len() + raw_buffer.len() / MAX_RUN + 2);
+  // Appending to a `Vec` never fails.
+  let _ = cobs_encode_to(raw_buffer, &mut encoded_buffer);
+
+  encoded_buffer
+}
+
+/// Decodes the first COBS frame in `encoded_buffer`, up to its 0x00
+/// delimiter.
+///
+/// Returns `SlipError::NoEndDelimiter` if there's no complete frame, or the
+/// errors a `CobsDecoder` returns for a frame that isn't encoded correctly.
+pub fn cobs_decode<T: AsRef<[u8]>>(encoded_buffer: T) -> Result<Vec<u8>, SlipError> {
+  let mut decoder = CobsDecoder::new();
+  for byte in encoded_buffer.as_ref() {
+    if let Some(frame) = decoder.push_byte(*byte) {
+      return frame.map(<[u8]>::to_vec);
+    }
+  }
+
+  Err(SlipError::NoEndDelimiter)
+}
+
+// Writes `raw_buffer` to `sink` as a COBS frame.
+pub(crate) fn cobs_encode_to<S: ByteSink>(raw_buffer: &[u8], sink: &mut S) -> Result<(), S::Error> {
+  let mut blocks = raw_buffer.split(|byte| *byte == 0x00).peekable();
+  while let Some(block) = blocks.next() {
+    for run in block.chunks(MAX_RUN) {
+      sink.write_bytes(&[run.len() as u8 + 1])?;
+      sink.write_bytes(run)?;
+    }
+
+    // A full run isn't followed by a zero when decoded, so a block that
+    // ends with one needs an empty run after it to carry its zero.
+    if block.len() % MAX_RUN == 0 && (block.is_empty() || blocks.peek().is_some()) {
+      sink.write_bytes(&[0x01])?;
+    }
+  }
+
+  sink.write_bytes(&[0x00])
+}
+
+/// The COBS framing, as a `Framer` that encodes frames like `cobs_encode`
+/// and decodes them with a `CobsDecoder`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{Cobs, Framer};
+///
+/// let mut encoded: Vec<u8> = Vec::new();
+/// Cobs.encode_frame(&[0xC0, 0x00], &mut encoded).unwrap();
+///
+/// assert_eq!(encoded, [0x02, 0xC0, 0x01, 0x00]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Cobs;
+
+impl Framer for Cobs {
+  type Decoder = CobsDecoder;
+
+  fn encode_frame<S>(&self, payload: &[u8], sink: &mut S) -> Result<(), S::Error>
+  where
+    S: ByteSink,
+    S::Error: From<SlipError>,
+  {
+    cobs_encode_to(payload, sink)
+  }
+
+  fn max_overhead(&self, payload_len: usize) -> usize {
+    payload_len / MAX_RUN + 2
+  }
+
+  fn decoder(&self) -> CobsDecoder {
+    CobsDecoder::new()
+  }
+}
+
+/// A streaming decoder for COBS frames, each ending with a 0x00 delimiter.
+///
+/// It works just like a `SlipDecoder`, so encoded data can be pushed in
+/// chunks of any size and complete frames taken off as they arrive. There's
+/// no leading delimiter in COBS, so the first byte pushed starts a frame,
+/// and extra delimiters between frames are skipped.
+///
+/// A frame that ends partway through a block, because bytes were lost, is
+/// returned as `SlipError::TruncatedEscape`, and decoding carries on with
+/// the next frame.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::CobsDecoder;
+///
+/// let mut decoder = CobsDecoder::new();
+///
+/// decoder.push(&[0x03, 0x11, 0x22]);
+/// assert!(decoder.next_frame().is_none());
+///
+/// decoder.push(&[0x02, 0x33, 0x00]);
+/// assert_eq!(decoder.next_frame().unwrap().unwrap(), [0x11, 0x22, 0x00, 0x33]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CobsDecoder {
+  frame: Vec<u8>,
+  frames: VecDeque<Result<Vec<u8>, SlipError>>,
+  limit: Option<usize>,
+  started: bool,
+  remaining: u8,
+  zero_pending: bool,
+  complete: bool,
+  error: Option<SlipError>,
+}
+
+impl CobsDecoder {
+  /// Creates a new decoder with no buffered data.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a new decoder that rejects frames longer than `limit` bytes
+  /// once decoded, with `SlipError::FrameTooLarge`.
+  pub fn with_limit(limit: usize) -> Self {
+    Self {
+      limit: Some(limit),
+      ..Self::default()
+    }
+  }
+
+  /// Pushes a chunk of encoded data into the decoder.
+  ///
+  /// Any frames completed by this chunk are queued and can be retrieved
+  /// with `next_frame` or `drain`.
+  pub fn push(&mut self, chunk: &[u8]) {
+    for byte in chunk {
+      if let Some(result) = self.step(*byte) {
+        self.frames.push_back(result.map(|()| self.frame.to_vec()));
+      }
+    }
+  }
+
+  /// Pushes a single byte of encoded data into the decoder, returning the
+  /// frame it completes directly rather than queueing it, like
+  /// `SlipDecoder::push_byte`.
+  pub fn push_byte(&mut self, byte: u8) -> Option<Result<&[u8], SlipError>> {
+    self
+      .step(byte)
+      .map(|result| result.map(|()| self.frame.as_slice()))
+  }
+
+  /// Returns the oldest complete frame, if there is one.
+  pub fn next_frame(&mut self) -> Option<Result<Vec<u8>, SlipError>> {
+    self.frames.pop_front()
+  }
+
+  /// Removes all complete frames from the decoder, oldest first.
+  pub fn drain(&mut self) -> Drain<'_, Result<Vec<u8>, SlipError>> {
+    self.frames.drain(..)
+  }
+
+  /// Returns the number of decoded bytes held for the frame in progress.
+  pub fn partial_len(&self) -> usize {
+    if self.complete {
+      0
+    } else {
+      self.frame.len()
+    }
+  }
+
+  fn step(&mut self, byte: u8) -> Option<Result<(), SlipError>> {
+    if self.complete {
+      self.frame.clear();
+      self.complete = false;
+    }
+
+    if byte == 0x00 {
+      let started = core::mem::replace(&mut self.started, false);
+      let remaining = core::mem::replace(&mut self.remaining, 0);
+      self.zero_pending = false;
+      let error = self.error.take();
+      if !started {
+        return None;
+      }
+
+      self.complete = true;
+      return Some(match error {
+        Some(error) => Err(error),
+        None if remaining > 0 => Err(SlipError::TruncatedEscape),
+        None => Ok(()),
+      });
+    }
+
+    self.started = true;
+    if self.error.is_some() {
+      return None;
+    }
+
+    if self.remaining == 0 {
+      // A code byte, starting a new block. Every block but the last ends
+      // with a zero, unless it was a full block of 254 bytes.
+      if self.zero_pending {
+        self.store(0x00);
+      }
+      self.zero_pending = byte != 0xFF;
+      self.remaining = byte - 1;
+    } else {
+      self.store(byte);
+      self.remaining -= 1;
+    }
+    None
+  }
+
+  fn store(&mut self, byte: u8) {
+    if self.limit.is_some_and(|limit| self.frame.len() >= limit) {
+      self.frame.clear();
+      self.error = Some(SlipError::FrameTooLarge);
+    } else {
+      self.frame.push(byte);
+    }
+  }
+}
+
+impl FrameDecoder for CobsDecoder {
+  fn push_decode(&mut self, byte: u8) -> Option<Result<&[u8], SlipError>> {
+    self.push_byte(byte)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_reference_encodings() {
+    let run: Vec<u8> = (0x01..=0xFF).collect();
+    let cases: [(Vec<u8>, Vec<u8>); 8] = [
+      (vec![], vec![0x01, 0x00]),
+      (vec![0x00], vec![0x01, 0x01, 0x00]),
+      (vec![0x00, 0x00], vec![0x01, 0x01, 0x01, 0x00]),
+      (
+        vec![0x11, 0x00, 0x00, 0x00],
+        vec![0x02, 0x11, 0x01, 0x01, 0x01, 0x00],
+      ),
+      (
+        run[..254].to_vec(),
+        [&[0xFF], &run[..254], &[0x00]].concat(),
+      ),
+      (
+        [&[0x00], &run[..254]].concat(),
+        [&[0x01, 0xFF], &run[..254], &[0x00]].concat(),
+      ),
+      (
+        run.clone(),
+        [&[0xFF], &run[..254], &[0x02, 0xFF, 0x00]].concat(),
+      ),
+      (
+        [&run[1..], &[0x00]].concat(),
+        [&[0xFF], &run[1..], &[0x01, 0x01, 0x00]].concat(),
+      ),
+    ];
+
+    for (raw, encoded) in cases {
+      assert_eq!(cobs_encode(&raw), encoded);
+      assert_eq!(cobs_decode(&encoded), Ok(raw));
+    }
+  }
+
+  #[test]
+  fn recovers_from_bad_frames() {
+    let mut input = cobs_encode(&[0x01, 0x00, 0x02]);
+    input.extend([0x05, 0x01, 0x00, 0x00]);
+    input.extend(cobs_encode(&[0x03; 5]));
+    input.extend(cobs_encode(&[0x04]));
+
+    let mut decoder = CobsDecoder::with_limit(4);
+    for chunk in input.chunks(3) {
+      decoder.push(chunk);
+    }
+
+    assert_eq!(
+      decoder.drain().collect::<Vec<_>>(),
+      [
+        Ok(vec![0x01, 0x00, 0x02]),
+        Err(SlipError::TruncatedEscape),
+        Err(SlipError::FrameTooLarge),
+        Ok(vec![0x04]),
+      ]
+    );
+    assert_eq!(cobs_decode([0x02, 0x01]), Err(SlipError::NoEndDelimiter));
+  }
+}
@@ -0,0 +1,144 @@
+use super::*;
+use bytes::{Buf, BytesMut};
+use std::io;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// A `tokio_util` codec for frames following the SLIP protocol.
+///
+/// Pairing it with `tokio_util::codec::Framed` turns any `AsyncRead` and
+/// `AsyncWrite`, such as a serial port or TCP stream, into a `Stream` of
+/// decoded frames and a `Sink` of payloads to encode. Any other `Framer`,
+/// such as `Cobs` or `SlipConfig::hdlc`, can be given to `with_framer` to
+/// use that framing instead.
+///
+/// # Example:
+///
+/// ```rust
+/// use bytes::BytesMut;
+/// use simple_slip::SlipCodec;
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// let mut codec = SlipCodec::new();
+/// let mut buffer = BytesMut::new();
+///
+/// codec.encode(&[0x01, 0xC0][..], &mut buffer).unwrap();
+/// assert_eq!(&buffer[..], [0xC0, 0x01, 0xDB, 0xDC, 0xC0]);
+///
+/// assert_eq!(codec.decode(&mut buffer).unwrap(), Some(vec![0x01, 0xC0]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlipCodec<F: Framer = SlipConfig> {
+  framer: F,
+  decoder: F::Decoder,
+}
+
+impl SlipCodec {
+  /// Creates a new codec.
+  pub fn new() -> Self {
+    Self::with_framer(SlipConfig::new())
+  }
+}
+
+impl Default for SlipCodec {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<F: Framer> SlipCodec<F> {
+  /// Creates a new codec that encodes with `framer`, and decodes with the
+  /// decoder it creates. A `SlipConfig` with `max_frame_len` set limits
+  /// the frames decoded.
+  pub fn with_framer(framer: F) -> Self {
+    Self {
+      decoder: framer.decoder(),
+      framer,
+    }
+  }
+}
+
+impl<F: Framer> Decoder for SlipCodec<F> {
+  type Item = Vec<u8>;
+  type Error = io::Error;
+
+  fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+    let mut consumed = 0;
+    let mut frame = None;
+    for byte in src.iter() {
+      consumed += 1;
+      if let Some(result) = self.decoder.push_decode(*byte) {
+        frame = Some(result.map(<[u8]>::to_vec));
+        break;
+      }
+    }
+    src.advance(consumed);
+
+    frame.transpose().map_err(io::Error::from)
+  }
+}
+
+impl<T: AsRef<[u8]>, F: Framer> Encoder<T> for SlipCodec<F> {
+  type Error = io::Error;
+
+  fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+    let payload = item.as_ref();
+    dst.reserve(payload.len() + self.framer.max_overhead(payload.len()));
+    self
+      .framer
+      .encode_frame(payload, dst)
+      .map_err(io::Error::from)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_frames_split_across_reads() {
+    let mut codec = SlipCodec::new();
+    let mut buffer = BytesMut::from(&[END, 0x01, ESC][..]);
+
+    assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+    assert!(buffer.is_empty());
+
+    buffer.extend_from_slice(&[ESC_ESC, END, END, 0x02, END]);
+    assert_eq!(codec.decode(&mut buffer).unwrap(), Some(vec![0x01, ESC]));
+    assert_eq!(codec.decode(&mut buffer).unwrap(), Some(vec![0x02]));
+    assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+  }
+
+  #[test]
+  fn encodes_the_same_bytes_as_encode() {
+    let payload: Vec<u8> = vec![0x01, ESC, 0x49, END, 0x15];
+    let mut buffer = BytesMut::new();
+
+    SlipCodec::new().encode(&payload, &mut buffer).unwrap();
+
+    assert_eq!(&buffer[..], encode(&payload).unwrap());
+  }
+
+  #[test]
+  fn round_trips_with_other_framers() {
+    fn round_trip<F: Framer>(mut codec: SlipCodec<F>) {
+      let payloads: [&[u8]; 3] = [&[0x00, END, ESC, 0x7E, 0x7D], &[], &[0x01; 300]];
+      let mut buffer = BytesMut::new();
+      for payload in payloads {
+        codec.encode(payload, &mut buffer).unwrap();
+      }
+
+      for payload in payloads {
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(payload.to_vec()));
+      }
+      assert!(buffer.is_empty());
+    }
+
+    round_trip(SlipCodec::with_framer(
+      SlipConfig::new().empty_frames(EmptyFramePolicy::Yield),
+    ));
+    round_trip(SlipCodec::with_framer(
+      SlipConfig::hdlc().empty_frames(EmptyFramePolicy::Yield),
+    ));
+    round_trip(SlipCodec::with_framer(Cobs));
+  }
+}
@@ -0,0 +1,1001 @@
+use super::*;
+use crate::decoder::{decode_body_with, split_packets_with};
+use crate::encoder::{encode_with, for_each_encoded_run};
+use alloc::vec::Vec;
+
+/// What a decoder does with an empty frame, from two `END` bytes in a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EmptyFramePolicy {
+  /// Silently skip empty frames.
+  #[default]
+  Skip,
+  /// Return empty frames like any other, for peers that send them as
+  /// keepalives.
+  Yield,
+  /// Return empty frames as `SlipError::EmptyFrame`, for protocols where
+  /// they mean the line is corrupt.
+  Error,
+}
+
+/// What a decoder does with an `ESC` byte that isn't followed by `ESC_END`
+/// or `ESC_ESC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InvalidEscapePolicy {
+  /// Reject the frame as `SlipError::InvalidEscapeByte`, or
+  /// `SlipError::TruncatedEscape` if the `ESC` byte ends the frame.
+  #[default]
+  Error,
+  /// Drop the `ESC` byte and keep the byte after it in the frame, as
+  /// RFC 1055 recommends. An `ESC` byte at the end of a frame is dropped.
+  Keep,
+}
+
+/// What a decoder does with a malformed frame, one with an invalid escape
+/// sequence that `InvalidEscapePolicy` doesn't let through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MalformedPolicy {
+  /// Silently drop malformed frames.
+  Skip,
+  /// Return malformed frames as an error.
+  #[default]
+  Error,
+  /// Return the raw, still escaped bytes of malformed frames, so they can
+  /// be logged for diagnosis.
+  KeepRaw,
+}
+
+/// The bytes used to delimit and escape frames, for devices that speak a
+/// SLIP-like protocol with nonstandard values.
+///
+/// `SlipConfig::new` starts from the bytes in RFC 1055, and any of them can
+/// then be replaced. `END`, `ESC`, `ESC_END` and `ESC_ESC` must all differ,
+/// otherwise frames can't be told apart, which `validate` checks.
+///
+/// The config can also leave out the `END` byte that usually starts each
+/// frame. It can encode and decode frames itself, and be given to a
+/// `SlipDecoder` or `SlipEncoder` with `with_config` to stream them.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{SlipConfig, SlipDecoder};
+///
+/// let config = SlipConfig::new().end(0x7E).esc(0x7D).esc_end(0x5E).esc_esc(0x5D);
+///
+/// let encoded = config.encode(&[0x01, 0x7E, 0xC0]).unwrap();
+/// assert_eq!(encoded, [0x7E, 0x01, 0x7D, 0x5E, 0xC0, 0x7E]);
+/// assert_eq!(config.decode(&encoded).unwrap(), [0x01, 0x7E, 0xC0]);
+///
+/// let mut decoder = SlipDecoder::with_config(config);
+/// decoder.push(&encoded);
+/// assert_eq!(decoder.next_frame().unwrap().unwrap(), [0x01, 0x7E, 0xC0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SlipConfig {
+  pub(crate) end: u8,
+  pub(crate) esc: u8,
+  pub(crate) esc_end: u8,
+  pub(crate) esc_esc: u8,
+  pub(crate) xor_escapes: bool,
+  pub(crate) leading_end: bool,
+  pub(crate) flush_end: bool,
+  pub(crate) empty_frames: EmptyFramePolicy,
+  pub(crate) invalid_escapes: InvalidEscapePolicy,
+  pub(crate) malformed_frames: MalformedPolicy,
+  pub(crate) max_frame_len: Option<usize>,
+  pub(crate) mtu: Option<usize>,
+  pub(crate) pad_to: Option<usize>,
+  pub(crate) length_prefix: bool,
+}
+
+impl SlipConfig {
+  /// Creates a config using the standard SLIP bytes.
+  pub const fn new() -> Self {
+    Self {
+      end: END,
+      esc: ESC,
+      esc_end: ESC_END,
+      esc_esc: ESC_ESC,
+      xor_escapes: false,
+      leading_end: true,
+      flush_end: false,
+      empty_frames: EmptyFramePolicy::Skip,
+      invalid_escapes: InvalidEscapePolicy::Error,
+      malformed_frames: MalformedPolicy::Error,
+      max_frame_len: None,
+      mtu: None,
+      pad_to: None,
+      length_prefix: false,
+    }
+  }
+
+  /// Creates a config using the PPP in HDLC-like framing of RFC 1662, which
+  /// many radios and modems that claim to speak SLIP actually use.
+  ///
+  /// Frames are delimited by the flag byte 0x7E, and the flag and the
+  /// escape byte 0x7D are escaped as 0x7D followed by the byte XORed with
+  /// 0x20. When decoding, any byte after 0x7D is XORed with 0x20 in the same
+  /// way, so frames from a peer that also escapes control characters are
+  /// read correctly. Control characters aren't escaped when encoding, as if
+  /// the async control character map had been negotiated down to zero.
+  ///
+  /// This only covers the byte stuffing, not the address, control or FCS
+  /// fields, which are left to the payload.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipConfig;
+  ///
+  /// let config = SlipConfig::hdlc();
+  ///
+  /// let encoded = config.encode(&[0x7E, 0x01, 0x7D]).unwrap();
+  /// assert_eq!(encoded, [0x7E, 0x7D, 0x5E, 0x01, 0x7D, 0x5D, 0x7E]);
+  /// assert_eq!(config.decode(&encoded).unwrap(), [0x7E, 0x01, 0x7D]);
+  ///
+  /// // An escaped control character.
+  /// assert_eq!(config.decode(&[0x7E, 0x7D, 0x31, 0x7E]).unwrap(), [0x11]);
+  /// ```
+  pub const fn hdlc() -> Self {
+    let mut config = Self::new().end(0x7E).esc(0x7D).esc_end(0x5E).esc_esc(0x5D);
+    config.xor_escapes = true;
+    config
+  }
+
+  /// Sets the byte that delimits frames, `END` (0xC0) by default.
+  pub const fn end(mut self, byte: u8) -> Self {
+    self.end = byte;
+    self
+  }
+
+  /// Sets the byte that starts an escape sequence, `ESC` (0xDB) by default.
+  pub const fn esc(mut self, byte: u8) -> Self {
+    self.esc = byte;
+    self
+  }
+
+  /// Sets the byte that follows `ESC` in place of an escaped `END`,
+  /// `ESC_END` (0xDC) by default.
+  pub const fn esc_end(mut self, byte: u8) -> Self {
+    self.esc_end = byte;
+    self
+  }
+
+  /// Sets the byte that follows `ESC` in place of an escaped `ESC`,
+  /// `ESC_ESC` (0xDD) by default.
+  pub const fn esc_esc(mut self, byte: u8) -> Self {
+    self.esc_esc = byte;
+    self
+  }
+
+  /// Sets whether frames start with an `END` byte as well as ending with
+  /// one, which is the default.
+  ///
+  /// RFC 1055 describes the leading `END` as optional, as it only serves to
+  /// flush out any line noise received since the last frame, and some peers
+  /// reject it. Without it, decoding with `decode` or a `SlipDecoder`
+  /// treats the start of the input as the start of a frame, rather than
+  /// discarding everything up to the first `END` byte.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipConfig;
+  ///
+  /// let config = SlipConfig::new().leading_end(false);
+  /// let encoded = config.encode(&[0x01, 0xC0]).unwrap();
+  ///
+  /// assert_eq!(encoded, [0x01, 0xDB, 0xDC, 0xC0]);
+  /// assert_eq!(config.decode(&encoded).unwrap(), [0x01, 0xC0]);
+  /// ```
+  pub const fn leading_end(mut self, enabled: bool) -> Self {
+    self.leading_end = enabled;
+    self
+  }
+
+  /// Sets whether an extra `END` byte is sent before each frame, purely to
+  /// flush out line noise, which is off by default.
+  ///
+  /// On a noisy line, RFC 1055 suggests sending an `END` byte before each
+  /// frame so that any noise received since the last frame is terminated
+  /// as a junk frame of its own, rather than corrupting the next one. This
+  /// byte is kept separate from the frame's own delimiters, so it can be
+  /// turned on whether or not the leading `END` is, and a `SlipEncoder`
+  /// counts it on its own in `EncodeStats::flush_ends`.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipConfig;
+  ///
+  /// let config = SlipConfig::new().leading_end(false).flush_end(true);
+  ///
+  /// assert_eq!(config.encode(&[0x01]).unwrap(), [0xC0, 0x01, 0xC0]);
+  ///
+  /// let config = SlipConfig::new().flush_end(true);
+  ///
+  /// assert_eq!(config.encode(&[0x01]).unwrap(), [0xC0, 0xC0, 0x01, 0xC0]);
+  /// ```
+  pub const fn flush_end(mut self, enabled: bool) -> Self {
+    self.flush_end = enabled;
+    self
+  }
+
+  /// Sets what decoding does with empty frames, which are skipped by
+  /// default.
+  ///
+  /// When frames start with an `END` byte, the one straight after the end
+  /// of a frame is taken to start the next frame, so an empty frame takes
+  /// a pair of `END` bytes of its own, just as `encode` produces for an
+  /// empty payload. This means flush `END` bytes can't be told apart from
+  /// empty frames, so they shouldn't be combined with
+  /// `EmptyFramePolicy::Yield` or `EmptyFramePolicy::Error`.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{EmptyFramePolicy, SlipConfig};
+  ///
+  /// let input = [0xC0, 0x01, 0xC0, 0xC0, 0xC0, 0xC0, 0x02, 0xC0];
+  ///
+  /// let (packets, _) = SlipConfig::new().decode_packets(&input);
+  /// assert_eq!(packets, [Ok(vec![0x01]), Ok(vec![0x02])]);
+  ///
+  /// let config = SlipConfig::new().empty_frames(EmptyFramePolicy::Yield);
+  /// let (packets, _) = config.decode_packets(&input);
+  /// assert_eq!(packets, [Ok(vec![0x01]), Ok(vec![]), Ok(vec![0x02])]);
+  /// ```
+  pub const fn empty_frames(mut self, policy: EmptyFramePolicy) -> Self {
+    self.empty_frames = policy;
+    self
+  }
+
+  /// Sets what decoding does with invalid escape sequences, which are
+  /// rejected as `SlipError::InvalidEscapeByte` by default.
+  ///
+  /// RFC 1055 leaves the byte after an unexpected `ESC` in the frame,
+  /// which `InvalidEscapePolicy::Keep` does too, to interoperate with
+  /// peers that don't escape bytes consistently.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{InvalidEscapePolicy, SlipConfig};
+  ///
+  /// let input = [0xC0, 0x01, 0xDB, 0x02, 0xDB, 0xDC, 0xC0];
+  /// let config = SlipConfig::new().invalid_escapes(InvalidEscapePolicy::Keep);
+  ///
+  /// assert!(SlipConfig::new().decode(&input).is_err());
+  /// assert_eq!(config.decode(&input).unwrap(), [0x01, 0x02, 0xC0]);
+  /// ```
+  pub const fn invalid_escapes(mut self, policy: InvalidEscapePolicy) -> Self {
+    self.invalid_escapes = policy;
+    self
+  }
+
+  /// Sets what decoding does with malformed frames, which are returned as
+  /// an error by default.
+  ///
+  /// With `MalformedPolicy::KeepRaw`, a malformed frame is returned in
+  /// place as its escaped bytes, from after the `END` byte that starts it
+  /// up to the one that ends it. Those can't be told apart from a frame
+  /// that decoded, so it's meant for capturing traffic to diagnose.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{MalformedPolicy, SlipConfig};
+  ///
+  /// let input = [0xC0, 0x01, 0xC0, 0xC0, 0xDB, 0xDC, 0xDB, 0x02, 0xC0];
+  ///
+  /// let config = SlipConfig::new().malformed_frames(MalformedPolicy::Skip);
+  /// assert_eq!(config.decode_packets(&input).0, [Ok(vec![0x01])]);
+  ///
+  /// let config = SlipConfig::new().malformed_frames(MalformedPolicy::KeepRaw);
+  /// assert_eq!(
+  ///   config.decode_packets(&input).0,
+  ///   [Ok(vec![0x01]), Ok(vec![0xDB, 0xDC, 0xDB, 0x02])]
+  /// );
+  /// ```
+  pub const fn malformed_frames(mut self, policy: MalformedPolicy) -> Self {
+    self.malformed_frames = policy;
+    self
+  }
+
+  /// Limits decoded frames to `len` bytes, rejecting longer ones as
+  /// `SlipError::FrameTooLarge`. Frames are unlimited by default.
+  ///
+  /// Without a limit, a peer that never sends an `END` byte can make a
+  /// `SlipDecoder` buffer data until it runs out of memory. `RFC_MTU` is a
+  /// reasonable limit for a peer that follows RFC 1055. A frame that's too
+  /// large is dropped, and decoding picks up again at the next `END` byte.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{SlipConfig, SlipError};
+  ///
+  /// let config = SlipConfig::new().max_frame_len(2);
+  /// let (packets, _) = config.decode_packets(&[0xC0, 0x01, 0x02, 0x03, 0xC0, 0x04, 0xC0]);
+  ///
+  /// assert_eq!(packets, [Err(SlipError::FrameTooLarge), Ok(vec![0x04])]);
+  /// ```
+  pub const fn max_frame_len(mut self, len: usize) -> Self {
+    self.max_frame_len = Some(len);
+    self
+  }
+
+  /// Limits payloads to `len` bytes, rejecting longer ones as
+  /// `SlipError::MtuExceeded` rather than encoding them. Payloads are
+  /// unlimited by default.
+  ///
+  /// A receiver will usually drop a frame larger than it expects without
+  /// saying so, so checking before sending catches oversized payloads
+  /// where they can still be dealt with. `RFC_MTU` is the size RFC 1055
+  /// suggests receivers should accept.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{SlipConfig, SlipError, RFC_MTU};
+  ///
+  /// let config = SlipConfig::new().mtu(RFC_MTU);
+  ///
+  /// assert!(config.encode(&[0x01; RFC_MTU]).is_ok());
+  /// assert_eq!(config.encode(&[0x01; RFC_MTU + 1]), Err(SlipError::MtuExceeded));
+  /// ```
+  pub const fn mtu(mut self, len: usize) -> Self {
+    self.mtu = Some(len);
+    self
+  }
+
+  /// Pads each encoded frame with `END` bytes after it, up to a multiple of
+  /// `block_len` bytes, for transports that send data in fixed-size blocks
+  /// or need it word aligned. Frames aren't padded by default, and a
+  /// `block_len` of 0 or 1 turns padding off again.
+  ///
+  /// The padding sits outside the frame, and decodes as empty frames, which
+  /// are skipped unless `EmptyFramePolicy::Yield` is used.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{decode_packets, SlipConfig};
+  ///
+  /// let config = SlipConfig::new().pad_to(8);
+  /// let encoded = config.encode(&[0x01, 0xC0]).unwrap();
+  ///
+  /// assert_eq!(encoded, [0xC0, 0x01, 0xDB, 0xDC, 0xC0, 0xC0, 0xC0, 0xC0]);
+  /// assert_eq!(decode_packets(&encoded).0, [[0x01, 0xC0]]);
+  /// ```
+  pub const fn pad_to(mut self, block_len: usize) -> Self {
+    self.pad_to = if block_len > 1 { Some(block_len) } else { None };
+    self
+  }
+
+  /// Sets whether each payload starts with its length, as two big-endian
+  /// bytes, which is off by default.
+  ///
+  /// If line noise corrupts an `END` byte into an ordinary one, the two
+  /// frames either side of it run together into one, and if noise adds an
+  /// `END` byte, a frame is cut in two. Both decode without any error in
+  /// plain SLIP, but with the length prefix the decoder checks it against
+  /// the length of the payload, returning `SlipError::LengthMismatch` if
+  /// they differ, and strips it off. This works with `encode` and `decode`
+  /// here, and with `SlipEncoder` and `SlipDecoder` given this config.
+  ///
+  /// Payloads can be at most 65535 bytes long, and longer ones are rejected
+  /// as `SlipError::MtuExceeded`. Empty frames are still handled by the
+  /// `EmptyFramePolicy`, as an empty payload has a prefix of its own.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{SlipConfig, SlipError};
+  ///
+  /// let config = SlipConfig::new().length_prefix(true);
+  ///
+  /// let encoded = config.encode(&[0x01, 0x02]).unwrap();
+  /// assert_eq!(encoded, [0xC0, 0x00, 0x02, 0x01, 0x02, 0xC0]);
+  /// assert_eq!(config.decode(&encoded).unwrap(), [0x01, 0x02]);
+  ///
+  /// // The frame lost a byte on the way.
+  /// let truncated = [0xC0, 0x00, 0x02, 0x01, 0xC0];
+  /// assert_eq!(config.decode(&truncated), Err(SlipError::LengthMismatch));
+  /// ```
+  pub const fn length_prefix(mut self, enabled: bool) -> Self {
+    self.length_prefix = enabled;
+    self
+  }
+
+  /// Checks that `END`, `ESC`, `ESC_END` and `ESC_ESC` are all different
+  /// bytes, returning `SlipError::InvalidConfig` if any two are the same.
+  ///
+  /// Encoding and decoding check this themselves, so a config that would
+  /// produce frames that can't be decoded is never used.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{SlipConfig, SlipError};
+  ///
+  /// assert_eq!(SlipConfig::hdlc().validate(), Ok(()));
+  ///
+  /// let config = SlipConfig::new().end(0xDB);
+  /// assert_eq!(config.validate(), Err(SlipError::InvalidConfig));
+  /// assert_eq!(config.encode(&[0x01]), Err(SlipError::InvalidConfig));
+  /// ```
+  pub const fn validate(&self) -> Result<(), SlipError> {
+    let bytes = [self.end, self.esc, self.esc_end, self.esc_esc];
+    let mut idx = 0;
+    while idx < bytes.len() {
+      let mut other = idx + 1;
+      while other < bytes.len() {
+        if bytes[idx] == bytes[other] {
+          return Err(SlipError::InvalidConfig);
+        }
+        other += 1;
+      }
+      idx += 1;
+    }
+
+    Ok(())
+  }
+
+  /// Encodes `raw_buffer` as a single frame, like `encode`.
+  pub fn encode(&self, raw_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
+    let mut encoded_buffer: Vec<u8> = Vec::new();
+    self.encode_to(raw_buffer, &mut encoded_buffer)?;
+
+    Ok(encoded_buffer)
+  }
+
+  /// Encodes `raw_buffer` as a single frame into any `ByteSink`, like
+  /// `encode_to`.
+  pub fn encode_to<S>(&self, raw_buffer: &[u8], sink: &mut S) -> Result<(), S::Error>
+  where
+    S: ByteSink,
+    S::Error: From<SlipError>,
+  {
+    self.validate()?;
+    self.check_mtu(raw_buffer)?;
+    let prefix = self.length_prefix_for(raw_buffer)?;
+
+    encode_with(self, prefix, raw_buffer, sink)
+  }
+
+  /// Decodes everything after the first `END` byte, like `decode`, or
+  /// everything when the leading `END` is turned off.
+  pub fn decode(&self, encoded_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
+    let mut decoded_buffer: Vec<u8> = Vec::new();
+    self.decode_to(encoded_buffer, &mut decoded_buffer)?;
+
+    Ok(decoded_buffer)
+  }
+
+  /// Decodes everything after the first `END` byte into any `ByteSink`,
+  /// like `decode_to`, or everything when the leading `END` is turned off.
+  pub fn decode_to<S>(&self, encoded_buffer: &[u8], sink: &mut S) -> Result<(), S::Error>
+  where
+    S: ByteSink,
+    S::Error: From<SlipError>,
+  {
+    self.validate()?;
+    let start = self
+      .find_end(encoded_buffer)
+      .ok_or(SlipError::NoEndDelimiter)?;
+    let body = match self.leading_end {
+      true => &encoded_buffer[start + 1..],
+      false => encoded_buffer,
+    };
+
+    sink.reserve(
+      self
+        .max_frame_len
+        .map_or(body.len(), |max| body.len().min(max)),
+    );
+    self.decode_body(body, sink)
+  }
+
+  /// Decodes the complete frames in `encoded_buffer`, like
+  /// `decode_packets_checked`, so a frame that isn't encoded correctly is
+  /// returned as an error in its place.
+  pub fn decode_packets(
+    &self,
+    encoded_buffer: &[u8],
+  ) -> (Vec<Result<Vec<u8>, SlipError>>, Vec<u8>) {
+    let mut packets: Vec<Result<Vec<u8>, SlipError>> = Vec::new();
+
+    let consumed = split_packets_with(self, encoded_buffer, |body| {
+      if body.is_empty() && self.empty_frames == EmptyFramePolicy::Error {
+        return packets.push(Err(SlipError::EmptyFrame));
+      }
+
+      let capacity = self
+        .max_frame_len
+        .map_or(body.len(), |max| body.len().min(max));
+      let mut decoded_buffer: Vec<u8> = Vec::with_capacity(capacity);
+      match self.decode_body(body, &mut decoded_buffer) {
+        Ok(()) => packets.push(Ok(decoded_buffer)),
+        Err(error) if error.is_malformed() => match self.malformed_frames {
+          MalformedPolicy::Skip => {}
+          MalformedPolicy::Error => packets.push(Err(error)),
+          MalformedPolicy::KeepRaw => packets.push(Ok(body.to_vec())),
+        },
+        Err(error) => packets.push(Err(error)),
+      }
+    });
+
+    (packets, encoded_buffer[consumed..].to_vec())
+  }
+
+  // Decodes a frame body into `sink`, checking and stripping its length
+  // prefix if there is one.
+  fn decode_body<S>(&self, body: &[u8], sink: &mut S) -> Result<(), S::Error>
+  where
+    S: ByteSink,
+    S::Error: From<SlipError>,
+  {
+    if !self.length_prefix {
+      return decode_body_with(self, body, sink);
+    }
+
+    let mut sink = PrefixedSink {
+      inner: sink,
+      prefix: [0; 2],
+      len: 0,
+    };
+    let config = Self {
+      max_frame_len: self.max_frame_len.map(|max| max.saturating_add(2)),
+      ..*self
+    };
+    decode_body_with(&config, body, &mut sink)?;
+
+    // Empty frames are left to the `EmptyFramePolicy`.
+    match sink.len.checked_sub(2) {
+      Some(len) if len == usize::from(u16::from_be_bytes(sink.prefix)) => Ok(()),
+      _ if sink.len == 0 => Ok(()),
+      _ => Err(SlipError::LengthMismatch.into()),
+    }
+  }
+
+  // Checks and strips the length prefix from a decoded frame, if there is
+  // one.
+  pub(crate) fn strip_length_prefix(&self, frame: &mut Vec<u8>) -> Result<(), SlipError> {
+    if !self.length_prefix {
+      return Ok(());
+    }
+
+    match frame.split_first_chunk::<2>() {
+      Some((prefix, payload)) if usize::from(u16::from_be_bytes(*prefix)) == payload.len() => {
+        frame.drain(..2);
+        Ok(())
+      }
+      _ => Err(SlipError::LengthMismatch),
+    }
+  }
+
+  // Returns `SlipError::MtuExceeded` if `raw_buffer` is longer than the MTU.
+  pub(crate) fn check_mtu(&self, raw_buffer: &[u8]) -> Result<(), SlipError> {
+    if self.mtu.is_some_and(|mtu| raw_buffer.len() > mtu) {
+      return Err(SlipError::MtuExceeded);
+    }
+
+    Ok(())
+  }
+
+  // Returns the length prefix for `raw_buffer`, or nothing without one.
+  //
+  // Returns `SlipError::MtuExceeded` if the payload is too long for its
+  // length to fit in the prefix.
+  pub(crate) fn length_prefix_for(&self, raw_buffer: &[u8]) -> Result<Option<[u8; 2]>, SlipError> {
+    if !self.length_prefix {
+      return Ok(None);
+    }
+
+    let len = u16::try_from(raw_buffer.len()).map_err(|_| SlipError::MtuExceeded)?;
+    Ok(Some(len.to_be_bytes()))
+  }
+
+  // Returns the exact length of `raw_buffer` once encoded as a frame with
+  // `prefix` in front of it, not counting any padding.
+  pub(crate) fn encoded_len(&self, prefix: Option<[u8; 2]>, raw_buffer: &[u8]) -> usize {
+    let mut escapes = 0;
+    for mut rest in [
+      prefix.as_ref().map_or(&[][..], |prefix| &prefix[..]),
+      raw_buffer,
+    ] {
+      while let Some(idx) = self.find_special(rest) {
+        escapes += 1;
+        rest = &rest[idx + 1..];
+      }
+    }
+
+    let delimiters = 1 + usize::from(self.leading_end) + usize::from(self.flush_end);
+    let prefix_len = prefix.map_or(0, |prefix| prefix.len());
+    raw_buffer
+      .len()
+      .saturating_add(prefix_len + escapes + delimiters)
+  }
+
+  // Returns the index of the first `END` byte in `haystack`.
+  pub(crate) fn find_end(&self, haystack: &[u8]) -> Option<usize> {
+    if self.end == END {
+      scan::find_end(haystack)
+    } else {
+      haystack.iter().position(|byte| *byte == self.end)
+    }
+  }
+
+  // Returns the index of the first `END` or `ESC` byte in `haystack`.
+  pub(crate) fn find_special(&self, haystack: &[u8]) -> Option<usize> {
+    if self.end == END && self.esc == ESC {
+      scan::find_special(haystack)
+    } else {
+      haystack
+        .iter()
+        .position(|byte| *byte == self.end || *byte == self.esc)
+    }
+  }
+
+  // Returns the escape sequence for `byte`, which must be `END` or `ESC`.
+  pub(crate) fn escape(&self, byte: u8) -> [u8; 2] {
+    if byte == self.end {
+      [self.esc, self.esc_end]
+    } else {
+      [self.esc, self.esc_esc]
+    }
+  }
+
+  // Returns the byte escaped by an `ESC` followed by `byte`, if it's valid
+  // or kept by the `InvalidEscapePolicy`.
+  pub(crate) fn unescape(&self, byte: u8) -> Option<u8> {
+    if byte == self.esc_end {
+      Some(self.end)
+    } else if byte == self.esc_esc {
+      Some(self.esc)
+    } else if self.xor_escapes && byte != self.end {
+      Some(byte ^ 0x20)
+    } else if self.invalid_escapes == InvalidEscapePolicy::Keep && byte != self.end {
+      Some(byte)
+    } else {
+      None
+    }
+  }
+}
+
+// Passes decoded bytes on to `inner`, holding back the two at the start
+// that make up the length prefix, and counting them all.
+struct PrefixedSink<'a, S> {
+  inner: &'a mut S,
+  prefix: [u8; 2],
+  len: usize,
+}
+
+impl<S: ByteSink> ByteSink for PrefixedSink<'_, S> {
+  type Error = S::Error;
+
+  fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), S::Error> {
+    let held = self.len.min(2);
+    let take = (2 - held).min(bytes.len());
+    self.prefix[held..held + take].copy_from_slice(&bytes[..take]);
+    self.len += bytes.len();
+
+    self.inner.write_bytes(&bytes[take..])
+  }
+
+  fn reserve(&mut self, additional: usize) {
+    self.inner.reserve(additional);
+  }
+}
+
+impl Default for SlipConfig {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Framer for SlipConfig {
+  type Decoder = SlipDecoder;
+
+  fn encode_frame<S>(&self, payload: &[u8], sink: &mut S) -> Result<(), S::Error>
+  where
+    S: ByteSink,
+    S::Error: From<SlipError>,
+  {
+    self.encode_to(payload, sink)
+  }
+
+  fn max_overhead(&self, payload_len: usize) -> usize {
+    // Every byte could need escaping, and padding adds up to a block less
+    // one.
+    let delimiters = 1 + usize::from(self.leading_end) + usize::from(self.flush_end);
+    let prefix_len = 4 * usize::from(self.length_prefix);
+    payload_len + prefix_len + delimiters + self.pad_to.map_or(0, |block_len| block_len - 1)
+  }
+
+  fn decoder(&self) -> SlipDecoder {
+    SlipDecoder::with_config(*self)
+  }
+
+  fn encode_runs<'a, E>(&self, payload: &'a [u8], emit: E) -> bool
+  where
+    E: FnMut(&'a [u8]),
+  {
+    for_each_encoded_run(self, payload, emit)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn standard_config_matches_encode_and_decode() {
+    let config = SlipConfig::default();
+    let input: Vec<u8> = vec![0x01, ESC, 0x49, END, 0x15];
+
+    let encoded = config.encode(&input).unwrap();
+    assert_eq!(encoded, encode(&input).unwrap());
+    assert_eq!(config.decode(&encoded), decode(&encoded));
+    assert_eq!(
+      config.decode(&[END, 0x01, ESC, 0x02, END]),
+      Err(SlipError::InvalidEscapeByte { found: 0x02 })
+    );
+    assert_eq!(config.decode(&[0x01]), Err(SlipError::NoEndDelimiter));
+  }
+
+  #[test]
+  fn streams_with_custom_bytes() {
+    let config = SlipConfig::new()
+      .end(0x7E)
+      .esc(0x7D)
+      .esc_end(0x5E)
+      .esc_esc(0x5D);
+    let payloads: [&[u8]; 2] = [&[0x7E, END, 0x7D, ESC], &[0x01]];
+
+    let mut encoder = SlipEncoder::with_config(config);
+    let mut decoder = SlipDecoder::with_config(config);
+    for payload in payloads {
+      encoder.push(payload).unwrap();
+    }
+    let mut chunk = [0u8; 3];
+    while !encoder.is_empty() {
+      let written = encoder.pull(&mut chunk);
+      decoder.push(&chunk[..written]);
+    }
+
+    assert_eq!(
+      config.encode(payloads[0]).unwrap(),
+      [0x7E, 0x7D, 0x5E, END, 0x7D, 0x5D, ESC, 0x7E]
+    );
+    assert_eq!(decoder.next_frame(), Some(Ok(payloads[0].to_vec())));
+    assert_eq!(decoder.next_frame(), Some(Ok(payloads[1].to_vec())));
+  }
+
+  #[test]
+  fn hdlc_escapes_with_xor() {
+    let config = SlipConfig::hdlc();
+    let payload: Vec<u8> = (0..=255).collect();
+
+    let encoded = config.encode(&payload).unwrap();
+    assert_eq!(encoded.len(), payload.len() + 4);
+    assert_eq!(config.decode(&encoded), Ok(payload.clone()));
+
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&[0x7E, 0x7D, 0x20, 0x7D, 0x23, 0x7E, 0x01, 0x7D, 0x7E]);
+    assert_eq!(decoder.next_frame(), Some(Ok(vec![0x00, 0x03])));
+    // An escaped flag aborts the frame.
+    assert_eq!(decoder.next_frame(), Some(Err(SlipError::TruncatedEscape)));
+  }
+
+  #[test]
+  fn omits_leading_end() {
+    let config = SlipConfig::new().leading_end(false);
+    let payloads: [&[u8]; 2] = [&[0x01, END], &[ESC]];
+
+    let mut encoder = SlipEncoder::with_config(config);
+    let mut encoded: Vec<u8> = Vec::new();
+    for payload in payloads {
+      encoder.push(payload).unwrap();
+      encoded.extend(config.encode(payload).unwrap());
+    }
+    let mut chunk = [0u8; 16];
+    let written = encoder.pull(&mut chunk);
+
+    assert_eq!(encoded, [0x01, ESC, ESC_END, END, ESC, ESC_ESC, END]);
+    assert_eq!(&chunk[..written], encoded);
+    assert_eq!(config.decode(&encoded[..4]), Ok(vec![0x01, END]));
+
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&encoded);
+    assert_eq!(decoder.next_frame(), Some(Ok(payloads[0].to_vec())));
+    assert_eq!(decoder.next_frame(), Some(Ok(payloads[1].to_vec())));
+
+    // The standard decoder still picks up every frame after the first.
+    let mut decoder = SlipDecoder::new();
+    decoder.push(&encoded);
+    assert_eq!(decoder.next_frame(), Some(Ok(payloads[1].to_vec())));
+  }
+
+  #[test]
+  fn yields_empty_frames() {
+    let input: Vec<u8> = vec![END, END, END, 0x01, END, END, END, END, END, ESC, END, END];
+
+    let config = SlipConfig::new().empty_frames(EmptyFramePolicy::Yield);
+    let expected = vec![
+      Ok(vec![]),
+      Ok(vec![0x01]),
+      Ok(vec![]),
+      Ok(vec![]),
+      Err(SlipError::TruncatedEscape),
+    ];
+    let (packets, remainder) = config.decode_packets(&input);
+    assert_eq!(packets, expected);
+    assert!(remainder.is_empty());
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&input);
+    assert_eq!(decoder.drain().collect::<Vec<_>>(), expected);
+
+    let config = config.leading_end(false);
+    let (packets, remainder) = config.decode_packets(&input[..5]);
+    assert_eq!(
+      packets,
+      [Ok(vec![]), Ok(vec![]), Ok(vec![]), Ok(vec![0x01])]
+    );
+    assert!(remainder.is_empty());
+
+    let (packets, _) = SlipConfig::new().decode_packets(&input);
+    assert_eq!(packets, [Ok(vec![0x01]), Err(SlipError::TruncatedEscape)]);
+
+    let config = SlipConfig::new().empty_frames(EmptyFramePolicy::Error);
+    let expected = vec![
+      Err(SlipError::EmptyFrame),
+      Ok(vec![0x01]),
+      Err(SlipError::EmptyFrame),
+      Err(SlipError::EmptyFrame),
+      Err(SlipError::TruncatedEscape),
+    ];
+    assert_eq!(config.decode_packets(&input).0, expected);
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&input);
+    assert_eq!(decoder.drain().collect::<Vec<_>>(), expected);
+  }
+
+  #[test]
+  fn limits_frame_len() {
+    let config = SlipConfig::new().max_frame_len(3);
+    let input: Vec<u8> = vec![
+      END, 0x01, 0x02, ESC, ESC_END, END, 0x01, 0x02, 0x03, 0x04, END, ESC, ESC_END, END,
+    ];
+    let expected = vec![
+      Ok(vec![0x01, 0x02, END]),
+      Err(SlipError::FrameTooLarge),
+      Ok(vec![END]),
+    ];
+
+    assert_eq!(config.decode(&input[..6]), Ok(vec![0x01, 0x02, END]));
+    assert_eq!(config.decode(&input[5..]), Err(SlipError::FrameTooLarge));
+    assert_eq!(config.decode_packets(&input).0, expected);
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&input);
+    assert_eq!(decoder.drain().collect::<Vec<_>>(), expected);
+  }
+
+  #[test]
+  fn rejects_payloads_over_mtu() {
+    let config = SlipConfig::new().mtu(2);
+    let mut buffer = [0u8; 8];
+    let mut output: &mut [u8] = &mut buffer;
+
+    assert_eq!(
+      config.encode_to(&[0x01, 0x02, 0x03], &mut output),
+      Err(SlipError::MtuExceeded)
+    );
+    assert_eq!(output.len(), 8);
+    assert_eq!(config.encode(&[END, ESC]), encode([END, ESC]));
+  }
+
+  #[test]
+  fn pads_frames_to_block_len() {
+    let payload: Vec<u8> = (0..40u8).map(|idx| idx.wrapping_mul(7)).collect();
+
+    for block_len in [4, 16, 64] {
+      let config = SlipConfig::new().flush_end(true).pad_to(block_len);
+      let encoded = config.encode(&payload).unwrap();
+      let frame_len = config.encoded_len(None, &payload);
+
+      assert_eq!(encoded.len() % block_len, 0);
+      assert!(encoded.len() - frame_len < block_len);
+      assert!(encoded[frame_len..].iter().all(|&byte| byte == END));
+      assert_eq!(decode(&encoded), Ok(payload.clone()));
+    }
+  }
+
+  #[test]
+  fn checks_length_prefix() {
+    let config = SlipConfig::new().length_prefix(true).max_frame_len(192);
+    let payloads: [Vec<u8>; 3] = [vec![END; 192], vec![], vec![ESC, 0x01]];
+
+    let mut encoder = SlipEncoder::with_config(config);
+    let mut encoded: Vec<u8> = Vec::new();
+    for payload in &payloads {
+      encoder.push(payload).unwrap();
+      encoded.extend(config.encode(payload).unwrap());
+    }
+    let mut chunk = vec![0u8; encoded.len()];
+    assert_eq!(encoder.pull(&mut chunk), encoded.len());
+    assert_eq!(chunk, encoded);
+    assert_eq!(encoded[..5], [END, 0x00, ESC, ESC_END, ESC]);
+
+    // Noise turns both `END` bytes between the last two frames into data.
+    let mut merged = encoded.clone();
+    let len = merged.len();
+    merged[len - 8..len - 6].copy_from_slice(&[0x55, 0x55]);
+    let expected = vec![Ok(payloads[0].clone()), Err(SlipError::LengthMismatch)];
+    assert_eq!(config.decode_packets(&merged).0, expected);
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&merged);
+    assert_eq!(decoder.drain().collect::<Vec<_>>(), expected);
+
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&encoded);
+    for payload in &payloads {
+      assert_eq!(decoder.next_frame(), Some(Ok(payload.clone())));
+    }
+    assert_eq!(config.decode(&[END, 0x00, 0x00, END]), Ok(vec![]));
+    assert_eq!(
+      config.encode(&vec![0x01; 0x10000]),
+      Err(SlipError::MtuExceeded)
+    );
+
+    // The streaming encoder refuses the same payloads, rather than sending
+    // a prefix that's wrapped around.
+    let config = SlipConfig::new().length_prefix(true);
+    let mut encoder = SlipEncoder::with_config(config);
+    assert_eq!(
+      encoder.push(&vec![0x01; 0x10000]),
+      Err(SlipError::MtuExceeded)
+    );
+    assert!(encoder.is_empty());
+    encoder.push(&vec![0x01; 0xFFFF]).unwrap();
+    let mut chunk = vec![0u8; 0x10010];
+    let written = encoder.pull(&mut chunk);
+    assert_eq!(config.decode(&chunk[..written]), Ok(vec![0x01; 0xFFFF]));
+  }
+
+  #[test]
+  fn rejects_colliding_bytes() {
+    let config = SlipConfig::new().end(ESC);
+    assert_eq!(config.validate(), Err(SlipError::InvalidConfig));
+    assert_eq!(config.encode(&[0x01]), Err(SlipError::InvalidConfig));
+    assert_eq!(
+      config.decode(&[ESC, 0x01, ESC]),
+      Err(SlipError::InvalidConfig)
+    );
+
+    let result = std::panic::catch_unwind(|| SlipDecoder::with_config(config));
+    assert!(result.is_err());
+  }
+
+  #[test]
+  fn keeps_bytes_after_invalid_escapes() {
+    let config = SlipConfig::new().invalid_escapes(InvalidEscapePolicy::Keep);
+    let input: Vec<u8> = vec![END, 0x01, ESC, 0x02, ESC, ESC, END, ESC, ESC_END, ESC, END];
+    let expected = vec![Ok(vec![0x01, 0x02, ESC]), Ok(vec![END])];
+
+    assert_eq!(config.decode(&input[..7]), Ok(vec![0x01, 0x02, ESC]));
+    assert_eq!(config.decode_packets(&input).0, expected);
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&input);
+    assert_eq!(decoder.drain().collect::<Vec<_>>(), expected);
+  }
+}
@@ -0,0 +1,371 @@
+use super::*;
+use crate::reader::{read_frame_timeout_with, read_frame_with, ReadBuffer};
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+/// A blocking transport that SLIP frames can be carried over.
+///
+/// This is implemented for every type that is both `std::io::Read` and
+/// `std::io::Write`, such as a `std::net::TcpStream` or a serial port, and
+/// can be implemented directly for anything else, like an in-memory pipe.
+pub trait SlipTransport {
+  /// Reads some bytes into `buffer`, returning how many were read. Zero
+  /// means the transport has been closed.
+  fn read_bytes(&mut self, buffer: &mut [u8]) -> io::Result<usize>;
+
+  /// Writes every byte in `buffer`.
+  fn write_bytes(&mut self, buffer: &[u8]) -> io::Result<()>;
+
+  /// Flushes any bytes buffered by the transport.
+  fn flush_bytes(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+impl<T: Read + Write> SlipTransport for T {
+  fn read_bytes(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+    self.read(buffer)
+  }
+
+  fn write_bytes(&mut self, buffer: &[u8]) -> io::Result<()> {
+    self.write_all(buffer)
+  }
+
+  fn flush_bytes(&mut self) -> io::Result<()> {
+    self.flush()
+  }
+}
+
+/// A connection sending and receiving SLIP frames over a `SlipTransport`.
+///
+/// Any other `Framer`, such as `Cobs` or `SlipConfig::hdlc`, can be given
+/// to `with_framer` to both send and receive frames in that framing
+/// instead.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::SlipConnection;
+/// use std::io::Cursor;
+///
+/// let mut connection = SlipConnection::new(Cursor::new(Vec::new()));
+/// connection.send(&[0x01, 0xC0]).unwrap();
+///
+/// connection.get_mut().set_position(0);
+///
+/// assert_eq!(connection.recv().unwrap(), [0x01, 0xC0]);
+/// ```
+#[derive(Debug)]
+pub struct SlipConnection<T, F: Framer = SlipConfig> {
+  transport: T,
+  framer: F,
+  decoder: F::Decoder,
+  buffer: ReadBuffer,
+  keepalive: Option<(Keepalive, Instant)>,
+  dedup: Option<(DedupFilter, Instant)>,
+}
+
+impl<T: SlipTransport> SlipConnection<T> {
+  /// Creates a new connection over `transport`.
+  pub fn new(transport: T) -> Self {
+    Self::with_framer(transport, SlipConfig::new())
+  }
+}
+
+impl<T: SlipTransport, F: Framer> SlipConnection<T, F> {
+  /// Creates a new connection over `transport` that encodes frames with
+  /// `framer`, and decodes them with the decoder it creates.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{SlipConfig, SlipConnection};
+  /// use std::io::Cursor;
+  ///
+  /// let config = SlipConfig::hdlc();
+  /// let mut connection = SlipConnection::with_framer(Cursor::new(Vec::new()), config);
+  /// connection.send(&[0x01, 0x7E]).unwrap();
+  ///
+  /// assert_eq!(connection.get_ref().get_ref(), &[0x7E, 0x01, 0x7D, 0x5E, 0x7E]);
+  ///
+  /// connection.get_mut().set_position(0);
+  ///
+  /// assert_eq!(connection.recv().unwrap(), [0x01, 0x7E]);
+  /// ```
+  pub fn with_framer(transport: T, framer: F) -> Self {
+    Self {
+      transport,
+      decoder: framer.decoder(),
+      framer,
+      buffer: ReadBuffer::new(),
+      keepalive: None,
+      dedup: None,
+    }
+  }
+
+  /// Supervises the link with `keepalive`, counting time in milliseconds
+  /// from now.
+  ///
+  /// Frames sent with `send` count as traffic, and `recv_supervised` sends
+  /// heartbeats, drops the ones received, and reports the link going down.
+  pub fn set_keepalive(&mut self, keepalive: Keepalive) {
+    self.keepalive = Some((keepalive, Instant::now()));
+  }
+
+  /// Returns the `Keepalive` supervising the link, if there is one.
+  pub fn keepalive(&self) -> Option<&Keepalive> {
+    self.keepalive.as_ref().map(|(keepalive, _)| keepalive)
+  }
+
+  /// Drops duplicate frames received with `dedup`, counting time in
+  /// milliseconds from now.
+  ///
+  /// `recv_supervised` picks out heartbeats before checking for
+  /// duplicates, so the keepalive still sees every one.
+  pub fn set_dedup(&mut self, dedup: DedupFilter) {
+    self.dedup = Some((dedup, Instant::now()));
+  }
+
+  /// Returns the `DedupFilter` dropping duplicate frames, if there is one.
+  pub fn dedup(&self) -> Option<&DedupFilter> {
+    self.dedup.as_ref().map(|(dedup, _)| dedup)
+  }
+
+  /// Encodes `payload` as a single frame and sends it over the transport.
+  pub fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+    let mut frame: Vec<u8> =
+      Vec::with_capacity(payload.len() + self.framer.max_overhead(payload.len()));
+    self
+      .framer
+      .encode_frame(payload, &mut frame)
+      .map_err(io::Error::from)?;
+
+    self.transport.write_bytes(&frame)?;
+    self.transport.flush_bytes()?;
+    if let Some((keepalive, start)) = &mut self.keepalive {
+      keepalive.on_sent(millis_since(*start));
+    }
+    Ok(())
+  }
+
+  /// Receives the next complete frame, blocking on the transport as needed.
+  ///
+  /// A frame that isn't encoded correctly is returned as an
+  /// `io::ErrorKind::InvalidData` error, and the transport closing as an
+  /// `io::ErrorKind::UnexpectedEof` error.
+  pub fn recv(&mut self) -> io::Result<Vec<u8>> {
+    loop {
+      let transport = &mut self.transport;
+      let frame = read_frame_with(&mut self.decoder, &mut self.buffer, |buffer| {
+        transport.read_bytes(buffer)
+      })?;
+
+      if !self.is_duplicate(&frame) {
+        return Ok(frame);
+      }
+    }
+  }
+
+  /// Receives the next complete frame, giving up once `timeout` has passed.
+  ///
+  /// This is only available for transports that implement `ReadTimeout`.
+  /// If no complete frame arrives in time, an `io::ErrorKind::TimedOut`
+  /// error is returned, and any part of a frame received so far is kept for
+  /// the next call.
+  pub fn recv_timeout(&mut self, timeout: Duration) -> io::Result<Vec<u8>>
+  where
+    T: ReadTimeout,
+  {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+      let frame = self.recv_any_timeout(deadline.saturating_duration_since(Instant::now()))?;
+      if !self.is_duplicate(&frame) {
+        return Ok(frame);
+      }
+    }
+  }
+
+  /// Receives the next frame that isn't a heartbeat, while supervising the
+  /// link with the `Keepalive` given to `set_keepalive`.
+  ///
+  /// Heartbeats are sent whenever nothing else has been sent for the
+  /// keepalive interval. If nothing at all is received within the keepalive
+  /// timeout, an `io::ErrorKind::NotConnected` error wrapping
+  /// `SlipError::LinkDown` is returned, and the next call carries on
+  /// waiting. Without a keepalive, this is the same as `recv`.
+  pub fn recv_supervised(&mut self) -> io::Result<Vec<u8>>
+  where
+    T: ReadTimeout,
+  {
+    let Some((mut keepalive, start)) = self.keepalive.take() else {
+      return self.recv();
+    };
+
+    let result = self.recv_supervised_with(&mut keepalive, start);
+    self.keepalive = Some((keepalive, start));
+    result
+  }
+
+  fn recv_supervised_with(
+    &mut self,
+    keepalive: &mut Keepalive,
+    start: Instant,
+  ) -> io::Result<Vec<u8>>
+  where
+    T: ReadTimeout,
+  {
+    loop {
+      let now = millis_since(start);
+      if keepalive.poll(now) == Some(LinkEvent::LinkDown) {
+        return Err(SlipError::LinkDown.into());
+      }
+      if let Some(heartbeat) = keepalive.poll_heartbeat(now) {
+        self.send(heartbeat)?;
+      }
+
+      let wait = keepalive.next_deadline().saturating_sub(now).max(1);
+      match self.recv_any_timeout(Duration::from_millis(wait)) {
+        Ok(frame) if keepalive.on_received(&frame, millis_since(start)) => {}
+        Ok(frame) if self.is_duplicate(&frame) => {}
+        Err(error) if error.kind() == io::ErrorKind::TimedOut => {}
+        result => return result,
+      }
+    }
+  }
+
+  // Receives the next frame like `recv_timeout`, duplicates included.
+  fn recv_any_timeout(&mut self, timeout: Duration) -> io::Result<Vec<u8>>
+  where
+    T: ReadTimeout,
+  {
+    read_frame_timeout_with(
+      &mut self.decoder,
+      &mut self.buffer,
+      &mut self.transport,
+      |transport, buffer| transport.read_bytes(buffer),
+      timeout,
+    )
+  }
+
+  // Returns `true` if `frame` should be dropped by the `DedupFilter`.
+  fn is_duplicate(&mut self, frame: &[u8]) -> bool {
+    self
+      .dedup
+      .as_mut()
+      .is_some_and(|(dedup, start)| dedup.is_duplicate(frame, millis_since(*start)))
+  }
+
+  /// Returns a reference to the transport.
+  pub fn get_ref(&self) -> &T {
+    &self.transport
+  }
+
+  /// Returns a mutable reference to the transport.
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.transport
+  }
+
+  /// Consumes the connection, returning the transport.
+  pub fn into_inner(self) -> T {
+    self.transport
+  }
+}
+
+// Returns the milliseconds since `start`, as the time for a `Keepalive`.
+pub(crate) fn millis_since(start: Instant) -> u64 {
+  start.elapsed().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::VecDeque;
+
+  #[derive(Default)]
+  struct Loopback(VecDeque<u8>);
+
+  impl SlipTransport for Loopback {
+    fn read_bytes(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+      let len = buffer.len().min(self.0.len()).min(3);
+      for (slot, byte) in buffer.iter_mut().zip(self.0.drain(..len)) {
+        *slot = byte;
+      }
+      Ok(len)
+    }
+
+    fn write_bytes(&mut self, buffer: &[u8]) -> io::Result<()> {
+      self.0.extend(buffer);
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn sends_and_receives_frames() {
+    let mut connection = SlipConnection::new(Loopback::default());
+
+    connection.send(&[0x01, ESC, 0x02]).unwrap();
+    connection.send(&[END]).unwrap();
+
+    assert_eq!(connection.recv().unwrap(), [0x01, ESC, 0x02]);
+    assert_eq!(connection.recv().unwrap(), [END]);
+    assert_eq!(
+      connection.recv().unwrap_err().kind(),
+      io::ErrorKind::UnexpectedEof
+    );
+  }
+
+  #[test]
+  fn sends_and_receives_with_a_custom_config() {
+    let config = SlipConfig::hdlc().length_prefix(true);
+    let mut connection = SlipConnection::with_framer(Loopback::default(), config);
+
+    connection.send(&[0x01, 0x7E, END]).unwrap();
+    assert_eq!(
+      connection.get_ref().0.iter().copied().collect::<Vec<u8>>(),
+      config.encode(&[0x01, 0x7E, END]).unwrap()
+    );
+    assert_eq!(connection.recv().unwrap(), [0x01, 0x7E, END]);
+
+    let mut connection = SlipConnection::with_framer(Loopback::default(), Cobs);
+    connection.send(&[0x00, 0x01]).unwrap();
+    assert_eq!(connection.recv().unwrap(), [0x00, 0x01]);
+  }
+
+  #[test]
+  fn drops_duplicate_frames() {
+    let mut connection = SlipConnection::new(Loopback::default());
+    connection.set_dedup(DedupFilter::new(4, 60_000));
+
+    for payload in [[0x01], [0x01], [0x02], [0x01]] {
+      connection.send(&payload).unwrap();
+    }
+
+    assert_eq!(connection.recv().unwrap(), [0x01]);
+    assert_eq!(connection.recv().unwrap(), [0x02]);
+    assert_eq!(
+      connection.recv().unwrap_err().kind(),
+      io::ErrorKind::UnexpectedEof
+    );
+    assert_eq!(connection.dedup().unwrap().dropped(), 2);
+  }
+
+  #[test]
+  fn supervises_the_link() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let mut server = SlipConnection::new(listener.accept().unwrap().0);
+    let mut client = SlipConnection::new(client);
+    client.set_keepalive(Keepalive::new(&[0xFF], 20, 100));
+
+    let error = client.recv_supervised().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotConnected);
+    assert!(!client.keepalive().unwrap().is_up());
+    assert_eq!(server.recv().unwrap(), [0xFF]);
+
+    server.send(&[0xFF]).unwrap();
+    server.send(&[0x01]).unwrap();
+    assert_eq!(client.recv_supervised().unwrap(), [0x01]);
+    assert!(client.keepalive().unwrap().is_up());
+  }
+}
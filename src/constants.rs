@@ -2,3 +2,7 @@ pub const END: u8 = 0xC0;
 pub const ESC: u8 = 0xDB;
 pub const ESC_END: u8 = 0xDC;
 pub const ESC_ESC: u8 = 0xDD;
+
+/// The largest packet, in bytes, that RFC 1055 suggests SLIP
+/// implementations should be able to receive.
+pub const RFC_MTU: usize = 1006;
@@ -0,0 +1,116 @@
+use super::*;
+use alloc::vec::Vec;
+use core::fmt;
+use crc::{Algorithm, Crc, CRC_32_ISO_HDLC};
+
+/// Frames payloads with a CRC-32 checksum, for links that need more
+/// integrity than CRC-16 gives.
+///
+/// This is a `FrameChecksum` like `Crc16`, except that the checksum is 4
+/// bytes long, again appended big-endian. It's the standard CRC-32
+/// (CRC-32/ISO-HDLC, as used by Ethernet and zlib) by default, and any
+/// other 32-bit algorithm from the `crc` crate can be selected with
+/// `with_algorithm`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{Crc32, SlipError};
+///
+/// let crc = Crc32::with_algorithm(&crc::CRC_32_ISCSI);
+///
+/// let mut encoded = crc.encode(&[0x01, 0xC0]).unwrap();
+/// assert_eq!(crc.decode(&encoded).unwrap(), [0x01, 0xC0]);
+///
+/// encoded[1] ^= 0x04;
+/// assert_eq!(crc.decode(&encoded), Err(SlipError::ChecksumMismatch));
+/// ```
+#[derive(Clone)]
+pub struct Crc32 {
+  crc: Crc<u32>,
+}
+
+impl Crc32 {
+  /// Creates the standard CRC-32.
+  pub const fn new() -> Self {
+    Self::with_algorithm(&CRC_32_ISO_HDLC)
+  }
+
+  /// Creates a CRC-32 using `algorithm`, such as `crc::CRC_32_ISCSI`.
+  pub const fn with_algorithm(algorithm: &'static Algorithm<u32>) -> Self {
+    Self {
+      crc: Crc::<u32>::new(algorithm),
+    }
+  }
+
+  /// Encodes `raw_buffer` as a single frame with its checksum appended,
+  /// like `encode_with_checksum`.
+  pub fn encode(&self, raw_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
+    encode_with_checksum(self, raw_buffer)
+  }
+
+  /// Decodes a frame encoded by `encode`, checking and stripping its
+  /// checksum.
+  ///
+  /// Returns `SlipError::ChecksumMismatch` if the checksum doesn't match the
+  /// payload, or the frame is too short to hold one.
+  pub fn decode(&self, encoded_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
+    decode_with_checksum(self, encoded_buffer)
+  }
+}
+
+impl FrameChecksum for Crc32 {
+  type Output = [u8; 4];
+
+  fn len(&self) -> usize {
+    4
+  }
+
+  fn compute(&self, payload: &[u8]) -> [u8; 4] {
+    self.crc.checksum(payload).to_be_bytes()
+  }
+}
+
+impl Default for Crc32 {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl fmt::Debug for Crc32 {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Crc32")
+      .field("algorithm", self.crc.algorithm)
+      .finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_with_any_algorithm() {
+    let payload: Vec<u8> = (0..=255).collect();
+
+    for crc in [Crc32::new(), Crc32::with_algorithm(&crc::CRC_32_BZIP2)] {
+      let encoded = crc.encode(&payload).unwrap();
+      assert_eq!(crc.decode(&encoded), Ok(payload.clone()));
+
+      let mut corrupt = encoded.clone();
+      corrupt[100] ^= 0x10;
+      assert_eq!(crc.decode(&corrupt), Err(SlipError::ChecksumMismatch));
+    }
+  }
+
+  #[test]
+  fn appends_the_standard_checksum() {
+    let encoded = Crc32::new().encode(b"123456789").unwrap();
+
+    assert_eq!(encoded[10..14], 0xCBF4_3926u32.to_be_bytes());
+    assert_eq!(
+      Crc32::new().decode(&[END, 0x01, 0x02, 0x03, END]),
+      Err(SlipError::ChecksumMismatch)
+    );
+  }
+}
@@ -0,0 +1,669 @@
+use super::*;
+use alloc::vec::Vec;
+
+// The packet types, held in the top bits of the first byte of a packet.
+const TYPE_UNCOMPRESSED_TCP: u8 = 0x70;
+const TYPE_COMPRESSED_TCP: u8 = 0x80;
+
+// The bits of the change mask at the start of a compressed packet.
+const NEW_C: u8 = 0x40;
+const NEW_I: u8 = 0x20;
+const TCP_PUSH_BIT: u8 = 0x10;
+const NEW_S: u8 = 0x08;
+const NEW_A: u8 = 0x04;
+const NEW_W: u8 = 0x02;
+const NEW_U: u8 = 0x01;
+
+// Combinations of changes that can't happen in practice, so are used to
+// stand for the two most common cases.
+const SPECIAL_I: u8 = NEW_S | NEW_W | NEW_U;
+const SPECIAL_D: u8 = NEW_S | NEW_A | NEW_W | NEW_U;
+const SPECIALS_MASK: u8 = 0x0F;
+
+// The TCP flags.
+const TH_FIN: u8 = 0x01;
+const TH_SYN: u8 = 0x02;
+const TH_RST: u8 = 0x04;
+const TH_PUSH: u8 = 0x08;
+const TH_ACK: u8 = 0x10;
+const TH_URG: u8 = 0x20;
+
+const IPPROTO_TCP: u8 = 6;
+const DEFAULT_SLOTS: usize = 16;
+
+/// Compresses the TCP/IP headers of outgoing packets, as described in RFC
+/// 1144 (Van Jacobson header compression), to be sent as compressed SLIP
+/// (CSLIP).
+///
+/// Each packet returned by `compress` is sent as a frame of its own, and
+/// turned back into the original packet by a `CslipDecompressor` on the
+/// other side. The type of packet is held in the top bits of its first
+/// byte, as CSLIP expects. The 40 bytes of headers of a typical packet in
+/// an established TCP connection shrink to between 3 and 16 bytes, while
+/// packets that can't be compressed, such as UDP or SYN packets, are sent
+/// unchanged.
+///
+/// The compressor keeps the headers of the last packet of up to 16 TCP
+/// connections, which has to match the number of slots of the
+/// decompressor.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{CslipCompressor, CslipDecompressor};
+///
+/// # fn packet(seq: u32, id: u16) -> Vec<u8> {
+/// #   let mut packet = vec![
+/// #     0x45, 0x00, 0x00, 0x2C, 0x00, 0x00, 0x40, 0x00, 0x40, 0x06, 0x00, 0x00, 10, 0, 0, 1, 10, 0,
+/// #     0, 2, 0x04, 0x00, 0x00, 0x50, 0, 0, 0, 0, 0x00, 0x00, 0x00, 0x01, 0x50, 0x18, 0x10, 0x00,
+/// #     0xAB, 0xCD, 0x00, 0x00, b'd', b'a', b't', b'a',
+/// #   ];
+/// #   packet[4..6].copy_from_slice(&id.to_be_bytes());
+/// #   packet[24..28].copy_from_slice(&seq.to_be_bytes());
+/// #   let sum = packet[..20].chunks(2).map(|word| u32::from(u16::from_be_bytes([word[0], word[1]]))).sum::<u32>();
+/// #   let sum = (sum & 0xFFFF) + (sum >> 16);
+/// #   packet[10..12].copy_from_slice(&(!(sum as u16)).to_be_bytes());
+/// #   packet
+/// # }
+/// let mut compressor = CslipCompressor::new();
+/// let mut decompressor = CslipDecompressor::new();
+///
+/// // The first packet of a connection is sent whole, to set up the state.
+/// let first = packet(1000, 1);
+/// let datagram = compressor.compress(&first);
+/// assert_eq!(datagram.len(), first.len());
+/// assert_eq!(decompressor.decompress(&datagram).unwrap(), first);
+///
+/// // The next one needs just 3 bytes of header.
+/// let second = packet(1004, 2);
+/// let datagram = compressor.compress(&second);
+/// assert_eq!(datagram.len(), 3 + 4);
+/// assert_eq!(decompressor.decompress(&datagram).unwrap(), second);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CslipCompressor {
+  // The headers of the last packet of each connection, empty for slots
+  // not used yet.
+  states: Vec<Vec<u8>>,
+  // The slots, most recently used first.
+  recent: Vec<u8>,
+  last_sent: Option<u8>,
+}
+
+impl CslipCompressor {
+  /// Creates a compressor keeping track of up to 16 TCP connections.
+  pub fn new() -> Self {
+    Self::with_slots(DEFAULT_SLOTS)
+  }
+
+  /// Creates a compressor keeping track of up to `slots` TCP connections.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `slots` is 0 or more than 256.
+  pub fn with_slots(slots: usize) -> Self {
+    assert!(
+      (1..=256).contains(&slots),
+      "slots must be between 1 and 256"
+    );
+
+    Self {
+      states: alloc::vec![Vec::new(); slots],
+      recent: (0..slots).map(|slot| slot as u8).collect(),
+      last_sent: None,
+    }
+  }
+
+  /// Compresses the headers of `packet`, a whole IPv4 datagram, returning
+  /// what to send in its place.
+  pub fn compress(&mut self, packet: &[u8]) -> Vec<u8> {
+    let Some((ip_len, hlen)) = tcp_header_lens(packet) else {
+      return packet.to_vec();
+    };
+    if packet[ip_len + 13] & (TH_SYN | TH_FIN | TH_RST | TH_ACK) != TH_ACK {
+      return packet.to_vec();
+    }
+
+    let (slot, known) = self.slot_for(packet, ip_len);
+    let compressed = known
+      .then(|| self.compress_with(packet, slot, ip_len, hlen))
+      .flatten();
+    self.states[usize::from(slot)] = packet[..hlen].to_vec();
+
+    compressed.unwrap_or_else(|| {
+      self.last_sent = Some(slot);
+
+      let mut uncompressed = packet.to_vec();
+      uncompressed[0] |= TYPE_UNCOMPRESSED_TCP;
+      uncompressed[9] = slot;
+      uncompressed
+    })
+  }
+
+  // Returns the slot of the connection `packet` belongs to, or the least
+  // recently used slot if it's a new connection, along with whether it was
+  // found, and makes it the most recently used.
+  fn slot_for(&mut self, packet: &[u8], ip_len: usize) -> (u8, bool) {
+    let found = self.recent.iter().position(|&slot| {
+      let state = &self.states[usize::from(slot)];
+      !state.is_empty() && same_connection(state, packet, ip_len)
+    });
+
+    let slot = self.recent.remove(found.unwrap_or(self.recent.len() - 1));
+    self.recent.insert(0, slot);
+    (slot, found.is_some())
+  }
+
+  // Compresses `packet` against the last packet in `slot`, or returns
+  // `None` if it has to be sent uncompressed.
+  fn compress_with(
+    &mut self,
+    packet: &[u8],
+    slot: u8,
+    ip_len: usize,
+    hlen: usize,
+  ) -> Option<Vec<u8>> {
+    let old = &self.states[usize::from(slot)];
+    // Only the fields expected to change from one packet to the next can
+    // be left out, so anything else changing needs the whole header.
+    if old.len() != hlen
+      || packet[0..2] != old[0..2]
+      || packet[6..10] != old[6..10]
+      || packet[20..ip_len] != old[20..ip_len]
+      || packet[ip_len + 12] != old[ip_len + 12]
+      || packet[ip_len + 20..hlen] != old[ip_len + 20..hlen]
+    {
+      return None;
+    }
+
+    let (th, oth) = (&packet[ip_len..], &old[ip_len..]);
+    let flags = th[13];
+    let mut changes = 0;
+    let mut deltas = Vec::new();
+
+    if flags & TH_URG != 0 {
+      encode_delta_z(&mut deltas, read_u16(th, 18));
+      changes |= NEW_U;
+    } else if th[18..20] != oth[18..20] {
+      return None;
+    }
+    let window = read_u16(th, 14).wrapping_sub(read_u16(oth, 14));
+    if window != 0 {
+      encode_delta(&mut deltas, window);
+      changes |= NEW_W;
+    }
+    let ack = read_u32(th, 8).wrapping_sub(read_u32(oth, 8));
+    if ack != 0 {
+      encode_delta(&mut deltas, u16::try_from(ack).ok()?);
+      changes |= NEW_A;
+    }
+    let seq = read_u32(th, 4).wrapping_sub(read_u32(oth, 4));
+    if seq != 0 {
+      encode_delta(&mut deltas, u16::try_from(seq).ok()?);
+      changes |= NEW_S;
+    }
+
+    let old_data_len = u32::from(read_u16(old, 2)).wrapping_sub(hlen as u32);
+    match changes {
+      // Nothing changed, so this is most likely a retransmission, which is
+      // sent whole in case the last one was lost. The exception is data
+      // following a bare ack.
+      0 if read_u16(packet, 2) == read_u16(old, 2) || old_data_len != 0 => return None,
+      SPECIAL_I | SPECIAL_D => return None,
+      // Echoed interactive traffic.
+      c if c == NEW_S | NEW_A && seq == ack && seq == old_data_len => {
+        changes = SPECIAL_I;
+        deltas.clear();
+      }
+      // A bulk data transfer.
+      NEW_S if seq == old_data_len => {
+        changes = SPECIAL_D;
+        deltas.clear();
+      }
+      _ => {}
+    }
+
+    let id = read_u16(packet, 4).wrapping_sub(read_u16(old, 4));
+    if id != 1 {
+      encode_delta_z(&mut deltas, id);
+      changes |= NEW_I;
+    }
+    if flags & TH_PUSH != 0 {
+      changes |= TCP_PUSH_BIT;
+    }
+
+    let mut compressed = Vec::with_capacity(4 + deltas.len() + packet.len() - hlen);
+    if self.last_sent == Some(slot) {
+      compressed.push(TYPE_COMPRESSED_TCP | changes);
+    } else {
+      self.last_sent = Some(slot);
+      compressed.extend_from_slice(&[TYPE_COMPRESSED_TCP | changes | NEW_C, slot]);
+    }
+    compressed.extend_from_slice(&th[16..18]);
+    compressed.extend_from_slice(&deltas);
+    compressed.extend_from_slice(&packet[hlen..]);
+
+    Some(compressed)
+  }
+}
+
+impl Default for CslipCompressor {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Restores the TCP/IP headers of packets compressed by a
+/// `CslipCompressor`, as described in RFC 1144.
+///
+/// Each decoded frame is given to `decompress`. As compressed packets only
+/// hold what changed since the last packet, a lost or corrupted frame
+/// throws the state out, so it should be reported with `toss`, and
+/// compressed packets are then refused until the compressor sends the
+/// connection id again.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{CslipDecompressor, SlipError};
+///
+/// let mut decompressor = CslipDecompressor::new();
+///
+/// // Packets that aren't compressed pass through unchanged.
+/// let udp = [0x45, 0x00, 0x00, 0x14, 0, 0, 0, 0, 0x40, 0x11, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2];
+/// assert_eq!(decompressor.decompress(&udp).unwrap(), udp);
+///
+/// // There's no state to decompress this against.
+/// assert_eq!(decompressor.decompress(&[0xC0, 0x00, 0x12, 0x34]), Err(SlipError::InvalidHeader));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CslipDecompressor {
+  // The headers of the last packet of each connection, empty for slots
+  // not used yet.
+  states: Vec<Vec<u8>>,
+  last_received: Option<u8>,
+  tossing: bool,
+}
+
+impl CslipDecompressor {
+  /// Creates a decompressor keeping track of up to 16 TCP connections.
+  pub fn new() -> Self {
+    Self::with_slots(DEFAULT_SLOTS)
+  }
+
+  /// Creates a decompressor keeping track of up to `slots` TCP connections.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `slots` is 0 or more than 256.
+  pub fn with_slots(slots: usize) -> Self {
+    assert!(
+      (1..=256).contains(&slots),
+      "slots must be between 1 and 256"
+    );
+
+    Self {
+      states: alloc::vec![Vec::new(); slots],
+      last_received: None,
+      tossing: false,
+    }
+  }
+
+  /// Restores the packet sent as `datagram`, a decoded frame.
+  ///
+  /// Returns `SlipError::InvalidHeader` if it's cut short, refers to an
+  /// unknown connection, or is compressed and arrived after a `toss`
+  /// before the connection id was sent again, after which compressed
+  /// packets are refused in the same way.
+  pub fn decompress(&mut self, datagram: &[u8]) -> Result<Vec<u8>, SlipError> {
+    let packet = match datagram.first() {
+      Some(first) if first & TYPE_COMPRESSED_TCP != 0 => self.decompress_compressed(datagram),
+      Some(&first) if first >= TYPE_UNCOMPRESSED_TCP => self.decompress_uncompressed(datagram),
+      Some(_) => return Ok(datagram.to_vec()),
+      None => None,
+    };
+
+    packet.ok_or_else(|| {
+      self.toss();
+      SlipError::InvalidHeader
+    })
+  }
+
+  /// Reports that a frame was lost or corrupted, so compressed packets are
+  /// refused until the compressor sends a connection id again.
+  pub fn toss(&mut self) {
+    self.tossing = true;
+  }
+
+  fn decompress_uncompressed(&mut self, datagram: &[u8]) -> Option<Vec<u8>> {
+    let mut packet = datagram.to_vec();
+    packet[0] &= 0x4F;
+    let slot = *packet.get(9)?;
+    packet[9] = IPPROTO_TCP;
+    let (_, hlen) = tcp_header_lens(&packet)?;
+
+    *self.states.get_mut(usize::from(slot))? = packet[..hlen].to_vec();
+    self.last_received = Some(slot);
+    self.tossing = false;
+
+    Some(packet)
+  }
+
+  fn decompress_compressed(&mut self, datagram: &[u8]) -> Option<Vec<u8>> {
+    let changes = datagram[0];
+    let mut cursor = 1;
+    if changes & NEW_C != 0 {
+      let slot = *datagram.get(cursor)?;
+      self
+        .states
+        .get(usize::from(slot))
+        .filter(|state| !state.is_empty())?;
+      self.last_received = Some(slot);
+      self.tossing = false;
+      cursor += 1;
+    } else if self.tossing {
+      return None;
+    }
+
+    let slot = usize::from(self.last_received?);
+    let mut header = self.states[slot].clone();
+    let hlen = header.len();
+    let ip_len = usize::from(header[0] & 0x0F) * 4;
+    let old_data_len = u32::from(read_u16(&header, 2)).wrapping_sub(hlen as u32);
+
+    let th = &mut header[ip_len..];
+    th[16..18].copy_from_slice(datagram.get(cursor..cursor + 2)?);
+    cursor += 2;
+    if changes & TCP_PUSH_BIT != 0 {
+      th[13] |= TH_PUSH;
+    } else {
+      th[13] &= !TH_PUSH;
+    }
+
+    match changes & SPECIALS_MASK {
+      SPECIAL_I => {
+        add_u32(th, 8, old_data_len);
+        add_u32(th, 4, old_data_len);
+      }
+      SPECIAL_D => add_u32(th, 4, old_data_len),
+      _ => {
+        if changes & NEW_U != 0 {
+          th[13] |= TH_URG;
+          let urgent = decode_delta(datagram, &mut cursor)?;
+          th[18..20].copy_from_slice(&urgent.to_be_bytes());
+        } else {
+          th[13] &= !TH_URG;
+        }
+        if changes & NEW_W != 0 {
+          let window = read_u16(th, 14).wrapping_add(decode_delta(datagram, &mut cursor)?);
+          th[14..16].copy_from_slice(&window.to_be_bytes());
+        }
+        if changes & NEW_A != 0 {
+          add_u32(th, 8, u32::from(decode_delta(datagram, &mut cursor)?));
+        }
+        if changes & NEW_S != 0 {
+          add_u32(th, 4, u32::from(decode_delta(datagram, &mut cursor)?));
+        }
+      }
+    }
+
+    let id_delta = match changes & NEW_I {
+      0 => 1,
+      _ => decode_delta(datagram, &mut cursor)?,
+    };
+    let id = read_u16(&header, 4).wrapping_add(id_delta);
+    header[4..6].copy_from_slice(&id.to_be_bytes());
+
+    let data = datagram.get(cursor..)?;
+    let total_len = u16::try_from(hlen + data.len()).ok()?;
+    header[2..4].copy_from_slice(&total_len.to_be_bytes());
+    header[10..12].copy_from_slice(&[0, 0]);
+    let checksum = ip_checksum(&header[..ip_len]);
+    header[10..12].copy_from_slice(&checksum.to_be_bytes());
+    self.states[slot] = header.clone();
+
+    header.extend_from_slice(data);
+    Some(header)
+  }
+}
+
+impl Default for CslipDecompressor {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// Returns the lengths of the IP header, and of the IP and TCP headers
+// together, if `packet` is an unfragmented IPv4 TCP packet that holds both.
+fn tcp_header_lens(packet: &[u8]) -> Option<(usize, usize)> {
+  if packet.len() < 40 || packet[0] >> 4 != 4 || packet[9] != IPPROTO_TCP {
+    return None;
+  }
+  if read_u16(packet, 6) & 0x3FFF != 0 {
+    return None;
+  }
+
+  let ip_len = usize::from(packet[0] & 0x0F) * 4;
+  let tcp_len = usize::from(*packet.get(ip_len + 12)? >> 4) * 4;
+  let hlen = ip_len + tcp_len;
+  (ip_len >= 20 && tcp_len >= 20 && hlen <= packet.len()).then_some((ip_len, hlen))
+}
+
+// Returns `true` if `packet` has the same addresses and ports as the
+// headers in `state`.
+fn same_connection(state: &[u8], packet: &[u8], ip_len: usize) -> bool {
+  let state_ip_len = usize::from(state[0] & 0x0F) * 4;
+
+  state[12..20] == packet[12..20]
+    && state[state_ip_len..state_ip_len + 4] == packet[ip_len..ip_len + 4]
+}
+
+// Appends a change that can't be 0, in one byte if it's small enough.
+fn encode_delta(deltas: &mut Vec<u8>, delta: u16) {
+  match u8::try_from(delta) {
+    Ok(delta) => deltas.push(delta),
+    Err(_) => encode_delta_z(deltas, delta),
+  }
+}
+
+// Appends a change that can be 0, which always takes three bytes.
+fn encode_delta_z(deltas: &mut Vec<u8>, delta: u16) {
+  match u8::try_from(delta) {
+    Ok(delta) if delta != 0 => deltas.push(delta),
+    _ => {
+      deltas.push(0);
+      deltas.extend_from_slice(&delta.to_be_bytes());
+    }
+  }
+}
+
+// Reads a change appended by `encode_delta` or `encode_delta_z`.
+fn decode_delta(datagram: &[u8], cursor: &mut usize) -> Option<u16> {
+  match *datagram.get(*cursor)? {
+    0 => {
+      let delta = datagram.get(*cursor + 1..*cursor + 3)?;
+      *cursor += 3;
+      Some(u16::from_be_bytes([delta[0], delta[1]]))
+    }
+    delta => {
+      *cursor += 1;
+      Some(u16::from(delta))
+    }
+  }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+  u16::from_be_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+  u32::from_be_bytes([
+    bytes[offset],
+    bytes[offset + 1],
+    bytes[offset + 2],
+    bytes[offset + 3],
+  ])
+}
+
+fn add_u32(bytes: &mut [u8], offset: usize, delta: u32) {
+  let value = read_u32(bytes, offset).wrapping_add(delta);
+  bytes[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+}
+
+// Returns the IPv4 header checksum of `header`, with its checksum field
+// zeroed.
+fn ip_checksum(header: &[u8]) -> u16 {
+  let mut sum: u32 = header
+    .chunks(2)
+    .map(|word| u32::from(read_u16(word, 0)))
+    .sum();
+  while sum > 0xFFFF {
+    sum = (sum & 0xFFFF) + (sum >> 16);
+  }
+
+  !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Builds a TCP/IP packet from port 1024 to port 80.
+  fn packet(id: u16, seq: u32, ack: u32, flags: u8, window: u16, data: &[u8]) -> Vec<u8> {
+    let mut packet = vec![
+      0x45,
+      0x00,
+      0x00,
+      0x00,
+      0x00,
+      0x00,
+      0x40,
+      0x00,
+      0x40,
+      IPPROTO_TCP,
+      0x00,
+      0x00,
+      10,
+      0,
+      0,
+      1,
+      10,
+      0,
+      0,
+      2,
+      0x04,
+      0x00,
+      0x00,
+      0x50,
+      0,
+      0,
+      0,
+      0,
+      0,
+      0,
+      0,
+      0,
+      0x50,
+      flags,
+      0,
+      0,
+      0xBE,
+      0xEF,
+      0x00,
+      0x00,
+    ];
+    packet.extend_from_slice(data);
+    let total_len = packet.len() as u16;
+    packet[2..4].copy_from_slice(&total_len.to_be_bytes());
+    packet[4..6].copy_from_slice(&id.to_be_bytes());
+    packet[24..28].copy_from_slice(&seq.to_be_bytes());
+    packet[28..32].copy_from_slice(&ack.to_be_bytes());
+    packet[34..36].copy_from_slice(&window.to_be_bytes());
+    let checksum = ip_checksum(&packet[..20]);
+    packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+    packet
+  }
+
+  // Changes the source port of `packet`.
+  fn other_connection(mut packet: Vec<u8>) -> Vec<u8> {
+    packet[20] = 0x08;
+    packet
+  }
+
+  #[test]
+  fn round_trips_a_tcp_conversation() {
+    let ack = TH_ACK;
+    let push = TH_ACK | TH_PUSH;
+    let packets = [
+      packet(1, 100, 500, TH_SYN, 1000, &[]),
+      packet(2, 101, 500, ack, 1000, &[]),
+      packet(3, 101, 500, push, 1000, b"hello"),
+      // A bulk transfer.
+      packet(4, 106, 500, ack, 1000, &[0x42; 300]),
+      packet(5, 406, 500, ack, 1000, &[END, ESC]),
+      // Echoed interactive traffic.
+      packet(6, 408, 502, push, 1000, &[0x01, 0x02]),
+      // A retransmission.
+      packet(6, 408, 502, push, 1000, &[0x01, 0x02]),
+      // Changes that can't be told from the special cases.
+      packet(7, 500, 600, ack | TH_URG, 999, b"x"),
+      // Large changes.
+      packet(300, 2000, 1000, ack | TH_URG, 999, b"x"),
+      packet(301, 2001, 1000, ack, 2000, &[]),
+      packet(302, 2001, 1000, ack, 2000, b"y"),
+      // Another connection, after which the first needs its id again.
+      other_connection(packet(1, 0, 0, ack, 1000, &[])),
+      packet(303, 2002, 1000, ack, 2000, b"z"),
+      // A jump too big to compress.
+      packet(304, 200_000, 1000, ack, 2000, &[]),
+      packet(305, 200_000, 1000, TH_FIN | TH_ACK, 2000, &[]),
+    ];
+
+    let mut compressor = CslipCompressor::with_slots(3);
+    let mut decompressor = CslipDecompressor::with_slots(3);
+    let mut lens = Vec::new();
+    for packet in &packets {
+      let datagram = compressor.compress(packet);
+      let frame = decode(encode(&datagram).unwrap()).unwrap();
+      assert_eq!(decompressor.decompress(&frame).as_ref(), Ok(packet));
+      lens.push(datagram.len() + 40 - packet.len());
+    }
+
+    assert_eq!(lens, [40, 40, 3, 3, 3, 3, 40, 40, 15, 7, 3, 40, 4, 40, 40]);
+  }
+
+  #[test]
+  fn refuses_compressed_packets_after_a_toss() {
+    let mut compressor = CslipCompressor::new();
+    let mut decompressor = CslipDecompressor::new();
+
+    let first = packet(1, 0, 0, TH_ACK, 1000, b"a");
+    let second = packet(2, 1, 0, TH_ACK, 1000, b"b");
+    let uncompressed = compressor.compress(&first);
+    decompressor.decompress(&uncompressed).unwrap();
+    let compressed = compressor.compress(&second);
+
+    decompressor.toss();
+    assert_eq!(
+      decompressor.decompress(&compressed),
+      Err(SlipError::InvalidHeader)
+    );
+
+    let mut with_slot = vec![compressed[0] | NEW_C, uncompressed[9]];
+    with_slot.extend_from_slice(&compressed[1..]);
+    assert_eq!(decompressor.decompress(&with_slot), Ok(second));
+
+    // Damaged packets are refused without panicking.
+    for len in 0..uncompressed.len() {
+      let _ = decompressor.decompress(&with_slot[..len.min(with_slot.len())]);
+      let _ = decompressor.decompress(&uncompressed[..len]);
+      let mut corrupt = with_slot.clone();
+      corrupt[len % with_slot.len()] ^= 0xFF;
+      let _ = decompressor.decompress(&corrupt);
+    }
+    assert_eq!(
+      decompressor.decompress(&[TYPE_COMPRESSED_TCP | NEW_C, 16, 0, 0]),
+      Err(SlipError::InvalidHeader)
+    );
+  }
+}
@@ -1,10 +1,12 @@
 use super::*;
+use alloc::vec::Vec;
 
 /// Decodes data following the SLIP protocol.
 ///
 /// Given a buffer of encoded data, this data will be decoded following
 /// the SLIP protocol, allocated into a new `Vec<u8>`, and returned
-/// to the calling scope.
+/// to the calling scope. Anything that can be viewed as a byte slice can be
+/// passed in, such as a `Vec<u8>`, an array, or a `bytes::Bytes`.
 ///
 /// # Example:
 ///
@@ -18,40 +20,380 @@ use super::*;
 ///
 /// assert_eq!(result, expected);
 /// ```
-pub fn decode(encoded_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
-  let trim_start = find_delimiter(encoded_buffer)?;
-  let trim_end = find_last_delimiter(encoded_buffer)?;
+pub fn decode<T: AsRef<[u8]>>(encoded_buffer: T) -> Result<Vec<u8>, SlipError> {
+  let mut decoded_buffer: Vec<u8> = Vec::new();
 
-  let mut read_idx = trim_start;
-  let mut write_idx = 0;
+  decode_to(encoded_buffer.as_ref(), &mut decoded_buffer)?;
+
+  Ok(decoded_buffer)
+}
 
-  let decoded_buffer_size = calc_decode_buffer_size(&encoded_buffer[trim_start..trim_end]);
+/// Decodes data following the SLIP protocol, reporting where decoding
+/// failed.
+///
+/// This works just like `decode`, except that an error comes with the
+/// offset into `encoded_buffer` of the byte that couldn't be decoded, and
+/// that byte, so the corruption can be found in a large buffer. For an
+/// invalid escape sequence, that's the byte after the `ESC` (0xDB) byte, or
+/// the `ESC` byte itself when nothing follows it.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_detailed, SlipError};
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0x02, 0xDB, 0x03, 0xC0];
+/// let error = decode_detailed(&input).unwrap_err();
+///
+/// assert_eq!(error.error, SlipError::InvalidEscapeByte { found: 0x03 });
+/// assert_eq!(error.offset, 4);
+/// assert_eq!(error.byte, Some(0x03));
+/// assert_eq!(
+///   error.to_string(),
+///   "invalid byte following an 'ESC' (0xDB) byte at offset 4 (byte 0x03)"
+/// );
+/// ```
+pub fn decode_detailed<T: AsRef<[u8]>>(encoded_buffer: T) -> Result<Vec<u8>, DecodeError> {
+  let encoded_buffer = encoded_buffer.as_ref();
 
-  let mut decoded_buffer: Vec<u8> = vec![0; decoded_buffer_size];
+  decode(encoded_buffer).map_err(|error| {
+    let offset = match error {
+      SlipError::TruncatedEscape | SlipError::InvalidEscapeByte { .. } => {
+        find_invalid_escape(encoded_buffer)
+      }
+      _ => None,
+    }
+    .unwrap_or(encoded_buffer.len());
 
-  while read_idx < encoded_buffer.len() {
-    let byte = encoded_buffer[read_idx];
+    DecodeError {
+      error,
+      offset,
+      byte: encoded_buffer.get(offset).copied(),
+    }
+  })
+}
+
+/// Decodes as much of the data following the SLIP protocol as it can,
+/// without ever failing.
+///
+/// This works like `decode`, the way `String::from_utf8_lossy` works like
+/// `String::from_utf8`. An invalid escape sequence is dropped from the
+/// output, rather than being replaced with anything, and input with no
+/// `END` (0xC0) byte at all is decoded from the start. It suits log viewers
+/// and the like, where a best-effort display beats an error.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::decode_lossy;
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0xDB, 0x02, 0x03, 0xDB, 0xDC, 0xC0];
+///
+/// assert_eq!(decode_lossy(&input), [0x01, 0x03, 0xC0]);
+/// assert_eq!(decode_lossy([0x01, 0xDB]), [0x01]);
+/// ```
+pub fn decode_lossy<T: AsRef<[u8]>>(encoded_buffer: T) -> Vec<u8> {
+  let encoded_buffer = encoded_buffer.as_ref();
+  let body = match scan::find_end(encoded_buffer) {
+    Some(idx) => &encoded_buffer[idx + 1..],
+    None => encoded_buffer,
+  };
+
+  let mut decoded_buffer: Vec<u8> = Vec::with_capacity(decoded_len_bound(body));
+  let mut rest = body;
+  while let Some(idx) = scan::find_special(rest) {
+    decoded_buffer.extend_from_slice(&rest[..idx]);
+    if rest[idx] == END {
+      rest = &rest[idx + 1..];
+      continue;
+    }
+
+    match rest.get(idx + 1) {
+      Some(&ESC_END) => decoded_buffer.push(END),
+      Some(&ESC_ESC) => decoded_buffer.push(ESC),
+      _ => {}
+    }
+    rest = rest.get(idx + 2..).unwrap_or_default();
+  }
+  decoded_buffer.extend_from_slice(rest);
+
+  decoded_buffer
+}
+
+// Returns the offset of the byte that makes the first escape sequence
+// `decode` sees in `encoded_buffer` invalid, or of the `ESC` byte if
+// nothing follows it.
+fn find_invalid_escape(encoded_buffer: &[u8]) -> Option<usize> {
+  let mut idx = scan::find_end(encoded_buffer)? + 1;
+  while let Some(found) = encoded_buffer[idx..].iter().position(|&byte| byte == ESC) {
+    let esc_idx = idx + found;
+    match encoded_buffer.get(esc_idx + 1) {
+      Some(&ESC_END) | Some(&ESC_ESC) => idx = esc_idx + 2,
+      Some(_) => return Some(esc_idx + 1),
+      None => return Some(esc_idx),
+    }
+  }
+
+  None
+}
+
+/// Decodes data following the SLIP protocol from an iterator of bytes.
+///
+/// This works just like `decode`, except that the bytes are decoded one at
+/// a time as the iterator yields them, so encoded data read from a source
+/// that produces bytes on the fly never has to be collected into a buffer
+/// first.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::decode_iter;
+///
+/// let input = [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+///
+/// assert_eq!(decode_iter(input).unwrap(), [0x01, 0xDB, 0x49, 0xC0, 0x15]);
+/// ```
+pub fn decode_iter<I: IntoIterator<Item = u8>>(encoded_bytes: I) -> Result<Vec<u8>, SlipError> {
+  let mut encoded_bytes = encoded_bytes.into_iter();
+  if !encoded_bytes.any(|byte| byte == END) {
+    return Err(SlipError::NoEndDelimiter);
+  }
+
+  let mut decoded_buffer: Vec<u8> = Vec::with_capacity(encoded_bytes.size_hint().0);
 
+  while let Some(byte) = encoded_bytes.next() {
     match byte {
-      ESC => {
-        let next_byte = encoded_buffer[read_idx + 1];
-        let (latest_read_idx, latest_write_idx) =
-          unescape(&next_byte, read_idx, write_idx, &mut decoded_buffer)?;
+      END => {}
+      ESC => match encoded_bytes.next() {
+        Some(ESC_END) => decoded_buffer.push(END),
+        Some(ESC_ESC) => decoded_buffer.push(ESC),
+        found => return Err(SlipError::invalid_escape(found, END)),
+      },
+      _ => decoded_buffer.push(byte),
+    }
+  }
+
+  Ok(decoded_buffer)
+}
+
+/// Decodes data following the SLIP protocol into an existing `Vec<u8>`.
+///
+/// `decoded_buffer` is cleared first, and the frame is decoded into the
+/// capacity it already has, so decoding frame after frame into the same
+/// `Vec<u8>` stops allocating once it has grown to fit the largest of them.
+/// If the frame isn't encoded correctly, `decoded_buffer` is left empty.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::decode_into;
+///
+/// let mut frame: Vec<u8> = Vec::new();
+///
+/// decode_into(&[0xC0, 0x01, 0xDB, 0xDC, 0xC0], &mut frame).unwrap();
+/// assert_eq!(frame, [0x01, 0xC0]);
+///
+/// decode_into(&[0xC0, 0x02, 0xC0], &mut frame).unwrap();
+/// assert_eq!(frame, [0x02]);
+/// ```
+pub fn decode_into(encoded_buffer: &[u8], decoded_buffer: &mut Vec<u8>) -> Result<(), SlipError> {
+  decoded_buffer.clear();
+
+  decode_to(encoded_buffer, decoded_buffer).inspect_err(|_| decoded_buffer.clear())
+}
+
+/// Decodes data following the SLIP protocol into any `ByteSink`.
+///
+/// This works just like `decode`, except that the decoded frame is
+/// appended to whatever is already in the sink. Decoding errors are
+/// converted into the sink's error type, which is where errors from the
+/// sink itself, such as running out of room, come from too.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::decode_to;
+///
+/// let input: [u8; 9] = [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+/// let mut buffer = [0u8; 8];
+/// let mut output: &mut [u8] = &mut buffer;
+///
+/// decode_to(&input, &mut output).unwrap();
+/// let len = 8 - output.len();
+///
+/// assert_eq!(&buffer[..len], [0x01, 0xDB, 0x49, 0xC0, 0x15]);
+/// ```
+pub fn decode_to<S>(encoded_buffer: &[u8], sink: &mut S) -> Result<(), S::Error>
+where
+  S: ByteSink,
+  S::Error: From<SlipError>,
+{
+  let encoded_buffer = frame_body(encoded_buffer)?;
+
+  sink.reserve(decoded_len_bound(encoded_buffer));
+  decode_body(encoded_buffer, sink)
+}
+
+/// Decodes data following the SLIP protocol, without aborting if the
+/// decoded frame can't be allocated.
+///
+/// This works just like `decode`, except that the output buffer is
+/// allocated with `try_reserve`, and `SlipError::AllocationFailed` is
+/// returned if that fails rather than aborting the process. This suits
+/// memory-constrained services that decode input of untrusted size.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::try_decode_alloc;
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+///
+/// assert_eq!(try_decode_alloc(&input).unwrap(), [0x01, 0xDB, 0x49, 0xC0, 0x15]);
+/// ```
+pub fn try_decode_alloc(encoded_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
+  let encoded_buffer = frame_body(encoded_buffer)?;
+
+  let mut decoded_buffer: Vec<u8> = Vec::new();
+  decoded_buffer
+    .try_reserve_exact(decoded_len_bound(encoded_buffer))
+    .map_err(|_| SlipError::AllocationFailed)?;
+  decode_body(encoded_buffer, &mut decoded_buffer)?;
+
+  Ok(decoded_buffer)
+}
+
+// Returns everything after the first delimiter, which is what `decode`
+// decodes.
+pub(crate) fn frame_body(encoded_buffer: &[u8]) -> Result<&[u8], SlipError> {
+  Ok(&encoded_buffer[find_delimiter(encoded_buffer)? + 1..])
+}
+
+// Returns the most bytes that `encoded_buffer` can decode to, which is exact
+// for a frame with nothing to unescape.
+pub(crate) fn decoded_len_bound(encoded_buffer: &[u8]) -> usize {
+  encoded_buffer
+    .strip_suffix(&[END])
+    .unwrap_or(encoded_buffer)
+    .len()
+}
+
+// Decodes a `frame_body` into `sink`, without reserving any room first.
+pub(crate) fn decode_body<S>(encoded_buffer: &[u8], sink: &mut S) -> Result<(), S::Error>
+where
+  S: ByteSink,
+  S::Error: From<SlipError>,
+{
+  decode_body_with(&SlipConfig::new(), encoded_buffer, sink)
+}
+
+// Decodes a `frame_body` into `sink` using the bytes from `config`.
+pub(crate) fn decode_body_with<S>(
+  config: &SlipConfig,
+  encoded_buffer: &[u8],
+  sink: &mut S,
+) -> Result<(), S::Error>
+where
+  S: ByteSink,
+  S::Error: From<SlipError>,
+{
+  let max_frame_len = config.max_frame_len.unwrap_or(usize::MAX);
+
+  // A frame with nothing to unescape is copied out in one go.
+  if let Some(body) = encoded_buffer.strip_suffix(&[config.end]) {
+    if body.len() <= max_frame_len && config.find_special(body).is_none() {
+      return sink.write_bytes(body);
+    }
+  }
+
+  let mut read_idx = 0;
+  let mut written: usize = 0;
 
-        read_idx = latest_read_idx;
-        write_idx = latest_write_idx;
+  while read_idx < encoded_buffer.len() {
+    match encoded_buffer[read_idx] {
+      byte if byte == config.esc => {
+        match encoded_buffer
+          .get(read_idx + 1)
+          .map(|&byte| config.unescape(byte))
+        {
+          Some(Some(_)) if written >= max_frame_len => {
+            return Err(SlipError::FrameTooLarge.into());
+          }
+          Some(Some(byte)) => {
+            sink.write_bytes(&[byte])?;
+            written += 1;
+            read_idx += 2;
+          }
+          // A dangling `ESC` is dropped.
+          _ if config.invalid_escapes == InvalidEscapePolicy::Keep => read_idx += 1,
+          _ => {
+            let found = encoded_buffer.get(read_idx + 1).copied();
+            return Err(SlipError::invalid_escape(found, config.end).into());
+          }
+        }
       }
-      END => read_idx += 1,
+      byte if byte == config.end => read_idx += 1,
       _ => {
-        decoded_buffer[write_idx] = byte;
+        let rest = &encoded_buffer[read_idx..];
+        let run = config.find_special(rest).unwrap_or(rest.len());
+        if run > max_frame_len - written {
+          return Err(SlipError::FrameTooLarge.into());
+        }
+        sink.write_bytes(&rest[..run])?;
+        written += run;
+        read_idx += run;
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Decodes data following the SLIP protocol in place.
+///
+/// Unlike `decode`, this never allocates. Everything up to the first `END`
+/// (0xC0) byte is skipped and the rest is decoded into the start of the
+/// same buffer, returning the decoded length. This is always possible as
+/// decoded data is never longer than its encoding.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::decode_in_place;
+///
+/// let mut buffer: [u8; 9] = [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+///
+/// let len = decode_in_place(&mut buffer).unwrap();
+///
+/// assert_eq!(&buffer[..len], &[0x01, 0xDB, 0x49, 0xC0, 0x15]);
+/// ```
+pub fn decode_in_place(buffer: &mut [u8]) -> Result<usize, SlipError> {
+  let mut read_idx = scan::find_end(buffer).ok_or(SlipError::NoEndDelimiter)?;
+  let mut write_idx = 0;
 
+  while read_idx < buffer.len() {
+    let byte = buffer[read_idx];
+    read_idx += 1;
+
+    match byte {
+      END => {}
+      ESC => {
+        buffer[write_idx] = match buffer.get(read_idx).copied() {
+          Some(ESC_END) => END,
+          Some(ESC_ESC) => ESC,
+          found => return Err(SlipError::invalid_escape(found, END)),
+        };
         read_idx += 1;
         write_idx += 1;
       }
+      _ => {
+        buffer[write_idx] = byte;
+        write_idx += 1;
+      }
     }
   }
 
-  Ok(decoded_buffer)
+  Ok(write_idx)
 }
 
 /// Decodes data following the SLIP protocol into delimited packets.
@@ -70,6 +412,12 @@ pub fn decode(encoded_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
 /// If we then append the next input to the remainder: `[0xC0, 0x01] + [0x02, ...]`, this will enable us to
 /// keep decoding packets across the input buffers boundary.
 ///
+/// This includes escape sequences split across input buffers. A frame whose last byte in the input is an
+/// `ESC` (0xDB) byte is left in the remainder, and is decoded correctly once the rest of it is appended.
+///
+/// Complete frames that aren't encoded correctly are dropped, so any input, however corrupt, decodes
+/// without panicking. Use `decode_packets_checked` to get an error in their place instead.
+///
 /// # Example:
 ///
 /// ```rust
@@ -89,143 +437,271 @@ pub fn decode(encoded_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
 ///
 /// assert_eq!(result, expected);
 /// assert_eq!(remainder, [0xC0, 0x01]);
-
 /// ```
 pub fn decode_packets(encoded_buffer: &[u8]) -> (Vec<Vec<u8>>, Vec<u8>) {
-  let mut parent_decoded_buffer: Vec<Vec<u8>> = Vec::new();
+  let (packets, consumed) = decode_packets_consumed(encoded_buffer);
 
-  let mut idx = 0;
-  let mut trim_start = find_delimiter(&encoded_buffer).unwrap();
+  (packets, encoded_buffer[consumed..].to_vec())
+}
 
-  while idx < encoded_buffer.len() {
-    match find_next_delimiter(encoded_buffer, trim_start + 1) {
-      Some(trim_end) => {
-        let decoded_buffer_size = calc_decode_buffer_size(&encoded_buffer[trim_start..trim_end]);
-        let mut local_decoded_buffer: Vec<u8> = vec![0; decoded_buffer_size];
-        simple_decode(
-          &encoded_buffer[trim_start..trim_end],
-          &mut local_decoded_buffer,
-        );
-
-        parent_decoded_buffer.push(local_decoded_buffer);
-
-        idx = trim_end + 1;
-        trim_start = idx;
-      }
-      None => {
-        return (
-          parent_decoded_buffer,
-          (&encoded_buffer[trim_start..]).to_vec(),
-        )
-      }
+/// Decodes data following the SLIP protocol into delimited packets,
+/// without copying the remainder.
+///
+/// This works just like `decode_packets`, except that rather than
+/// allocating a new `Vec<u8>` for the remainder, the number of bytes
+/// consumed from the input is returned. The remainder is then
+/// `&encoded_buffer[consumed..]`, which the caller can keep however suits
+/// them best.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::decode_packets_consumed;
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0, 0xC0, 0x01];
+///
+/// let (result, consumed): (Vec<Vec<u8>>, usize) = decode_packets_consumed(&input);
+///
+/// assert_eq!(result, [[0x01, 0xDB, 0x49, 0xC0, 0x15]]);
+/// assert_eq!(&input[consumed..], [0xC0, 0x01]);
+/// ```
+pub fn decode_packets_consumed(encoded_buffer: &[u8]) -> (Vec<Vec<u8>>, usize) {
+  let mut parent_decoded_buffer: Vec<Vec<u8>> = Vec::new();
+
+  let consumed = split_packets(encoded_buffer, |frame| {
+    if let Ok(packet) = decode_packet(frame) {
+      parent_decoded_buffer.push(packet);
     }
-  }
+  });
 
-  (parent_decoded_buffer, (&[]).to_vec())
+  (parent_decoded_buffer, consumed)
 }
 
-fn simple_decode(encoded_buffer: &[u8], decoded_buffer: &mut Vec<u8>) {
-  let mut read_idx = 0;
-  let mut write_idx = 0;
-  while read_idx < encoded_buffer.len() {
-    let byte = encoded_buffer[read_idx];
-
-    match byte {
-      ESC => {
-        let next_byte = encoded_buffer[read_idx + 1];
-        let (latest_read_idx, latest_write_idx) =
-          unescape(&next_byte, read_idx, write_idx, decoded_buffer).unwrap();
+/// Decodes data following the SLIP protocol into delimited packets,
+/// returning a result for each one.
+///
+/// This works just like `decode_packets`, except that a frame that isn't
+/// encoded correctly is returned as an error in its place, rather than
+/// being dropped, so one corrupt frame can be reported without losing the
+/// frames around it.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_packets_checked, SlipError};
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0xC0, 0xC0, 0xDB, 0x02, 0xC0, 0xC0, 0x03, 0xC0, 0xC0, 0x04];
+///
+/// let (result, remainder) = decode_packets_checked(&input);
+///
+/// assert_eq!(
+///   result,
+///   [Ok(vec![0x01]), Err(SlipError::InvalidEscapeByte { found: 0x02 }), Ok(vec![0x03])]
+/// );
+/// assert_eq!(remainder, [0xC0, 0x04]);
+/// ```
+pub fn decode_packets_checked(encoded_buffer: &[u8]) -> (Vec<Result<Vec<u8>, SlipError>>, Vec<u8>) {
+  SlipConfig::new().decode_packets(encoded_buffer)
+}
 
-        read_idx = latest_read_idx;
-        write_idx = latest_write_idx;
-      }
-      END => read_idx += 1,
-      _ => {
-        decoded_buffer[write_idx] = byte;
+// Calls `on_packet` with the encoded bytes of each complete packet in
+// `encoded_buffer`, without its delimiters, returning the number of bytes
+// consumed.
+pub(crate) fn split_packets<'a, F>(encoded_buffer: &'a [u8], on_packet: F) -> usize
+where
+  F: FnMut(&'a [u8]),
+{
+  split_packets_with(&SlipConfig::new(), encoded_buffer, on_packet)
+}
 
-        read_idx += 1;
-        write_idx += 1;
-      }
+// Splits `encoded_buffer` into packets like `split_packets`, using the
+// delimiter and empty frame handling from `config`.
+pub(crate) fn split_packets_with<'a, F>(
+  config: &SlipConfig,
+  encoded_buffer: &'a [u8],
+  mut on_packet: F,
+) -> usize
+where
+  F: FnMut(&'a [u8]),
+{
+  let mut start = 0;
+  if config.leading_end {
+    match config.find_end(encoded_buffer) {
+      Some(idx) => start = idx + 1,
+      None => return 0,
     }
   }
-}
 
-fn find_delimiter(buffer: &[u8]) -> Result<usize, SlipError> {
-  for (i, _) in buffer.iter().enumerate() {
-    if buffer[i] == END {
-      if buffer[i + 1] == END {
-        return Ok(i + 1);
-      } else {
-        return Ok(i);
-      }
+  // With a leading `END` on every frame, the `END` straight after a frame
+  // starts the next one rather than delimiting an empty frame.
+  let mut after_frame = false;
+  while let Some(len) = config.find_end(&encoded_buffer[start..]) {
+    let packet = &encoded_buffer[start..start + len];
+    let yield_empty =
+      config.empty_frames != EmptyFramePolicy::Skip && !(config.leading_end && after_frame);
+
+    after_frame = !packet.is_empty() || yield_empty;
+    if after_frame {
+      on_packet(packet);
     }
+    start += len + 1;
   }
 
-  Err(SlipError::NoEndDelimiter)
+  // Keep the `END` that starts a partial frame in the remainder.
+  if config.leading_end && start < encoded_buffer.len() {
+    start - 1
+  } else {
+    start
+  }
 }
 
-fn find_next_delimiter(buffer: &[u8], start_idx: usize) -> Option<usize> {
-  let mut idx = start_idx;
-  while idx < buffer.len() {
-    if buffer[idx] == END {
-      return Some(idx);
-    }
+// Decodes a single packet found by `split_packets`.
+pub(crate) fn decode_packet(body: &[u8]) -> Result<Vec<u8>, SlipError> {
+  // A frame never decodes to more bytes than it has, and one with nothing
+  // to unescape is copied in a single run.
+  let mut decoded_buffer: Vec<u8> = Vec::with_capacity(body.len());
+  simple_decode(body, &mut decoded_buffer)?;
 
-    idx += 1;
-  }
+  Ok(decoded_buffer)
+}
 
-  None
+/// The outcome of attempting to decode a single frame with `try_decode`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeStatus {
+  /// A frame was decoded from the first `consumed` bytes of the buffer.
+  Complete { frame: Vec<u8>, consumed: usize },
+  /// The buffer ends before a frame does, so more data is needed.
+  Incomplete,
+  /// The frame isn't encoded correctly. The first `consumed` bytes of the
+  /// buffer should be dropped before trying again.
+  Invalid { error: SlipError, consumed: usize },
 }
 
-fn find_last_delimiter(buffer: &[u8]) -> Result<usize, SlipError> {
-  for (i, _) in buffer.iter().rev().enumerate() {
-    let idx = (buffer.len() - 1) - i;
-    if buffer[idx] == END {
-      if buffer[idx - 1] == END {
-        return Ok(idx - 1);
-      } else {
-        return Ok(idx);
+/// Attempts to decode the first frame in a buffer following the SLIP protocol.
+///
+/// Unlike `decode`, running out of data part way through a frame, or part
+/// way through an escape sequence, isn't an error. Instead
+/// `DecodeStatus::Incomplete` is returned so the caller can wait for more
+/// data, which keeps it distinct from data that is actually corrupt.
+///
+/// Once a frame is complete, `consumed` counts every byte before its
+/// terminating `END` (0xC0) byte. That `END` byte is left in the buffer, as
+/// it may also be the start of the next frame.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{try_decode, DecodeStatus};
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB];
+///
+/// assert_eq!(try_decode(&input), DecodeStatus::Incomplete);
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+///
+/// assert_eq!(
+///   try_decode(&input),
+///   DecodeStatus::Complete { frame: vec![0x01, 0xDB, 0x49, 0xC0, 0x15], consumed: 8 }
+/// );
+/// ```
+pub fn try_decode(encoded_buffer: &[u8]) -> DecodeStatus {
+  let mut idx = match scan::find_end(encoded_buffer) {
+    Some(delimiter_idx) => delimiter_idx + 1,
+    None => return DecodeStatus::Incomplete,
+  };
+  let mut frame: Vec<u8> = Vec::new();
+
+  while idx < encoded_buffer.len() {
+    match encoded_buffer[idx] {
+      END if frame.is_empty() => idx += 1,
+      END => {
+        return DecodeStatus::Complete {
+          frame,
+          consumed: idx,
+        }
+      }
+      ESC => match encoded_buffer.get(idx + 1) {
+        Some(&ESC_END) => {
+          frame.push(END);
+          idx += 2;
+        }
+        Some(&ESC_ESC) => {
+          frame.push(ESC);
+          idx += 2;
+        }
+        // The `END` cutting the escape short is left in the buffer, as it
+        // starts the next frame.
+        Some(&END) => {
+          return DecodeStatus::Invalid {
+            error: SlipError::TruncatedEscape,
+            consumed: idx + 1,
+          }
+        }
+        Some(&found) => {
+          return DecodeStatus::Invalid {
+            error: SlipError::InvalidEscapeByte { found },
+            consumed: idx + 1,
+          }
+        }
+        None => return DecodeStatus::Incomplete,
+      },
+      _ => {
+        let run = scan::find_special(&encoded_buffer[idx..]).unwrap_or(encoded_buffer.len() - idx);
+        frame.extend_from_slice(&encoded_buffer[idx..idx + run]);
+        idx += run;
       }
     }
   }
 
-  Err(SlipError::NoEndDelimiter)
+  DecodeStatus::Incomplete
 }
 
-fn calc_decode_buffer_size(encoded_buffer: &[u8]) -> usize {
-  let mut sum = 0;
-  let mut idx = 0;
+// Decodes `encoded_buffer` onto the end of `decoded_buffer`.
+fn simple_decode(encoded_buffer: &[u8], decoded_buffer: &mut Vec<u8>) -> Result<(), SlipError> {
+  for_each_decoded_run(encoded_buffer, |run| decoded_buffer.extend_from_slice(run))
+}
 
-  while idx < encoded_buffer.len() {
-    let byte = encoded_buffer[idx];
+// Calls `emit` with each run of decoded bytes in `encoded_buffer`, stopping
+// at the first invalid escape sequence, including an `ESC` at the end.
+fn for_each_decoded_run<F>(encoded_buffer: &[u8], mut emit: F) -> Result<(), SlipError>
+where
+  F: FnMut(&[u8]),
+{
+  let mut read_idx = 0;
 
-    if byte == ESC {
-      sum += 1;
-      idx += 2;
-      continue;
-    } else if byte != END {
-      sum += 1;
+  while read_idx < encoded_buffer.len() {
+    match encoded_buffer[read_idx] {
+      ESC => {
+        match encoded_buffer.get(read_idx + 1) {
+          Some(&ESC_END) => emit(&[END]),
+          Some(&ESC_ESC) => emit(&[ESC]),
+          Some(&found) => return Err(SlipError::invalid_escape(Some(found), END)),
+          None => return Err(SlipError::TruncatedEscape),
+        }
+        read_idx += 2;
+      }
+      END => read_idx += 1,
+      _ => {
+        let rest = &encoded_buffer[read_idx..];
+        let run = scan::find_special(rest).unwrap_or(rest.len());
+        emit(&rest[..run]);
+        read_idx += run;
+      }
     }
-
-    idx += 1;
   }
 
-  return sum;
+  Ok(())
 }
 
-fn unescape(
-  val: &u8,
-  read_idx: usize,
-  write_idx: usize,
-  write_buffer: &mut Vec<u8>,
-) -> Result<(usize, usize), SlipError> {
-  match *val {
-    ESC_ESC => write_buffer[write_idx] = ESC,
-    ESC_END => write_buffer[write_idx] = END,
-    _ => return Err(SlipError::InvalidEncoding),
-  }
+fn find_delimiter(buffer: &[u8]) -> Result<usize, SlipError> {
+  let idx = scan::find_end(buffer).ok_or(SlipError::NoEndDelimiter)?;
 
-  Ok((read_idx + 2, write_idx + 1))
+  if buffer.get(idx + 1) == Some(&END) {
+    Ok(idx + 1)
+  } else {
+    Ok(idx)
+  }
 }
 
 #[cfg(test)]
@@ -242,6 +718,123 @@ mod tests {
     let res: Vec<u8> = decode(&input).unwrap();
 
     assert_eq!(res, expected);
+    assert_eq!(decode([0xA1, END, END, 0x01]), Ok(vec![0x01]));
+    assert_eq!(decode([END, 0x01, ESC]), Err(SlipError::TruncatedEscape));
+  }
+
+  #[test]
+  fn reports_error_offsets() {
+    let input: Vec<u8> = vec![ESC, END, 0x01, ESC, ESC_END, 0x02, ESC, ESC];
+
+    let error = decode_detailed(&input).unwrap_err();
+    assert_eq!((error.offset, error.byte), (7, Some(ESC)));
+    let error = decode_detailed(&input[..7]).unwrap_err();
+    assert_eq!((error.offset, error.byte), (6, Some(ESC)));
+    let error = decode_detailed(&input[..1]).unwrap_err();
+    assert_eq!(
+      error,
+      DecodeError {
+        error: SlipError::NoEndDelimiter,
+        offset: 1,
+        byte: None,
+      }
+    );
+    assert_eq!(decode_detailed(&input[..6]), Ok(vec![0x01, END, 0x02]));
+  }
+
+  #[test]
+  fn decodes_lossily() {
+    let input: Vec<u8> = vec![
+      0xA1, END, 0x01, ESC, ESC_ESC, ESC, 0x02, 0x03, END, ESC, END, 0x04, ESC,
+    ];
+
+    assert_eq!(decode_lossy(&input), [0x01, ESC, 0x03, 0x04]);
+    assert_eq!(decode_lossy(&input[..5]), decode(&input[..5]).unwrap());
+    assert_eq!(decode_lossy([0x01, ESC, ESC_END]), [0x01, END]);
+    assert!(decode_lossy([]).is_empty());
+  }
+
+  #[test]
+  fn decodes_any_input() {
+    let input: Vec<u8> = vec![0xA1, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END];
+    let expected = decode(&input).unwrap();
+
+    assert_eq!(decode(input.clone()), Ok(expected.clone()));
+    assert_eq!(decode_iter(input.iter().copied()), Ok(expected));
+    assert_eq!(decode([END, 0x01, END]), Ok(vec![0x01]));
+    assert_eq!(
+      decode_iter([END, 0x01, ESC]),
+      Err(SlipError::TruncatedEscape)
+    );
+    assert_eq!(decode_iter([0x01]), Err(SlipError::NoEndDelimiter));
+  }
+
+  #[test]
+  fn decodes_into_existing_capacity() {
+    let mut frame: Vec<u8> = Vec::with_capacity(16);
+    let ptr = frame.as_ptr();
+
+    assert_eq!(
+      decode_into(&[END, 0x01, ESC, ESC_END, END], &mut frame),
+      Ok(())
+    );
+    assert_eq!(frame, [0x01, END]);
+    assert_eq!(decode_into(&[END, 0x02, END], &mut frame), Ok(()));
+    assert_eq!(frame, [0x02]);
+    assert_eq!(frame.as_ptr(), ptr);
+
+    assert_eq!(
+      decode_into(&[END, 0x03, ESC, 0x04], &mut frame),
+      Err(SlipError::InvalidEscapeByte { found: 0x04 })
+    );
+    assert!(frame.is_empty());
+  }
+
+  #[test]
+  fn try_decode_alloc_matches_decode() {
+    let inputs: [&[u8]; 4] = [
+      &[
+        0xA1, END, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END,
+      ],
+      &[END, 0x01, 0x02, END],
+      &[END, 0x01, ESC],
+      &[0x01],
+    ];
+
+    for input in inputs {
+      assert_eq!(try_decode_alloc(input), decode(input));
+    }
+  }
+
+  #[test]
+  fn decodes_in_place() {
+    let mut input: Vec<u8> = vec![
+      0xA1, 0xA2, 0xA3, END, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END,
+    ];
+    let expected = decode(&input).unwrap();
+
+    let len = decode_in_place(&mut input).unwrap();
+
+    assert_eq!(&input[..len], expected);
+    assert_eq!(
+      decode_in_place(&mut [END, 0x01, ESC]),
+      Err(SlipError::TruncatedEscape)
+    );
+    assert_eq!(decode_in_place(&mut [0x01]), Err(SlipError::NoEndDelimiter));
+  }
+
+  #[test]
+  fn copies_frames_without_escapes() {
+    let payload: Vec<u8> = (0..=0xBF).collect();
+    let mut input = encode(&payload).unwrap();
+
+    assert_eq!(decode(&input).unwrap(), payload);
+
+    input.extend(encode([0x01, END]).unwrap());
+    input.extend(encode(&payload).unwrap());
+    let (packets, remainder) = decode_packets(&input);
+    assert_eq!(packets, [payload.clone(), vec![0x01, END], payload]);
+    assert!(remainder.is_empty());
   }
 
   #[test]
@@ -264,11 +857,149 @@ mod tests {
     assert_eq!(remainder, expected_remainder);
   }
 
+  #[test]
+  fn decodes_packets_without_copying_remainder() {
+    let input: Vec<u8> = vec![END, 0x01, ESC, ESC_END, END, END, 0x02, END, END, 0x03, ESC];
+
+    let (packets, consumed) = decode_packets_consumed(&input);
+
+    assert_eq!(packets, [vec![0x01, END], vec![0x02]]);
+    assert_eq!(&input[consumed..], &[END, 0x03, ESC]);
+  }
+
+  #[test]
+  fn keeps_split_escape_in_remainder() {
+    let first: Vec<u8> = vec![END, 0x01, END, END, 0x02, ESC];
+    let second: Vec<u8> = vec![ESC_END, 0x03, END];
+
+    let (packets, mut remainder) = decode_packets(&first);
+    assert_eq!(packets, [vec![0x01]]);
+    assert_eq!(remainder, [END, 0x02, ESC]);
+
+    remainder.extend(second);
+    let (packets, remainder) = decode_packets(&remainder);
+    assert_eq!(packets, [vec![0x02, END, 0x03]]);
+    assert!(remainder.is_empty());
+  }
+
+  #[test]
+  fn rejects_escape_cut_off_by_end() {
+    let encoded = [END, 0x01, ESC, END, 0x02, END];
+
+    let (packets, _) = decode_packets(&encoded);
+    assert_eq!(packets, [vec![0x02]]);
+    let (packets, _) = decode_packets_checked(&encoded);
+    assert_eq!(packets, [Err(SlipError::TruncatedEscape), Ok(vec![0x02])]);
+    assert_eq!(decode(&encoded[..4]), Err(SlipError::TruncatedEscape));
+  }
+
+  #[test]
+  fn try_decode_reports_status() {
+    assert_eq!(try_decode(&[0xA1, 0xA2]), DecodeStatus::Incomplete);
+    assert_eq!(try_decode(&[END, 0x01, ESC]), DecodeStatus::Incomplete);
+    assert_eq!(
+      try_decode(&[0xA1, END, END, 0x01, ESC, ESC_END, END, 0x02, END]),
+      DecodeStatus::Complete {
+        frame: vec![0x01, END],
+        consumed: 6
+      }
+    );
+    assert_eq!(
+      try_decode(&[END, 0x01, ESC, 0x02, END]),
+      DecodeStatus::Invalid {
+        error: SlipError::InvalidEscapeByte { found: 0x02 },
+        consumed: 3
+      }
+    );
+  }
+
+  #[test]
+  fn try_decode_recovers_after_truncated_escape() {
+    let mut input: &[u8] = &[END, 0x01, ESC, END, 0x02, END];
+    let mut results = Vec::new();
+
+    loop {
+      match try_decode(input) {
+        DecodeStatus::Complete { frame, consumed } => {
+          results.push(Ok(frame));
+          input = &input[consumed..];
+        }
+        DecodeStatus::Invalid { error, consumed } => {
+          results.push(Err(error));
+          input = &input[consumed..];
+        }
+        DecodeStatus::Incomplete => break,
+      }
+    }
+
+    assert_eq!(results, [Err(SlipError::TruncatedEscape), Ok(vec![0x02])]);
+    assert_eq!(input, [END]);
+  }
+
+  #[test]
+  fn drops_frames_with_invalid_escapes() {
+    let input: Vec<u8> = vec![END, 0x01, ESC, 0x02, END, END, 0x03, END, 0x04];
+
+    let (packets, remainder) = decode_packets(&input);
+
+    assert_eq!(packets, [vec![0x03]]);
+    assert_eq!(remainder, [END, 0x04]);
+
+    let (packets, remainder) = decode_packets_checked(&input);
+
+    assert_eq!(
+      packets,
+      [
+        Err(SlipError::InvalidEscapeByte { found: 0x02 }),
+        Ok(vec![0x03])
+      ]
+    );
+    assert_eq!(remainder, [END, 0x04]);
+  }
+
+  // Decodes pseudo-random buffers, heavy on the special bytes, through every
+  // decoding function, none of which may panic however corrupt they are.
+  #[test]
+  fn never_panics_on_arbitrary_input() {
+    let alphabet = [END, ESC, ESC_END, ESC_ESC, 0x00, 0x01, 0xFF];
+    let mut state: u32 = 0x2545_F491;
+    let mut next = move || {
+      state ^= state << 13;
+      state ^= state >> 17;
+      state ^= state << 5;
+      state
+    };
+
+    for _ in 0..2000 {
+      let len = (next() % 24) as usize;
+      let mut input: Vec<u8> = (0..len)
+        .map(|_| match next() % 4 {
+          0 => next() as u8,
+          _ => alphabet[next() as usize % alphabet.len()],
+        })
+        .collect();
+
+      let _ = decode(&input);
+      let _ = decode_detailed(&input);
+      let _ = decode_lossy(&input);
+      let _ = decode_iter(input.iter().copied());
+      let _ = decode_into(&input, &mut Vec::new());
+      let _ = try_decode_alloc(&input);
+      let _ = try_decode(&input);
+      let _ = decode_packets(&input);
+      let _ = decode_packets_checked(&input);
+      let mut decoder = SlipDecoder::new();
+      decoder.push(&input);
+      let _ = decoder.drain().count();
+      let _ = decode_in_place(&mut input);
+    }
+  }
+
   #[test]
   fn errors_when_no_delimiter() {
     let error_input: [u8; 10] = [
       0xA1, 0xA2, 0xA3, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15,
     ];
-    assert!(decode(&error_input).is_err());
+    assert!(decode(error_input).is_err());
   }
 }
@@ -0,0 +1,133 @@
+use super::*;
+use alloc::collections::VecDeque;
+
+/// Drops frames whose payload was already received recently, such as the
+/// retransmissions of firmware that sends a frame again whenever it times
+/// out waiting for a reply.
+///
+/// A hash of each payload is kept for the last `window` frames received,
+/// and a payload with the same hash as one of them is a duplicate unless
+/// it's been at least `max_age` since that one arrived. Time is measured by
+/// a `now` timestamp passed in by the caller, in whatever monotonic unit it
+/// likes, like `Keepalive`.
+///
+/// A payload that's legitimately sent twice in a row, such as an unchanged
+/// reading, is dropped too, so `max_age` should be shorter than the time
+/// between such frames. Two different payloads with the same 64-bit hash
+/// are very unlikely, but would also be taken as duplicates.
+///
+/// `SlipConnection` drops duplicates itself when given a `DedupFilter` with
+/// `set_dedup`, counting time in milliseconds.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::DedupFilter;
+///
+/// let mut filter = DedupFilter::new(4, 1000);
+///
+/// assert!(!filter.is_duplicate(&[0x01], 0));
+/// assert!(!filter.is_duplicate(&[0x02], 10));
+/// assert!(filter.is_duplicate(&[0x01], 500));
+///
+/// // Long enough after the first copy, it's taken as a new frame.
+/// assert!(!filter.is_duplicate(&[0x01], 1000));
+/// assert_eq!(filter.dropped(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupFilter {
+  window: usize,
+  max_age: u64,
+  seen: VecDeque<Seen>,
+  dropped: u64,
+}
+
+// A payload received within the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Seen {
+  hash: u64,
+  at: u64,
+}
+
+impl DedupFilter {
+  /// Creates a filter remembering the last `window` frames, each for
+  /// `max_age`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `window` is 0.
+  pub fn new(window: usize, max_age: u64) -> Self {
+    assert!(window > 0, "window must be at least 1");
+
+    Self {
+      window,
+      max_age,
+      seen: VecDeque::with_capacity(window),
+      dropped: 0,
+    }
+  }
+
+  /// Records that `frame` was received at `now`, returning `true` if it's
+  /// a duplicate that should be dropped.
+  ///
+  /// Duplicates aren't recorded again, so a frame that keeps being resent
+  /// is let through again once `max_age` has passed since the first copy.
+  pub fn is_duplicate(&mut self, frame: &[u8], now: u64) -> bool {
+    let max_age = self.max_age;
+    self
+      .seen
+      .retain(|seen| now.saturating_sub(seen.at) < max_age);
+
+    let hash = fnv1a(frame);
+    if self.seen.iter().any(|seen| seen.hash == hash) {
+      self.dropped += 1;
+      return true;
+    }
+
+    if self.seen.len() == self.window {
+      self.seen.pop_front();
+    }
+    self.seen.push_back(Seen { hash, at: now });
+    false
+  }
+
+  /// Forgets every frame received, such as after the link has been reset.
+  pub fn clear(&mut self) {
+    self.seen.clear();
+  }
+
+  /// Returns the number of frames taken as duplicates.
+  pub fn dropped(&self) -> u64 {
+    self.dropped
+  }
+}
+
+// Returns the 64-bit FNV-1a hash of `bytes`, which is quick and the same on
+// every platform.
+fn fnv1a(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+    (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01B3)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_remembers_the_window() {
+    let mut filter = DedupFilter::new(2, u64::MAX);
+
+    assert!(!filter.is_duplicate(&[END], 0));
+    assert!(!filter.is_duplicate(&[ESC], 0));
+    assert!(filter.is_duplicate(&[END], 0));
+    assert!(!filter.is_duplicate(&[], 0));
+    assert!(!filter.is_duplicate(&[END], 0));
+    assert!(filter.is_duplicate(&[], 0));
+    assert_eq!(filter.dropped(), 2);
+
+    filter.clear();
+    assert!(!filter.is_duplicate(&[END], 0));
+    assert_eq!(fnv1a(b"a"), 0xAF63_DC4C_8601_EC8C);
+  }
+}
@@ -0,0 +1,112 @@
+use super::*;
+use alloc::vec::Vec;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::{decompress_to_vec_with_limit, TINFLStatus};
+
+/// Compresses each payload with raw DEFLATE, for low-bandwidth links such
+/// as radios.
+///
+/// Every payload is compressed on its own, so frames can still be decoded
+/// independently, and lost frames don't affect the ones after them. Small
+/// or random payloads can come out slightly larger than they went in.
+///
+/// To protect against payloads that decompress to a huge size,
+/// decompressing stops with `SlipError::FrameTooLarge` past `max_size`
+/// bytes, which is 64 KiB by default. A payload that isn't valid DEFLATE
+/// data fails with `SlipError::TransformFailed`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_with_transform, encode_with_transform, Deflate};
+///
+/// let deflate = Deflate::new().level(9);
+/// let payload = [0x42; 200];
+///
+/// let encoded = encode_with_transform(&deflate, &payload).unwrap();
+/// assert!(encoded.len() < 20);
+/// assert_eq!(decode_with_transform(&deflate, &encoded).unwrap(), payload);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deflate {
+  level: u8,
+  max_size: usize,
+}
+
+impl Deflate {
+  /// Creates a transform compressing at level 6, and decompressing
+  /// payloads up to 64 KiB.
+  pub const fn new() -> Self {
+    Self {
+      level: 6,
+      max_size: 64 * 1024,
+    }
+  }
+
+  /// Sets the compression level, from 0 (no compression) to 10 (slowest
+  /// and smallest). Higher levels are clamped to 10.
+  pub const fn level(mut self, level: u8) -> Self {
+    self.level = if level > 10 { 10 } else { level };
+    self
+  }
+
+  /// Sets the largest size a payload can decompress to.
+  pub const fn max_size(mut self, max_size: usize) -> Self {
+    self.max_size = max_size;
+    self
+  }
+}
+
+impl Default for Deflate {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PayloadTransform for Deflate {
+  fn apply(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+    Ok(compress_to_vec(payload, self.level))
+  }
+
+  fn revert(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+    decompress_to_vec_with_limit(payload, self.max_size).map_err(|error| match error.status {
+      TINFLStatus::HasMoreOutput => SlipError::FrameTooLarge,
+      _ => SlipError::TransformFailed,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_awkward_payloads() {
+    let payloads: [Vec<u8>; 3] = [Vec::new(), (0..=255).collect(), [END, ESC].repeat(500)];
+
+    for level in [0, 1, 10] {
+      let deflate = Deflate::new().level(level);
+      for payload in &payloads {
+        let encoded = encode_with_transform(&deflate, payload).unwrap();
+        assert_eq!(
+          decode_with_transform(&deflate, &encoded).as_ref(),
+          Ok(payload)
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn rejects_bad_and_oversized_payloads() {
+    let encoded = encode_with_transform(&Deflate::new(), [0x00; 100]).unwrap();
+
+    assert_eq!(
+      decode_with_transform(&Deflate::new().max_size(99), &encoded),
+      Err(SlipError::FrameTooLarge)
+    );
+    assert_eq!(
+      decode_with_transform(&Deflate::new(), [END, 0xFF, 0xFF, END]),
+      Err(SlipError::TransformFailed)
+    );
+  }
+}
@@ -0,0 +1,213 @@
+use super::*;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A single problem found by `diagnose`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeFinding {
+  /// There's no `END` (0xC0) byte anywhere in the buffer, so there's no
+  /// frame to decode.
+  NoEndDelimiter,
+  /// The first `len` bytes come before the first `END` byte, and are
+  /// skipped as noise.
+  LeadingNoise { len: usize },
+  /// The `ESC` (0xDB) byte at `offset` is followed by `found`, which isn't
+  /// `ESC_END` or `ESC_ESC`.
+  InvalidEscape { offset: usize, found: u8 },
+  /// The `ESC` byte at `offset` is followed by an `END` byte, or by the end
+  /// of the buffer, so it has nothing to escape.
+  StrayEscape { offset: usize },
+  /// The frame starting at `offset` has no `END` byte after it, so it's
+  /// left in the remainder by `decode_packets`.
+  UnterminatedFrame { offset: usize },
+}
+
+impl fmt::Display for DecodeFinding {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      DecodeFinding::NoEndDelimiter => f.write_str("no 'END' (0xC0) byte in the buffer"),
+      DecodeFinding::LeadingNoise { len } => {
+        write!(
+          f,
+          "{} bytes of leading noise before the first 'END' (0xC0) byte",
+          len
+        )
+      }
+      DecodeFinding::InvalidEscape { offset, found } => write!(
+        f,
+        "'ESC' (0xDB) byte at offset {} followed by invalid byte 0x{:02X}",
+        offset, found
+      ),
+      DecodeFinding::StrayEscape { offset } => {
+        write!(
+          f,
+          "stray 'ESC' (0xDB) byte at offset {} with nothing to escape",
+          offset
+        )
+      }
+      DecodeFinding::UnterminatedFrame { offset } => {
+        write!(
+          f,
+          "frame starting at offset {} has no 'END' (0xC0) byte after it",
+          offset
+        )
+      }
+    }
+  }
+}
+
+/// The findings from `diagnose`, in the order they were found.
+///
+/// Displaying the report lists the findings a line at a time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DecodeReport {
+  findings: Vec<DecodeFinding>,
+}
+
+impl DecodeReport {
+  /// Returns the findings.
+  pub fn findings(&self) -> &[DecodeFinding] {
+    &self.findings
+  }
+
+  /// Returns `true` if nothing was found wrong with the buffer.
+  pub fn is_clean(&self) -> bool {
+    self.findings.is_empty()
+  }
+}
+
+impl fmt::Display for DecodeReport {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.findings.is_empty() {
+      return f.write_str("no problems found");
+    }
+
+    for (idx, finding) in self.findings.iter().enumerate() {
+      if idx > 0 {
+        f.write_str("\n")?;
+      }
+      write!(f, "{}", finding)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Explains why a buffer of data following the SLIP protocol doesn't
+/// decode, or doesn't decode as expected.
+///
+/// Rather than stopping at the first error like `decode`, the whole buffer
+/// is checked, and everything that looks wrong is listed in the report
+/// along with where it is, for a "why won't this decode" debugging
+/// workflow.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{diagnose, DecodeFinding};
+///
+/// let input = [0xA1, 0xA2, 0xC0, 0x01, 0xDB, 0x02, 0xC0, 0x03, 0xDB];
+/// let report = diagnose(&input);
+///
+/// assert_eq!(
+///   report.findings(),
+///   [
+///     DecodeFinding::LeadingNoise { len: 2 },
+///     DecodeFinding::InvalidEscape { offset: 4, found: 0x02 },
+///     DecodeFinding::StrayEscape { offset: 8 },
+///     DecodeFinding::UnterminatedFrame { offset: 7 },
+///   ]
+/// );
+/// assert_eq!(
+///   report.to_string().lines().next(),
+///   Some("2 bytes of leading noise before the first 'END' (0xC0) byte")
+/// );
+/// ```
+pub fn diagnose(encoded_buffer: &[u8]) -> DecodeReport {
+  let mut findings: Vec<DecodeFinding> = Vec::new();
+
+  let first_end = match scan::find_end(encoded_buffer) {
+    Some(idx) => idx,
+    None => {
+      findings.push(DecodeFinding::NoEndDelimiter);
+      return DecodeReport { findings };
+    }
+  };
+  if first_end > 0 {
+    findings.push(DecodeFinding::LeadingNoise { len: first_end });
+  }
+
+  let mut frame_start = first_end + 1;
+  let mut idx = frame_start;
+  while let Some(found) = scan::find_special(&encoded_buffer[idx..]) {
+    idx += found;
+    if encoded_buffer[idx] == END {
+      idx += 1;
+      frame_start = idx;
+      continue;
+    }
+
+    match encoded_buffer.get(idx + 1) {
+      Some(&ESC_END) | Some(&ESC_ESC) => idx += 2,
+      Some(&END) | None => {
+        findings.push(DecodeFinding::StrayEscape { offset: idx });
+        idx += 1;
+      }
+      Some(&found) => {
+        findings.push(DecodeFinding::InvalidEscape { offset: idx, found });
+        idx += 2;
+      }
+    }
+  }
+
+  if frame_start < encoded_buffer.len() {
+    findings.push(DecodeFinding::UnterminatedFrame {
+      offset: frame_start,
+    });
+  }
+
+  DecodeReport { findings }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_nothing_wrong_with_encoded_frames() {
+    let mut input = encode([0x01, END, ESC]).unwrap();
+    input.extend(encode([0x02]).unwrap());
+
+    let report = diagnose(&input);
+
+    assert!(report.is_clean());
+    assert_eq!(report.to_string(), "no problems found");
+  }
+
+  #[test]
+  fn lists_every_finding() {
+    assert_eq!(
+      diagnose(&[0x01, ESC, 0x02]).findings(),
+      [DecodeFinding::NoEndDelimiter]
+    );
+
+    let report = diagnose(&[END, ESC, END, 0x01, ESC, ESC, ESC_END, END]);
+    assert_eq!(
+      report.findings(),
+      [
+        DecodeFinding::StrayEscape { offset: 1 },
+        DecodeFinding::InvalidEscape {
+          offset: 4,
+          found: ESC
+        },
+      ]
+    );
+    assert_eq!(
+      report.to_string(),
+      "stray 'ESC' (0xDB) byte at offset 1 with nothing to escape\n\
+       'ESC' (0xDB) byte at offset 4 followed by invalid byte 0xDB"
+    );
+  }
+}
@@ -0,0 +1,152 @@
+use super::*;
+
+/// A streaming decoder for UART DMA double buffering.
+///
+/// Each half of the DMA buffer is handed to `feed_dma_half` as soon as the
+/// DMA engine has moved on to the other half. Frames that lie entirely
+/// within a half are decoded in place, overwriting the encoded data in the
+/// half, and passed to the callback borrowed straight from it, so they're
+/// never copied. Only a frame that straddles the boundary between two
+/// halves has its decoded bytes copied into an internal `[u8; N]` scratch
+/// buffer, which is where it's returned from once the rest arrives.
+///
+/// The decoder never allocates, takes time in proportion to the bytes it's
+/// given, and doesn't panic, so `feed_dma_half` can be called from a DMA
+/// half or full transfer complete interrupt. Both halves and the scratch
+/// buffer are decoded by a `SlipMachine`. A frame that straddles a boundary and is
+/// longer than `N` bytes is reported as `SlipError::FrameTooLarge`, while
+/// frames within a single half are only bounded by the size of the half.
+///
+/// Like `SlipDecoder`, any bytes received before the first `END` (0xC0)
+/// byte are discarded.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::DmaSlipDecoder;
+///
+/// let mut decoder: DmaSlipDecoder<16> = DmaSlipDecoder::new();
+/// let mut dma = [0xC0, 0x01, 0xC0, 0xC0, 0x02, 0xDB, 0xDC, 0xC0, 0x03, 0x04];
+/// let mut frames: Vec<Vec<u8>> = Vec::new();
+///
+/// let (first, second) = dma.split_at_mut(5);
+/// decoder.feed_dma_half(first, |frame| frames.push(frame.unwrap().to_vec()));
+/// decoder.feed_dma_half(second, |frame| frames.push(frame.unwrap().to_vec()));
+///
+/// assert_eq!(frames, [vec![0x01], vec![0x02, 0xC0]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DmaSlipDecoder<const N: usize> {
+  // Decodes the frame carried over from one half to the next.
+  carry: StaticSlipDecoder<N>,
+}
+
+impl<const N: usize> DmaSlipDecoder<N> {
+  /// Creates a new decoder, waiting for the first `END` (0xC0) byte.
+  pub const fn new() -> Self {
+    Self {
+      carry: StaticSlipDecoder::new(),
+    }
+  }
+
+  /// Decodes the frames in a half of the DMA buffer that's just been filled.
+  ///
+  /// `on_frame` is called with every frame completed by `half`, in order.
+  /// Frames containing an invalid escape sequence are passed as a
+  /// `SlipError::InvalidEscapeByte` or `SlipError::TruncatedEscape` error.
+  /// The contents of `half` are overwritten as frames are decoded in place.
+  pub fn feed_dma_half<F>(&mut self, half: &mut [u8], mut on_frame: F)
+  where
+    F: FnMut(Result<&[u8], SlipError>),
+  {
+    // Finish the frame carried over from the previous half.
+    let mut read = 0;
+    loop {
+      let Some(&byte) = half.get(read) else {
+        return;
+      };
+      read += 1;
+
+      if let Some(frame) = self.carry.push_byte(byte) {
+        on_frame(frame);
+      }
+      if byte == END {
+        break;
+      }
+    }
+
+    let rest = &mut half[read..];
+    let mut machine = SlipMachine::synced();
+    loop {
+      match machine.advance(rest) {
+        SlipEvent::Frame(range) => on_frame(Ok(&rest[range])),
+        SlipEvent::Error(error) => on_frame(Err(error)),
+        SlipEvent::NeedMoreData => break,
+      }
+    }
+
+    // Carry the partial frame at the end of the half over to the next one.
+    self.carry.resume(machine, rest);
+  }
+
+  /// Returns the decoder to the state it was created in, dropping any
+  /// carried over frame and waiting for the next `END` (0xC0) byte.
+  pub fn reset(&mut self) {
+    self.carry.reset();
+  }
+}
+
+impl<const N: usize> Default for DmaSlipDecoder<N> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn feed<const N: usize>(
+    decoder: &mut DmaSlipDecoder<N>,
+    half: &mut [u8],
+  ) -> Vec<Result<Vec<u8>, SlipError>> {
+    let mut frames = Vec::new();
+    decoder.feed_dma_half(half, |frame| frames.push(frame.map(|frame| frame.to_vec())));
+    frames
+  }
+
+  #[test]
+  fn decodes_like_slip_decoder_at_every_split() {
+    let input: Vec<u8> = vec![
+      0xA1, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END, END, 0x02, ESC, 0x03, END,
+      0x04, END, 0x05, ESC, ESC_END, END,
+    ];
+    let mut expected = SlipDecoder::new();
+    expected.push(&input);
+    let expected: Vec<_> = expected.drain().collect();
+
+    for half_len in 1..input.len() {
+      let mut decoder: DmaSlipDecoder<8> = DmaSlipDecoder::new();
+      let mut frames = Vec::new();
+      for chunk in input.chunks(half_len) {
+        frames.extend(feed(&mut decoder, &mut chunk.to_vec()));
+      }
+
+      assert_eq!(frames, expected, "half length {}", half_len);
+    }
+  }
+
+  #[test]
+  fn limits_only_straddling_frames() {
+    let mut decoder: DmaSlipDecoder<2> = DmaSlipDecoder::new();
+
+    let frames = feed(
+      &mut decoder,
+      &mut [END, 0x01, 0x02, 0x03, END, 0x04, 0x05, 0x06],
+    );
+    assert_eq!(frames, [Ok(vec![0x01, 0x02, 0x03])]);
+
+    let frames = feed(&mut decoder, &mut [END, END, 0x07, END]);
+    assert_eq!(frames, [Err(SlipError::FrameTooLarge), Ok(vec![0x07])]);
+  }
+}
@@ -0,0 +1,177 @@
+use super::*;
+use crate::embedded_core::{EmbeddedSlipError, CHUNK_SIZE};
+use alloc::vec::Vec;
+use embedded_io_async::{ErrorType, Read, Write};
+
+/// Sends and receives frames following the SLIP protocol over any
+/// `embedded_io_async::Read` and `embedded_io_async::Write`, such as an
+/// Embassy UART.
+///
+/// Data is read in small chunks and fed through a `SlipDecoder`, so partial
+/// frames and any data read past the end of a frame are kept until the next
+/// call to `read_frame`. Frames are written straight to the inner writer a
+/// run of bytes at a time, without encoding them into a buffer first.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::EmbeddedSlip;
+///
+/// # futures::executor::block_on(async {
+/// let input: &[u8] = &[0xC0, 0x01, 0xDB, 0xDD, 0xC0, 0xC0, 0x02, 0xC0];
+/// let mut slip = EmbeddedSlip::new(input);
+///
+/// assert_eq!(slip.read_frame().await.unwrap(), [0x01, 0xDB]);
+/// assert_eq!(slip.read_frame().await.unwrap(), [0x02]);
+/// assert!(slip.read_frame().await.is_err());
+///
+/// let mut slip = EmbeddedSlip::new(Vec::new());
+/// slip.write_frame(&[0x01, 0xC0]).await.unwrap();
+///
+/// assert_eq!(slip.get_ref(), &[0xC0, 0x01, 0xDB, 0xDC, 0xC0]);
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct EmbeddedSlip<T> {
+  inner: T,
+  decoder: SlipDecoder,
+}
+
+impl<T> EmbeddedSlip<T> {
+  /// Creates a new `EmbeddedSlip` over `inner`.
+  pub fn new(inner: T) -> Self {
+    Self::with_decoder(inner, SlipDecoder::new())
+  }
+
+  /// Creates a new `EmbeddedSlip` over `inner` that decodes with `decoder`,
+  /// for example one created with `SlipDecoder::with_limit`.
+  pub fn with_decoder(inner: T, decoder: SlipDecoder) -> Self {
+    Self { inner, decoder }
+  }
+
+  /// Returns a reference to the inner reader or writer.
+  pub fn get_ref(&self) -> &T {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader or writer.
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.inner
+  }
+
+  /// Consumes the `EmbeddedSlip`, returning the inner reader or writer.
+  ///
+  /// Any buffered data that hasn't been returned as a frame is lost.
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+impl<T: Read> EmbeddedSlip<T> {
+  /// Reads the next complete frame.
+  ///
+  /// A frame that isn't encoded correctly is returned as
+  /// `EmbeddedSlipError::Decode`, after which the following frames can still
+  /// be read. Reaching the end of the inner reader returns
+  /// `EmbeddedSlipError::UnexpectedEof`.
+  pub async fn read_frame(&mut self) -> Result<Vec<u8>, EmbeddedSlipError<T::Error>> {
+    let mut chunk = [0u8; CHUNK_SIZE];
+
+    loop {
+      if let Some(frame) = self.decoder.next_frame() {
+        return frame.map_err(EmbeddedSlipError::Decode);
+      }
+
+      let read = self
+        .inner
+        .read(&mut chunk)
+        .await
+        .map_err(EmbeddedSlipError::Io)?;
+      if read == 0 {
+        return Err(EmbeddedSlipError::UnexpectedEof);
+      }
+
+      self.decoder.push(&chunk[..read]);
+    }
+  }
+}
+
+impl<T: Write> EmbeddedSlip<T> {
+  /// Encodes `payload` as a single frame, writes it to the inner writer and
+  /// flushes it.
+  pub async fn write_frame(
+    &mut self,
+    payload: &[u8],
+  ) -> Result<(), EmbeddedSlipError<<T as ErrorType>::Error>> {
+    self.write_all(&[END]).await?;
+
+    let mut rest = payload;
+    while let Some(idx) = scan::find_special(rest) {
+      self.write_all(&rest[..idx]).await?;
+      match rest[idx] {
+        END => self.write_all(&[ESC, ESC_END]).await?,
+        _ => self.write_all(&[ESC, ESC_ESC]).await?,
+      }
+      rest = &rest[idx + 1..];
+    }
+    self.write_all(rest).await?;
+    self.write_all(&[END]).await?;
+
+    self.inner.flush().await.map_err(EmbeddedSlipError::Io)
+  }
+
+  async fn write_all(
+    &mut self,
+    buf: &[u8],
+  ) -> Result<(), EmbeddedSlipError<<T as ErrorType>::Error>> {
+    self
+      .inner
+      .write_all(buf)
+      .await
+      .map_err(EmbeddedSlipError::Io)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use futures::executor::block_on;
+
+  #[test]
+  fn writes_the_same_bytes_as_encode() {
+    let payloads: [&[u8]; 3] = [&[0x01, ESC, 0x49, END, 0x15], &[END, END, ESC], &[0x02]];
+    let mut slip = EmbeddedSlip::new(Vec::new());
+    let mut expected: Vec<u8> = Vec::new();
+
+    block_on(async {
+      for payload in payloads {
+        slip.write_frame(payload).await.unwrap();
+        expected.extend(encode(payload).unwrap());
+      }
+    });
+
+    assert_eq!(slip.into_inner(), expected);
+  }
+
+  #[test]
+  fn reads_frames_split_across_chunks() {
+    let payload: Vec<u8> = (0..=255).collect();
+    let mut input = encode(&payload).unwrap();
+    input.extend([END, 0x01, ESC, 0x02, END]);
+    let mut slip = EmbeddedSlip::new(input.as_slice());
+
+    block_on(async {
+      assert_eq!(slip.read_frame().await.unwrap(), payload);
+      assert_eq!(
+        slip.read_frame().await,
+        Err(EmbeddedSlipError::Decode(SlipError::InvalidEscapeByte {
+          found: 0x02
+        }))
+      );
+      assert_eq!(
+        slip.read_frame().await,
+        Err(EmbeddedSlipError::UnexpectedEof)
+      );
+    });
+  }
+}
@@ -0,0 +1,147 @@
+use super::*;
+use crate::embedded_core::{EmbeddedSlipError, CHUNK_SIZE};
+use alloc::vec::Vec;
+use embedded_io::{ErrorType, Read, Write};
+
+/// Sends and receives frames following the SLIP protocol over any blocking
+/// `embedded_io::Read` and `embedded_io::Write`.
+///
+/// This is the blocking counterpart of `EmbeddedSlip`, for the UART drivers
+/// of HALs that expose `embedded_io`, and for std sockets wrapped with the
+/// `embedded-io-adapters` crate, so the same SLIP code runs on both.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::BlockingEmbeddedSlip;
+///
+/// let input: &[u8] = &[0xC0, 0x01, 0xDB, 0xDD, 0xC0, 0xC0, 0x02, 0xC0];
+/// let mut slip = BlockingEmbeddedSlip::new(input);
+///
+/// assert_eq!(slip.read_frame().unwrap(), [0x01, 0xDB]);
+/// assert_eq!(slip.read_frame().unwrap(), [0x02]);
+/// assert!(slip.read_frame().is_err());
+///
+/// let mut slip = BlockingEmbeddedSlip::new(Vec::new());
+/// slip.write_frame(&[0x01, 0xC0]).unwrap();
+///
+/// assert_eq!(slip.get_ref(), &[0xC0, 0x01, 0xDB, 0xDC, 0xC0]);
+/// ```
+#[derive(Debug)]
+pub struct BlockingEmbeddedSlip<T> {
+  inner: T,
+  decoder: SlipDecoder,
+}
+
+impl<T> BlockingEmbeddedSlip<T> {
+  /// Creates a new `BlockingEmbeddedSlip` over `inner`.
+  pub fn new(inner: T) -> Self {
+    Self::with_decoder(inner, SlipDecoder::new())
+  }
+
+  /// Creates a new `BlockingEmbeddedSlip` over `inner` that decodes with
+  /// `decoder`, for example one created with `SlipDecoder::with_limit`.
+  pub fn with_decoder(inner: T, decoder: SlipDecoder) -> Self {
+    Self { inner, decoder }
+  }
+
+  /// Returns a reference to the inner reader or writer.
+  pub fn get_ref(&self) -> &T {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader or writer.
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.inner
+  }
+
+  /// Consumes the `BlockingEmbeddedSlip`, returning the inner reader or
+  /// writer.
+  ///
+  /// Any buffered data that hasn't been returned as a frame is lost.
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+impl<T: Read> BlockingEmbeddedSlip<T> {
+  /// Reads the next complete frame, blocking on the inner reader as needed.
+  ///
+  /// A frame that isn't encoded correctly is returned as
+  /// `EmbeddedSlipError::Decode`, after which the following frames can still
+  /// be read. Reaching the end of the inner reader returns
+  /// `EmbeddedSlipError::UnexpectedEof`.
+  pub fn read_frame(&mut self) -> Result<Vec<u8>, EmbeddedSlipError<T::Error>> {
+    let mut chunk = [0u8; CHUNK_SIZE];
+
+    loop {
+      if let Some(frame) = self.decoder.next_frame() {
+        return frame.map_err(EmbeddedSlipError::Decode);
+      }
+
+      let read = self.inner.read(&mut chunk).map_err(EmbeddedSlipError::Io)?;
+      if read == 0 {
+        return Err(EmbeddedSlipError::UnexpectedEof);
+      }
+
+      self.decoder.push(&chunk[..read]);
+    }
+  }
+}
+
+impl<T: Write> BlockingEmbeddedSlip<T> {
+  /// Encodes `payload` as a single frame, writes it to the inner writer and
+  /// flushes it.
+  pub fn write_frame(
+    &mut self,
+    payload: &[u8],
+  ) -> Result<(), EmbeddedSlipError<<T as ErrorType>::Error>> {
+    self.write_all(&[END])?;
+
+    let mut rest = payload;
+    while let Some(idx) = scan::find_special(rest) {
+      self.write_all(&rest[..idx])?;
+      match rest[idx] {
+        END => self.write_all(&[ESC, ESC_END])?,
+        _ => self.write_all(&[ESC, ESC_ESC])?,
+      }
+      rest = &rest[idx + 1..];
+    }
+    self.write_all(rest)?;
+    self.write_all(&[END])?;
+
+    self.inner.flush().map_err(EmbeddedSlipError::Io)
+  }
+
+  fn write_all(&mut self, buf: &[u8]) -> Result<(), EmbeddedSlipError<<T as ErrorType>::Error>> {
+    self.inner.write_all(buf).map_err(EmbeddedSlipError::Io)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_frames() {
+    let payloads: [&[u8]; 3] = [&[0x01, ESC, 0x49, END, 0x15], &[END, END, ESC], &[0x02]];
+    let mut writer = BlockingEmbeddedSlip::new(Vec::new());
+
+    for payload in payloads {
+      writer.write_frame(payload).unwrap();
+    }
+
+    let encoded = writer.into_inner();
+    let expected: Vec<u8> = payloads
+      .iter()
+      .flat_map(|payload| encode(payload).unwrap())
+      .collect();
+    assert_eq!(encoded, expected);
+
+    let mut reader = BlockingEmbeddedSlip::new(encoded.as_slice());
+    for payload in payloads {
+      assert_eq!(reader.read_frame().unwrap(), payload);
+    }
+    assert_eq!(reader.read_frame(), Err(EmbeddedSlipError::UnexpectedEof));
+  }
+}
@@ -0,0 +1,40 @@
+use super::*;
+use core::fmt;
+use embedded_io::{Error, ErrorKind};
+
+/// The size of the chunks read from an embedded reader at a time, kept
+/// small as it lives on the stack.
+pub(crate) const CHUNK_SIZE: usize = 64;
+
+/// An error from sending or receiving frames over `embedded_io` or
+/// `embedded_io_async`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EmbeddedSlipError<E> {
+  /// The inner reader or writer failed.
+  Io(E),
+  /// A frame was received that wasn't encoded correctly.
+  Decode(SlipError),
+  /// The inner reader ended before a complete frame was received.
+  UnexpectedEof,
+}
+
+impl<E: fmt::Debug> fmt::Display for EmbeddedSlipError<E> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      EmbeddedSlipError::Io(error) => write!(f, "io error: {:?}", error),
+      EmbeddedSlipError::Decode(error) => fmt::Display::fmt(error, f),
+      EmbeddedSlipError::UnexpectedEof => f.write_str("reader ended before a complete frame"),
+    }
+  }
+}
+
+impl<E: Error> Error for EmbeddedSlipError<E> {
+  fn kind(&self) -> ErrorKind {
+    match self {
+      EmbeddedSlipError::Io(error) => error.kind(),
+      EmbeddedSlipError::Decode(_) => ErrorKind::InvalidData,
+      EmbeddedSlipError::UnexpectedEof => ErrorKind::Other,
+    }
+  }
+}
@@ -1,10 +1,28 @@
 use super::*;
+use alloc::vec::Vec;
+
+// Every byte value, so that `for_each_encoded_run` can hand out any
+// delimiter or escape byte as a slice that outlives the payload.
+static BYTES: [u8; 256] = {
+  let mut bytes = [0; 256];
+  let mut idx = 0;
+  while idx < bytes.len() {
+    bytes[idx] = idx as u8;
+    idx += 1;
+  }
+  bytes
+};
+
+// The escape sequences of plain SLIP, handed out as a single run each.
+static ESCAPED_END: [u8; 2] = [ESC, ESC_END];
+static ESCAPED_ESC: [u8; 2] = [ESC, ESC_ESC];
 
 /// Encodes data following the SLIP protocol.
 ///
 /// Given a buffer of unencoded data, this data will be encoded following
 /// the SLIP protocol, allocated into a new `Vec<u8>`, and returned
-/// to the calling scope.
+/// to the calling scope. Anything that can be viewed as a byte slice can be
+/// passed in, such as a `Vec<u8>`, an array, or a `bytes::Bytes`.
 ///
 /// # Example:
 ///
@@ -18,28 +36,322 @@ use super::*;
 ///
 /// assert_eq!(result, expected);
 /// ```
-pub fn encode(raw_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
-  let mut encoded_buffer: Vec<u8> = vec![END];
-
-  for byte in raw_buffer {
-    match *byte {
-      END => {
-        let esc_end: [u8; 2] = [ESC, ESC_END];
-        encoded_buffer.extend(esc_end);
-      }
-      ESC => {
-        let esc_esc: [u8; 2] = [ESC, ESC_ESC];
-        encoded_buffer.extend(esc_esc);
-      }
-      _ => encoded_buffer.push(*byte),
+pub fn encode<T: AsRef<[u8]>>(raw_buffer: T) -> Result<Vec<u8>, SlipError> {
+  let mut encoded_buffer: Vec<u8> = Vec::new();
+
+  encode_append(raw_buffer.as_ref(), &mut encoded_buffer);
+
+  Ok(encoded_buffer)
+}
+
+/// Encodes data following the SLIP protocol from an iterator of bytes.
+///
+/// The bytes are encoded one at a time as the iterator yields them, so a
+/// payload produced on the fly never has to be collected into a buffer
+/// before it's encoded.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_iter;
+///
+/// let result: Vec<u8> = encode_iter((0..3).map(|idx| 0xBF + idx));
+///
+/// assert_eq!(result, [0xC0, 0xBF, 0xDB, 0xDC, 0xC1, 0xC0]);
+/// ```
+pub fn encode_iter<I: IntoIterator<Item = u8>>(raw_bytes: I) -> Vec<u8> {
+  let raw_bytes = raw_bytes.into_iter();
+  let mut encoded_buffer: Vec<u8> = Vec::with_capacity(raw_bytes.size_hint().0 + 2);
+
+  encoded_buffer.push(END);
+  for byte in raw_bytes {
+    match byte {
+      END => encoded_buffer.extend_from_slice(&[ESC, ESC_END]),
+      ESC => encoded_buffer.extend_from_slice(&[ESC, ESC_ESC]),
+      _ => encoded_buffer.push(byte),
     }
   }
-
   encoded_buffer.push(END);
 
+  encoded_buffer
+}
+
+/// Encodes data following the SLIP protocol, without aborting if the
+/// encoded frame can't be allocated.
+///
+/// This works just like `encode`, except that the exact size of the encoded
+/// frame is allocated up front with `try_reserve`, and
+/// `SlipError::AllocationFailed` is returned if that fails rather than
+/// aborting the process.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::try_encode;
+///
+/// let result: Vec<u8> = try_encode(&[0x01, 0xDB, 0x49, 0xC0, 0x15]).unwrap();
+///
+/// assert_eq!(result, [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0]);
+/// ```
+pub fn try_encode(raw_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
+  let mut encoded_buffer: Vec<u8> = Vec::new();
+  encoded_buffer
+    .try_reserve_exact(encoded_len(raw_buffer))
+    .map_err(|_| SlipError::AllocationFailed)?;
+
+  encode_append(raw_buffer, &mut encoded_buffer);
+
   Ok(encoded_buffer)
 }
 
+/// Encodes data following the SLIP protocol into a caller provided buffer.
+///
+/// Unlike `encode`, this never allocates. The encoded frame is written to
+/// the start of `encoded_buffer` and its length is returned. If the buffer
+/// is too small to hold the whole frame, `SlipError::BufferTooSmall` is
+/// returned and the contents of the buffer are unspecified.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_to_slice;
+///
+/// let input: [u8; 5] = [0x01, 0xDB, 0x49, 0xC0, 0x15];
+/// let mut output = [0u8; 16];
+///
+/// let len = encode_to_slice(&input, &mut output).unwrap();
+///
+/// assert_eq!(&output[..len], &[0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0]);
+/// assert!(encode_to_slice(&input, &mut output[..8]).is_err());
+/// ```
+pub fn encode_to_slice(raw_buffer: &[u8], encoded_buffer: &mut [u8]) -> Result<usize, SlipError> {
+  let capacity = encoded_buffer.len();
+  let mut rest = encoded_buffer;
+
+  encode_to(raw_buffer, &mut rest)?;
+
+  Ok(capacity - rest.len())
+}
+
+/// Encodes data following the SLIP protocol into any `ByteSink`.
+///
+/// The encoded frame is appended to whatever is already in the sink, and
+/// any error from the sink, such as running out of room, is returned as is.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_to;
+///
+/// let mut output: Vec<u8> = vec![0xA1];
+///
+/// encode_to(&[0x01, 0xC0], &mut output).unwrap();
+///
+/// assert_eq!(output, [0xA1, 0xC0, 0x01, 0xDB, 0xDC, 0xC0]);
+/// ```
+pub fn encode_to<S: ByteSink>(raw_buffer: &[u8], sink: &mut S) -> Result<(), S::Error> {
+  encode_with(&SlipConfig::new(), None, raw_buffer, sink)
+}
+
+// Encodes `raw_buffer` into `sink` using the bytes from `config`.
+pub(crate) fn encode_with<S: ByteSink>(
+  config: &SlipConfig,
+  prefix: Option<[u8; 2]>,
+  raw_buffer: &[u8],
+  sink: &mut S,
+) -> Result<(), S::Error> {
+  // Enough for the common case of a payload with nothing to escape, which
+  // is then copied in a single run.
+  sink.reserve(raw_buffer.len() + 2 + usize::from(config.flush_end));
+
+  if config.flush_end {
+    sink.write_bytes(&[config.end])?;
+  }
+  if config.leading_end {
+    sink.write_bytes(&[config.end])?;
+  }
+  if let Some(prefix) = prefix {
+    escape_to(config, &prefix, sink)?;
+  }
+  escape_to(config, raw_buffer, sink)?;
+  sink.write_bytes(&[config.end])?;
+
+  if let Some(block_len) = config.pad_to {
+    let fill = [config.end; 16];
+    let mut padding = (block_len - config.encoded_len(prefix, raw_buffer) % block_len) % block_len;
+    while padding > 0 {
+      let len = padding.min(fill.len());
+      sink.write_bytes(&fill[..len])?;
+      padding -= len;
+    }
+  }
+
+  Ok(())
+}
+
+/// Encodes a single frame following the SLIP protocol, whose payload is
+/// made up of several slices one after another.
+///
+/// This gives the same result as concatenating `parts` and passing that to
+/// `encode`, but the parts are escaped straight into the output, so a
+/// header, body and trailer kept in separate buffers don't have to be
+/// copied together first.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_parts;
+///
+/// let header: [u8; 2] = [0x01, 0xC0];
+/// let body: Vec<u8> = vec![0x02, 0x03];
+///
+/// let result = encode_parts(&[&header, &body, &[0xDB]]);
+///
+/// assert_eq!(result, [0xC0, 0x01, 0xDB, 0xDC, 0x02, 0x03, 0xDB, 0xDD, 0xC0]);
+/// ```
+pub fn encode_parts(parts: &[&[u8]]) -> Vec<u8> {
+  let payload_len: usize = parts.iter().map(|part| part.len()).sum();
+  let mut encoded_buffer: Vec<u8> = Vec::with_capacity(payload_len + 2);
+
+  encoded_buffer.push(END);
+  for part in parts {
+    // Appending to a `Vec` never fails.
+    let _ = escape_to(&SlipConfig::new(), part, &mut encoded_buffer);
+  }
+  encoded_buffer.push(END);
+
+  encoded_buffer
+}
+
+// Calls `emit` with each run of `raw_buffer` once encoded as a frame with
+// `config`, exactly as `encode_with` writes it, borrowing the ordinary
+// bytes straight from it. Returns `false` without emitting anything if the
+// payload can't be encoded, leaving `encode_with` to report why.
+pub(crate) fn for_each_encoded_run<'a, F>(
+  config: &SlipConfig,
+  raw_buffer: &'a [u8],
+  mut emit: F,
+) -> bool
+where
+  F: FnMut(&'a [u8]),
+{
+  if config.validate().is_err() || config.check_mtu(raw_buffer).is_err() {
+    return false;
+  }
+  let Ok(prefix) = config.length_prefix_for(raw_buffer) else {
+    return false;
+  };
+
+  if config.flush_end {
+    emit(byte_run(config.end));
+  }
+  if config.leading_end {
+    emit(byte_run(config.end));
+  }
+  for byte in prefix.into_iter().flatten() {
+    match config.find_special(&[byte]) {
+      Some(_) => escape_run(config, byte, &mut emit),
+      None => emit(byte_run(byte)),
+    }
+  }
+  for_each_escaped_run(config, raw_buffer, &mut emit);
+  emit(byte_run(config.end));
+
+  if let Some(block_len) = config.pad_to {
+    let padding = (block_len - config.encoded_len(prefix, raw_buffer) % block_len) % block_len;
+    for _ in 0..padding {
+      emit(byte_run(config.end));
+    }
+  }
+
+  true
+}
+
+// Calls `emit` with each run of `raw_buffer` once escaped with `config`,
+// but without any delimiters around it.
+pub(crate) fn for_each_escaped_run<'a, F>(config: &SlipConfig, raw_buffer: &'a [u8], mut emit: F)
+where
+  F: FnMut(&'a [u8]),
+{
+  let mut rest = raw_buffer;
+  while let Some(idx) = config.find_special(rest) {
+    if idx > 0 {
+      emit(&rest[..idx]);
+    }
+    escape_run(config, rest[idx], &mut emit);
+    rest = &rest[idx + 1..];
+  }
+
+  if !rest.is_empty() {
+    emit(rest);
+  }
+}
+
+// Calls `emit` with the escape sequence for `byte`, which must be `END` or
+// `ESC`.
+fn escape_run<'a, F: FnMut(&'a [u8])>(config: &SlipConfig, byte: u8, emit: &mut F) {
+  match config.escape(byte) {
+    [ESC, ESC_END] => emit(&ESCAPED_END),
+    [ESC, ESC_ESC] => emit(&ESCAPED_ESC),
+    [esc, escaped] => {
+      emit(byte_run(esc));
+      emit(byte_run(escaped));
+    }
+  }
+}
+
+// Returns `byte` on its own, as a slice that lives forever.
+fn byte_run(byte: u8) -> &'static [u8] {
+  let idx = usize::from(byte);
+  &BYTES[idx..idx + 1]
+}
+
+// Writes `raw_buffer` to `sink` with every special byte escaped, but
+// without any delimiters around it.
+pub(crate) fn escape_to<S: ByteSink>(
+  config: &SlipConfig,
+  raw_buffer: &[u8],
+  sink: &mut S,
+) -> Result<(), S::Error> {
+  let mut rest = raw_buffer;
+  while let Some(idx) = config.find_special(rest) {
+    sink.write_bytes(&rest[..idx])?;
+    sink.write_bytes(&config.escape(rest[idx]))?;
+    rest = &rest[idx + 1..];
+  }
+
+  sink.write_bytes(rest)
+}
+
+/// Encodes data following the SLIP protocol onto the end of an existing
+/// `Vec<u8>`.
+///
+/// Nothing already in `encoded_buffer` is touched, so a transmit buffer
+/// holding several frames can be built up one frame at a time, reusing its
+/// capacity, without allocating a new `Vec<u8>` for each frame.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_append;
+///
+/// let mut output: Vec<u8> = Vec::with_capacity(16);
+///
+/// encode_append(&[0x01], &mut output);
+/// encode_append(&[0xC0], &mut output);
+///
+/// assert_eq!(output, [0xC0, 0x01, 0xC0, 0xC0, 0xDB, 0xDC, 0xC0]);
+/// ```
+pub fn encode_append(raw_buffer: &[u8], encoded_buffer: &mut Vec<u8>) {
+  // Appending to a `Vec` never fails.
+  let _ = encode_to(raw_buffer, encoded_buffer);
+}
+
+// Returns the exact length of `raw_buffer` once encoded as a frame.
+pub(crate) fn encoded_len(raw_buffer: &[u8]) -> usize {
+  SlipConfig::new().encoded_len(None, raw_buffer)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -50,5 +362,39 @@ mod tests {
 
     let res: Vec<u8> = encode(&input).unwrap();
     assert_eq!(res, expected);
+    assert_eq!(encode(input.clone()).unwrap(), expected);
+    assert_eq!(encode_iter(input), expected);
+  }
+
+  #[test]
+  fn encodes_to_slice_without_allocating() {
+    let input: [u8; 5] = [0x01, ESC, 0x49, END, 0x15];
+    let expected = encode(input).unwrap();
+    let mut output = [0u8; 9];
+
+    assert_eq!(encode_to_slice(&input, &mut output), Ok(9));
+    assert_eq!(output.as_slice(), expected);
+    assert_eq!(
+      encode_to_slice(&input, &mut output[..8]),
+      Err(SlipError::BufferTooSmall)
+    );
+  }
+
+  #[test]
+  fn encodes_parts_like_their_concatenation() {
+    let parts: [&[u8]; 4] = [&[0x01, END], &[], &[ESC, 0x02], &[END]];
+
+    assert_eq!(encode_parts(&parts), encode(parts.concat()).unwrap());
+    assert_eq!(encode_parts(&[]), [END, END]);
+  }
+
+  #[test]
+  fn try_encode_allocates_exactly() {
+    let input: [u8; 5] = [0x01, ESC, 0x49, END, 0x15];
+
+    let result = try_encode(&input).unwrap();
+
+    assert_eq!(result, encode(input).unwrap());
+    assert_eq!(result.capacity(), result.len());
   }
 }
@@ -1,18 +1,307 @@
-use std::fmt;
+use core::fmt;
 
 // pub type Result<T> = std::result::Result<T, self::Error>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum SlipError {
   NoEndDelimiter,
-  InvalidEncoding,
+  /// An `ESC` byte was the last byte of a frame, with nothing to escape.
+  TruncatedEscape,
+  /// An `ESC` byte was followed by `found`, which isn't `ESC_END` or
+  /// `ESC_ESC`.
+  InvalidEscapeByte {
+    found: u8,
+  },
+  FrameTooLarge,
+  /// A frame had no bytes between its delimiters, and empty frames are
+  /// treated as errors.
+  EmptyFrame,
+  /// A frame's checksum didn't match its payload, or it was too short to
+  /// hold one.
+  ChecksumMismatch,
+  /// A `PayloadTransform` couldn't apply or revert itself, such as when a
+  /// payload fails to decrypt or decompress.
+  TransformFailed,
+  /// A fragment's header was cut short, or its index was out of range, so
+  /// it couldn't be reassembled.
+  InvalidFragment,
+  /// The header a protocol layered on top of frames puts at the start of
+  /// each payload, such as `ReliableSlip`'s, was cut short or invalid.
+  InvalidHeader,
+  /// Nothing was received from the other side within the `Keepalive`
+  /// timeout, not even a heartbeat.
+  LinkDown,
+  /// A frame's length prefix didn't match the length of its payload, or
+  /// it was too short to hold one, so part of it was lost or another frame
+  /// ran into it.
+  LengthMismatch,
+  /// A `SlipConfig` used the same byte for two of `END`, `ESC`, `ESC_END`
+  /// and `ESC_ESC`, so its frames couldn't be decoded.
+  InvalidConfig,
+  BufferTooSmall,
+  QueueFull,
+  AllocationFailed,
+  MtuExceeded,
 }
 
 impl fmt::Display for SlipError {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     f.write_str(match self {
       SlipError::NoEndDelimiter => "no 'END' (0xCO) delimiter byte found in buffer",
-      SlipError::InvalidEncoding => "buffer not encoded to SLIP protocol",
+      SlipError::TruncatedEscape => "frame ends with an unfinished escape sequence",
+      SlipError::InvalidEscapeByte { .. } => "invalid byte following an 'ESC' (0xDB) byte",
+      SlipError::FrameTooLarge => "frame exceeds the maximum frame size",
+      SlipError::EmptyFrame => "frame is empty",
+      SlipError::BufferTooSmall => "output buffer is too small",
+      SlipError::QueueFull => "frame queue is full",
+      SlipError::AllocationFailed => "failed to allocate memory for the frame",
+      SlipError::MtuExceeded => "payload exceeds the MTU",
+      SlipError::ChecksumMismatch => "frame checksum doesn't match its payload",
+      SlipError::TransformFailed => "payload transform failed",
+      SlipError::InvalidFragment => "fragment header is invalid",
+      SlipError::InvalidHeader => "payload header is invalid",
+      SlipError::LinkDown => "nothing received from the link within the keepalive timeout",
+      SlipError::LengthMismatch => "frame length prefix doesn't match its payload",
+      SlipError::InvalidConfig => "config uses the same byte for two roles",
     })
   }
 }
+
+impl core::error::Error for SlipError {}
+
+impl SlipError {
+  /// Returns a stable numeric code for the error, for passing it across an
+  /// FFI boundary or matching it in logs.
+  ///
+  /// The low byte identifies the variant, and never changes once assigned,
+  /// while the high byte holds the `found` byte of an `InvalidEscapeByte`
+  /// error. Codes start from 1, leaving 0 to mean success. The codes are:
+  ///
+  /// | Code | Variant             |
+  /// |------|---------------------|
+  /// | 1    | `NoEndDelimiter`    |
+  /// | 2    | `TruncatedEscape`   |
+  /// | 3    | `InvalidEscapeByte` |
+  /// | 4    | `FrameTooLarge`     |
+  /// | 5    | `EmptyFrame`        |
+  /// | 6    | `BufferTooSmall`    |
+  /// | 7    | `QueueFull`         |
+  /// | 8    | `AllocationFailed`  |
+  /// | 9    | `MtuExceeded`       |
+  /// | 10   | `ChecksumMismatch`  |
+  /// | 11   | `TransformFailed`   |
+  /// | 12   | `InvalidFragment`   |
+  /// | 13   | `InvalidHeader`     |
+  /// | 14   | `LinkDown`          |
+  /// | 15   | `LengthMismatch`    |
+  /// | 16   | `InvalidConfig`     |
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipError;
+  ///
+  /// let error = SlipError::InvalidEscapeByte { found: 0x02 };
+  ///
+  /// assert_eq!(error.code(), 0x0203);
+  /// assert_eq!(error.code() & 0xFF, 3);
+  /// assert_eq!(SlipError::from_code(0x0203), Some(error));
+  /// assert_eq!(SlipError::from_code(0), None);
+  /// ```
+  pub const fn code(&self) -> u16 {
+    match self {
+      SlipError::NoEndDelimiter => 1,
+      SlipError::TruncatedEscape => 2,
+      SlipError::InvalidEscapeByte { found } => 3 | (*found as u16) << 8,
+      SlipError::FrameTooLarge => 4,
+      SlipError::EmptyFrame => 5,
+      SlipError::BufferTooSmall => 6,
+      SlipError::QueueFull => 7,
+      SlipError::AllocationFailed => 8,
+      SlipError::MtuExceeded => 9,
+      SlipError::ChecksumMismatch => 10,
+      SlipError::TransformFailed => 11,
+      SlipError::InvalidFragment => 12,
+      SlipError::InvalidHeader => 13,
+      SlipError::LinkDown => 14,
+      SlipError::LengthMismatch => 15,
+      SlipError::InvalidConfig => 16,
+    }
+  }
+
+  /// Returns the error with the given `code`, as returned by `code`, or
+  /// `None` if it isn't a known code.
+  pub const fn from_code(code: u16) -> Option<Self> {
+    let [variant, found] = code.to_le_bytes();
+    let error = match variant {
+      3 => SlipError::InvalidEscapeByte { found },
+      _ if found != 0 => return None,
+      1 => SlipError::NoEndDelimiter,
+      2 => SlipError::TruncatedEscape,
+      4 => SlipError::FrameTooLarge,
+      5 => SlipError::EmptyFrame,
+      6 => SlipError::BufferTooSmall,
+      7 => SlipError::QueueFull,
+      8 => SlipError::AllocationFailed,
+      9 => SlipError::MtuExceeded,
+      10 => SlipError::ChecksumMismatch,
+      11 => SlipError::TransformFailed,
+      12 => SlipError::InvalidFragment,
+      13 => SlipError::InvalidHeader,
+      14 => SlipError::LinkDown,
+      15 => SlipError::LengthMismatch,
+      16 => SlipError::InvalidConfig,
+      _ => return None,
+    };
+
+    Some(error)
+  }
+
+  /// Returns the error for an `ESC` byte followed by `found`, or by the
+  /// end of the frame if that's `None` or the `END` byte.
+  pub(crate) fn invalid_escape(found: Option<u8>, end: u8) -> Self {
+    match found {
+      Some(found) if found != end => SlipError::InvalidEscapeByte { found },
+      _ => SlipError::TruncatedEscape,
+    }
+  }
+
+  // Returns `true` for the errors that make a frame malformed, as handled
+  // by `MalformedPolicy`.
+  pub(crate) fn is_malformed(&self) -> bool {
+    matches!(
+      self,
+      SlipError::TruncatedEscape | SlipError::InvalidEscapeByte { .. }
+    )
+  }
+}
+
+/// A `SlipError` from decoding, along with where in the input it happened.
+///
+/// Returned by `decode_detailed`, so corruption in a large buffer can be
+/// traced back to the exact byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DecodeError {
+  /// What went wrong.
+  pub error: SlipError,
+  /// The offset into the input of the byte that couldn't be decoded, or
+  /// the length of the input if it ended too soon.
+  pub offset: usize,
+  /// The byte at `offset`, if there is one.
+  pub byte: Option<u8>,
+}
+
+impl fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.byte {
+      Some(byte) => write!(
+        f,
+        "{} at offset {} (byte 0x{:02X})",
+        self.error, self.offset, byte
+      ),
+      None => write!(f, "{} at offset {}", self.error, self.offset),
+    }
+  }
+}
+
+impl core::error::Error for DecodeError {
+  fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+    Some(&self.error)
+  }
+}
+
+impl From<DecodeError> for SlipError {
+  fn from(error: DecodeError) -> Self {
+    error.error
+  }
+}
+
+#[cfg(feature = "std")]
+impl From<SlipError> for std::io::Error {
+  fn from(error: SlipError) -> Self {
+    use std::io::ErrorKind;
+
+    let kind = match error {
+      SlipError::NoEndDelimiter => ErrorKind::UnexpectedEof,
+      SlipError::TruncatedEscape
+      | SlipError::InvalidEscapeByte { .. }
+      | SlipError::FrameTooLarge
+      | SlipError::EmptyFrame
+      | SlipError::ChecksumMismatch
+      | SlipError::TransformFailed
+      | SlipError::InvalidFragment
+      | SlipError::InvalidHeader
+      | SlipError::LengthMismatch => ErrorKind::InvalidData,
+      SlipError::BufferTooSmall => ErrorKind::WriteZero,
+      SlipError::QueueFull => ErrorKind::WouldBlock,
+      SlipError::AllocationFailed => ErrorKind::OutOfMemory,
+      SlipError::MtuExceeded | SlipError::InvalidConfig => ErrorKind::InvalidInput,
+      SlipError::LinkDown => ErrorKind::NotConnected,
+    };
+
+    std::io::Error::new(kind, error)
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use std::io;
+
+  fn decode_boxed(input: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(crate::decode(input)?)
+  }
+
+  fn decode_io(input: &[u8]) -> io::Result<Vec<u8>> {
+    Ok(crate::decode(input)?)
+  }
+
+  #[test]
+  fn round_trips_codes() {
+    let errors = [
+      SlipError::NoEndDelimiter,
+      SlipError::TruncatedEscape,
+      SlipError::InvalidEscapeByte { found: 0x00 },
+      SlipError::InvalidEscapeByte { found: 0xFF },
+      SlipError::FrameTooLarge,
+      SlipError::EmptyFrame,
+      SlipError::BufferTooSmall,
+      SlipError::QueueFull,
+      SlipError::AllocationFailed,
+      SlipError::MtuExceeded,
+      SlipError::ChecksumMismatch,
+      SlipError::TransformFailed,
+      SlipError::InvalidFragment,
+      SlipError::InvalidHeader,
+      SlipError::LinkDown,
+      SlipError::LengthMismatch,
+      SlipError::InvalidConfig,
+    ];
+
+    for error in errors {
+      assert_eq!(SlipError::from_code(error.code()), Some(error));
+    }
+    assert_eq!(SlipError::from_code(17), None);
+    assert_eq!(SlipError::from_code(0x0101), None);
+  }
+
+  #[test]
+  fn converts_with_question_mark() {
+    let error = decode_boxed(&[0x01]).unwrap_err();
+    assert_eq!(
+      error.downcast_ref::<SlipError>(),
+      Some(&SlipError::NoEndDelimiter)
+    );
+
+    let error = decode_io(&[0xC0, 0xDB, 0x01]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+      error.get_ref().and_then(|error| error.downcast_ref()),
+      Some(&SlipError::InvalidEscapeByte { found: 0x01 })
+    );
+  }
+}
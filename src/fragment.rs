@@ -0,0 +1,296 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// The length of the header at the start of each fragment: a 2-byte
+/// big-endian message id, then the fragment's index, then the number of
+/// fragments in the message.
+pub const FRAGMENT_HEADER_LEN: usize = 4;
+
+/// Splits payloads larger than the link MTU into numbered fragments, to be
+/// put back together by a `Reassembler`.
+///
+/// Each fragment starts with a `FRAGMENT_HEADER_LEN` byte header, and is
+/// at most `mtu` bytes long including it, so it can be sent as a frame of
+/// its own. Every payload is fragmented, even one that fits in a single
+/// fragment, so the receiver can always expect a header. A message can be
+/// split into at most 255 fragments.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{encode, Fragmenter};
+///
+/// let mut fragmenter = Fragmenter::new(8);
+///
+/// let fragments = fragmenter.fragment(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]).unwrap();
+/// assert_eq!(
+///   fragments,
+///   [
+///     vec![0x00, 0x00, 0x00, 0x02, 0x01, 0x02, 0x03, 0x04],
+///     vec![0x00, 0x00, 0x01, 0x02, 0x05, 0x06],
+///   ]
+/// );
+///
+/// let frame = encode(&fragments[1]).unwrap();
+/// assert_eq!(frame, [0xC0, 0x00, 0x00, 0x01, 0x02, 0x05, 0x06, 0xC0]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragmenter {
+  mtu: usize,
+  next_id: u16,
+}
+
+impl Fragmenter {
+  /// Creates a fragmenter for a link that carries frames of up to `mtu`
+  /// bytes, such as `RFC_MTU`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `mtu` isn't larger than `FRAGMENT_HEADER_LEN`.
+  pub fn new(mtu: usize) -> Self {
+    assert!(
+      mtu > FRAGMENT_HEADER_LEN,
+      "MTU must leave room for the fragment header"
+    );
+
+    Self { mtu, next_id: 0 }
+  }
+
+  /// Splits `payload` into fragments, each ready to be encoded as a frame.
+  ///
+  /// Each call uses the next message id, wrapping around after 65535.
+  /// Returns `SlipError::MtuExceeded` if the payload needs more than 255
+  /// fragments.
+  pub fn fragment(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>, SlipError> {
+    let chunk_len = self.mtu - FRAGMENT_HEADER_LEN;
+    let count = payload.len().div_ceil(chunk_len).max(1);
+    let count = u8::try_from(count).map_err(|_| SlipError::MtuExceeded)?;
+
+    let [id_high, id_low] = self.next_id.to_be_bytes();
+    self.next_id = self.next_id.wrapping_add(1);
+
+    let fragments = (0..count)
+      .map(|index| {
+        let start = usize::from(index) * chunk_len;
+        let chunk = &payload[start..payload.len().min(start + chunk_len)];
+
+        let mut fragment = Vec::with_capacity(FRAGMENT_HEADER_LEN + chunk.len());
+        fragment.extend_from_slice(&[id_high, id_low, index, count]);
+        fragment.extend_from_slice(chunk);
+        fragment
+      })
+      .collect();
+
+    Ok(fragments)
+  }
+}
+
+/// Puts the fragments made by a `Fragmenter` back together into payloads.
+///
+/// Fragments can arrive in any order, and fragments of several messages can
+/// be interleaved. Time is measured by a `now` timestamp passed in by the
+/// caller, in whatever monotonic unit it likes, such as milliseconds since
+/// boot, so this works without `std`. A message still missing fragments
+/// `timeout` after its first one arrived is dropped, and so is the oldest
+/// message if a new one arrives while `max_pending` are already waiting.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{Fragmenter, Reassembler};
+///
+/// let mut fragmenter = Fragmenter::new(8);
+/// let mut reassembler = Reassembler::new(100);
+///
+/// let fragments = fragmenter.fragment(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]).unwrap();
+///
+/// assert_eq!(reassembler.push(&fragments[1], 0), Ok(None));
+/// assert_eq!(
+///   reassembler.push(&fragments[0], 50),
+///   Ok(Some(vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06]))
+/// );
+///
+/// // Arrives too late to be put back together.
+/// let fragments = fragmenter.fragment(&[0x07, 0x08, 0x09, 0x0A, 0x0B]).unwrap();
+/// assert_eq!(reassembler.push(&fragments[0], 100), Ok(None));
+/// assert_eq!(reassembler.expire(200), 1);
+/// assert_eq!(reassembler.push(&fragments[1], 200), Ok(None));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reassembler {
+  timeout: u64,
+  max_pending: usize,
+  pending: Vec<PartialMessage>,
+}
+
+// A message with some of its fragments received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PartialMessage {
+  id: u16,
+  started: u64,
+  received: usize,
+  fragments: Vec<Option<Vec<u8>>>,
+}
+
+impl Reassembler {
+  /// Creates a reassembler that drops messages still incomplete `timeout`
+  /// after their first fragment, with up to 8 messages pending at once.
+  pub fn new(timeout: u64) -> Self {
+    Self {
+      timeout,
+      max_pending: 8,
+      pending: Vec::new(),
+    }
+  }
+
+  /// Sets how many incomplete messages can be pending at once.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `max_pending` is 0.
+  pub fn max_pending(mut self, max_pending: usize) -> Self {
+    assert!(max_pending > 0, "must allow at least one pending message");
+    self.max_pending = max_pending;
+    self
+  }
+
+  /// Adds a fragment received at `now`, returning the whole payload if it
+  /// was the last one missing.
+  ///
+  /// Messages that have timed out are dropped first, as by `expire`. A
+  /// fragment that was already received is ignored. Returns
+  /// `SlipError::InvalidFragment` if the fragment's header is cut short or
+  /// its index is out of range.
+  pub fn push(&mut self, fragment: &[u8], now: u64) -> Result<Option<Vec<u8>>, SlipError> {
+    let (header, chunk) = fragment
+      .split_first_chunk::<FRAGMENT_HEADER_LEN>()
+      .ok_or(SlipError::InvalidFragment)?;
+    let [id_high, id_low, index, count] = *header;
+    if index >= count {
+      return Err(SlipError::InvalidFragment);
+    }
+
+    self.expire(now);
+    if count == 1 {
+      return Ok(Some(chunk.to_vec()));
+    }
+
+    let id = u16::from_be_bytes([id_high, id_low]);
+    let position = match self.pending.iter().position(|message| message.id == id) {
+      // The id has wrapped around onto a new message.
+      Some(position) if self.pending[position].fragments.len() != usize::from(count) => {
+        self.pending.remove(position);
+        None
+      }
+      position => position,
+    };
+    let position = position.unwrap_or_else(|| {
+      if self.pending.len() == self.max_pending {
+        self.pending.remove(0);
+      }
+      self.pending.push(PartialMessage {
+        id,
+        started: now,
+        received: 0,
+        fragments: alloc::vec![None; usize::from(count)],
+      });
+      self.pending.len() - 1
+    });
+
+    let message = &mut self.pending[position];
+    let slot = &mut message.fragments[usize::from(index)];
+    if slot.is_none() {
+      *slot = Some(chunk.to_vec());
+      message.received += 1;
+    }
+    if message.received < message.fragments.len() {
+      return Ok(None);
+    }
+
+    let message = self.pending.remove(position);
+    Ok(Some(
+      message.fragments.into_iter().flatten().flatten().collect(),
+    ))
+  }
+
+  /// Drops the messages still incomplete `timeout` after their first
+  /// fragment arrived, returning how many were dropped.
+  pub fn expire(&mut self, now: u64) -> usize {
+    let before = self.pending.len();
+    let timeout = self.timeout;
+    self
+      .pending
+      .retain(|message| now.saturating_sub(message.started) < timeout);
+
+    before - self.pending.len()
+  }
+
+  /// Returns the number of messages waiting for more fragments.
+  pub fn pending(&self) -> usize {
+    self.pending.len()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reassembles_interleaved_messages() {
+    let mut fragmenter = Fragmenter::new(FRAGMENT_HEADER_LEN + 3);
+    let mut reassembler = Reassembler::new(10);
+
+    let first: Vec<u8> = (0..10).collect();
+    let second: Vec<u8> = (10..17).collect();
+    let mut first_fragments = fragmenter.fragment(&first).unwrap();
+    let second_fragments = fragmenter.fragment(&second).unwrap();
+    assert_eq!(first_fragments.len(), 4);
+    assert!(first_fragments
+      .iter()
+      .all(|fragment| fragment.len() <= FRAGMENT_HEADER_LEN + 3));
+    first_fragments.reverse();
+
+    let mut payloads = Vec::new();
+    for (first, second) in first_fragments.iter().zip(&second_fragments) {
+      payloads.extend(reassembler.push(first, 0).unwrap());
+      payloads.extend(reassembler.push(first, 0).unwrap());
+      payloads.extend(reassembler.push(second, 0).unwrap());
+    }
+    payloads.extend(reassembler.push(&first_fragments[3], 0).unwrap());
+
+    assert_eq!(payloads, [second, first]);
+    assert_eq!(reassembler.pending(), 0);
+    assert_eq!(
+      fragmenter.fragment(&[]).unwrap(),
+      [[0x00, 0x02, 0x00, 0x01]]
+    );
+    assert_eq!(
+      Fragmenter::new(5).fragment(&[0x00; 256]),
+      Err(SlipError::MtuExceeded)
+    );
+  }
+
+  #[test]
+  fn drops_stale_and_excess_messages() {
+    let mut fragmenter = Fragmenter::new(5);
+    let mut reassembler = Reassembler::new(10).max_pending(1);
+
+    let old = fragmenter.fragment(&[0x01, 0x02]).unwrap();
+    let new = fragmenter.fragment(&[0x03, 0x04]).unwrap();
+    assert_eq!(reassembler.push(&old[0], 0), Ok(None));
+    assert_eq!(reassembler.push(&new[0], 5), Ok(None));
+    assert_eq!(reassembler.push(&old[1], 5), Ok(None));
+    assert_eq!(reassembler.push(&new[1], 14), Ok(None));
+    assert_eq!(reassembler.expire(24), 1);
+    assert_eq!(reassembler.pending(), 0);
+
+    assert_eq!(
+      reassembler.push(&[0x00, 0x00, 0x02], 0),
+      Err(SlipError::InvalidFragment)
+    );
+    assert_eq!(
+      reassembler.push(&[0x00, 0x00, 0x02, 0x02], 0),
+      Err(SlipError::InvalidFragment)
+    );
+  }
+}
@@ -0,0 +1,260 @@
+use super::*;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug)]
+struct Slot<const N: usize> {
+  frame: [u8; N],
+  len: usize,
+}
+
+/// A fixed-capacity single-producer, single-consumer queue of frames.
+///
+/// The queue holds up to `FRAMES` frames of up to `N` bytes each, in inline
+/// buffers, so it never allocates and can be placed in a `static`. Once
+/// split into a `FrameProducer` and a `FrameConsumer`, an interrupt handler
+/// can push decoded frames while the idle loop pops them, without any
+/// locks or critical sections, as the two halves only coordinate through a
+/// pair of atomic indices.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{SlipFrameQueue, StaticSlipDecoder};
+///
+/// let mut queue: SlipFrameQueue<16, 4> = SlipFrameQueue::new();
+/// let (mut producer, mut consumer) = queue.split();
+///
+/// // In the UART interrupt handler:
+/// let mut decoder: StaticSlipDecoder<16> = StaticSlipDecoder::new();
+/// for byte in [0xC0, 0x01, 0xDB, 0xDC, 0xC0] {
+///   if let Some(Ok(frame)) = decoder.push_byte(byte) {
+///     producer.push(frame).unwrap();
+///   }
+/// }
+///
+/// // In the idle loop:
+/// assert_eq!(consumer.pop_with(|frame| frame.to_vec()), Some(vec![0x01, 0xC0]));
+/// assert_eq!(consumer.pop_with(|frame| frame.to_vec()), None);
+/// ```
+#[derive(Debug)]
+pub struct SlipFrameQueue<const N: usize, const FRAMES: usize> {
+  slots: [UnsafeCell<Slot<N>>; FRAMES],
+  // Both indices count up to `2 * FRAMES` before wrapping around to 0, so
+  // they stay apart by the number of frames waiting even when `FRAMES`
+  // isn't a power of two, and are reduced modulo `FRAMES` to find a slot.
+  // Only the consumer writes `head` and only the producer writes `tail`.
+  head: AtomicUsize,
+  tail: AtomicUsize,
+}
+
+// SAFETY: A slot is only ever accessed by the producer while it's outside
+// `head..tail`, and by the consumer while it's inside, and `split` borrows
+// the queue mutably so there's only one of each.
+unsafe impl<const N: usize, const FRAMES: usize> Sync for SlipFrameQueue<N, FRAMES> {}
+
+impl<const N: usize, const FRAMES: usize> SlipFrameQueue<N, FRAMES> {
+  /// Creates a new, empty queue.
+  ///
+  /// A queue with room for no frames at all doesn't compile.
+  pub const fn new() -> Self {
+    const { assert!(FRAMES > 0, "the queue must have room for a frame") };
+
+    Self {
+      slots: [const {
+        UnsafeCell::new(Slot {
+          frame: [0; N],
+          len: 0,
+        })
+      }; FRAMES],
+      head: AtomicUsize::new(0),
+      tail: AtomicUsize::new(0),
+    }
+  }
+
+  /// Splits the queue into its producer and consumer halves.
+  pub fn split(&mut self) -> (FrameProducer<'_, N, FRAMES>, FrameConsumer<'_, N, FRAMES>) {
+    let queue: &Self = self;
+
+    (FrameProducer { queue }, FrameConsumer { queue })
+  }
+
+  fn len(&self) -> usize {
+    let tail = self.tail.load(Ordering::Acquire);
+    let head = self.head.load(Ordering::Acquire);
+
+    (tail + 2 * FRAMES - head) % (2 * FRAMES)
+  }
+}
+
+// Returns the index after `index`, wrapping around at `2 * FRAMES`.
+fn next_index<const FRAMES: usize>(index: usize) -> usize {
+  (index + 1) % (2 * FRAMES)
+}
+
+impl<const N: usize, const FRAMES: usize> Default for SlipFrameQueue<N, FRAMES> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// The pushing half of a `SlipFrameQueue`, created by `split`.
+#[derive(Debug)]
+pub struct FrameProducer<'a, const N: usize, const FRAMES: usize> {
+  queue: &'a SlipFrameQueue<N, FRAMES>,
+}
+
+impl<const N: usize, const FRAMES: usize> FrameProducer<'_, N, FRAMES> {
+  /// Copies `frame` onto the back of the queue.
+  ///
+  /// Returns `SlipError::FrameTooLarge` if the frame is longer than `N`
+  /// bytes, and `SlipError::QueueFull` if there are already `FRAMES` frames
+  /// waiting to be popped.
+  pub fn push(&mut self, frame: &[u8]) -> Result<(), SlipError> {
+    if frame.len() > N {
+      return Err(SlipError::FrameTooLarge);
+    } else if self.is_full() {
+      return Err(SlipError::QueueFull);
+    }
+
+    let tail = self.queue.tail.load(Ordering::Relaxed);
+    // SAFETY: The queue isn't full, so the consumer won't touch this slot
+    // until `tail` is advanced past it below.
+    let slot = unsafe { &mut *self.queue.slots[tail % FRAMES].get() };
+    slot.frame[..frame.len()].copy_from_slice(frame);
+    slot.len = frame.len();
+
+    self
+      .queue
+      .tail
+      .store(next_index::<FRAMES>(tail), Ordering::Release);
+    Ok(())
+  }
+
+  /// Returns `true` if there's no room for another frame.
+  pub fn is_full(&self) -> bool {
+    self.queue.len() >= FRAMES
+  }
+}
+
+/// The popping half of a `SlipFrameQueue`, created by `split`.
+#[derive(Debug)]
+pub struct FrameConsumer<'a, const N: usize, const FRAMES: usize> {
+  queue: &'a SlipFrameQueue<N, FRAMES>,
+}
+
+impl<const N: usize, const FRAMES: usize> FrameConsumer<'_, N, FRAMES> {
+  /// Removes the frame at the front of the queue, passing it to `f`.
+  ///
+  /// The frame is borrowed straight from the queue's slot, which is only
+  /// handed back to the producer once `f` returns.
+  pub fn pop_with<F, R>(&mut self, f: F) -> Option<R>
+  where
+    F: FnOnce(&[u8]) -> R,
+  {
+    if self.is_empty() {
+      return None;
+    }
+
+    let head = self.queue.head.load(Ordering::Relaxed);
+    // SAFETY: The queue isn't empty, so the producer won't touch this slot
+    // until `head` is advanced past it below.
+    let slot = unsafe { &*self.queue.slots[head % FRAMES].get() };
+    let result = f(&slot.frame[..slot.len]);
+
+    self
+      .queue
+      .head
+      .store(next_index::<FRAMES>(head), Ordering::Release);
+    Some(result)
+  }
+
+  /// Returns the number of frames waiting to be popped.
+  pub fn len(&self) -> usize {
+    self.queue.len()
+  }
+
+  /// Returns `true` if there are no frames waiting to be popped.
+  pub fn is_empty(&self) -> bool {
+    self.queue.len() == 0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rejects_frames_when_full_or_too_large() {
+    let mut queue: SlipFrameQueue<2, 2> = SlipFrameQueue::new();
+    let (mut producer, mut consumer) = queue.split();
+
+    assert_eq!(
+      producer.push(&[0x01, 0x02, 0x03]),
+      Err(SlipError::FrameTooLarge)
+    );
+    assert_eq!(producer.push(&[0x01]), Ok(()));
+    assert_eq!(producer.push(&[0x02, 0x03]), Ok(()));
+    assert_eq!(producer.push(&[0x04]), Err(SlipError::QueueFull));
+    assert_eq!(consumer.len(), 2);
+
+    assert_eq!(consumer.pop_with(|frame| frame.to_vec()), Some(vec![0x01]));
+    assert_eq!(producer.push(&[0x04]), Ok(()));
+    assert_eq!(
+      consumer.pop_with(|frame| frame.to_vec()),
+      Some(vec![0x02, 0x03])
+    );
+    assert_eq!(consumer.pop_with(|frame| frame.to_vec()), Some(vec![0x04]));
+    assert!(consumer.is_empty());
+  }
+
+  #[test]
+  fn wraps_indices_around() {
+    let mut queue: SlipFrameQueue<1, 3> = SlipFrameQueue::new();
+    queue.head = AtomicUsize::new(5);
+    queue.tail = AtomicUsize::new(5);
+    let (mut producer, mut consumer) = queue.split();
+
+    for value in 0..12 {
+      assert_eq!(producer.push(&[value]), Ok(()));
+      assert_eq!(producer.push(&[value + 100]), Ok(()));
+      assert_eq!(consumer.len(), 2);
+      assert_eq!(consumer.pop_with(|frame| frame[0]), Some(value));
+      assert_eq!(consumer.pop_with(|frame| frame[0]), Some(value + 100));
+    }
+    assert!(consumer.is_empty());
+
+    for value in 0..3 {
+      assert_eq!(producer.push(&[value]), Ok(()));
+    }
+    assert!(producer.is_full());
+    assert_eq!(consumer.pop_with(|frame| frame[0]), Some(0));
+  }
+
+  #[test]
+  fn passes_frames_between_threads() {
+    let mut queue: SlipFrameQueue<4, 3> = SlipFrameQueue::new();
+    let (mut producer, mut consumer) = queue.split();
+    let count: u32 = 10_000;
+
+    std::thread::scope(|scope| {
+      scope.spawn(move || {
+        for value in 0..count {
+          while producer.push(&value.to_le_bytes()).is_err() {
+            std::thread::yield_now();
+          }
+        }
+      });
+
+      for value in 0..count {
+        let frame = loop {
+          if let Some(frame) = consumer.pop_with(|frame| frame.to_vec()) {
+            break frame;
+          }
+          std::thread::yield_now();
+        };
+        assert_eq!(frame, value.to_le_bytes());
+      }
+    });
+  }
+}
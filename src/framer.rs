@@ -0,0 +1,78 @@
+use super::*;
+
+/// A framing algorithm, which turns payloads into frames, and creates the
+/// `FrameDecoder` that finds the payloads again in a stream of bytes.
+///
+/// It's implemented by framing profiles, which hold no decoding state, so
+/// encoding never carries a decoder around: `SlipConfig`, which frames
+/// with SLIP or whatever profile it picks, such as `SlipConfig::hdlc`,
+/// `Cobs`, and `Slip6` with the `slip6` feature. `SlipWriter`,
+/// `SlipConnection` and `SlipCodec` take the framing as a type parameter,
+/// defaulting to SLIP, so code written against them can switch framings
+/// without any other changes.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{Cobs, FrameDecoder, Framer, SlipConfig};
+///
+/// fn round_trip<F: Framer>(framer: F, payload: &[u8]) -> Vec<u8> {
+///   let mut encoded: Vec<u8> = Vec::new();
+///   framer.encode_frame(payload, &mut encoded).unwrap();
+///   assert!(encoded.len() <= payload.len() + framer.max_overhead(payload.len()));
+///
+///   let mut decoder = framer.decoder();
+///   encoded
+///     .iter()
+///     .find_map(|byte| decoder.push_decode(*byte).map(|frame| frame.unwrap().to_vec()))
+///     .unwrap()
+/// }
+///
+/// assert_eq!(round_trip(SlipConfig::new(), &[0x00, 0xC0]), [0x00, 0xC0]);
+/// assert_eq!(round_trip(Cobs, &[0x00, 0xC0]), [0x00, 0xC0]);
+/// ```
+pub trait Framer {
+  /// The decoder for frames in this framing.
+  type Decoder: FrameDecoder;
+
+  /// Encodes `payload` as a single frame into any `ByteSink`.
+  fn encode_frame<S>(&self, payload: &[u8], sink: &mut S) -> Result<(), S::Error>
+  where
+    S: ByteSink,
+    S::Error: From<SlipError>;
+
+  /// Returns the most bytes that encoding a payload of `payload_len` bytes
+  /// can add to it, delimiters included, so buffers can be sized up front.
+  fn max_overhead(&self, payload_len: usize) -> usize;
+
+  /// Creates a decoder for frames in this framing, with nothing buffered.
+  fn decoder(&self) -> Self::Decoder;
+
+  /// Encodes `payload` as a single frame by calling `emit` with each run of
+  /// it in order, borrowing the unescaped bytes straight from `payload`, so
+  /// it can be written with `Write::write_vectored` without being copied.
+  ///
+  /// Returns `false` without calling `emit` if the framing can't be split
+  /// up like this, which is the default, or if `payload` can't be encoded,
+  /// so `encode_frame` has to be used instead.
+  fn encode_runs<'a, E>(&self, payload: &'a [u8], emit: E) -> bool
+  where
+    E: FnMut(&'a [u8]),
+  {
+    let _ = (payload, emit);
+    false
+  }
+}
+
+/// Finds the payloads framed by a `Framer` in a stream of bytes, a byte at
+/// a time.
+///
+/// It's implemented by `SlipDecoder`, `CobsDecoder` and `Slip6Decoder`, as
+/// created by `Framer::decoder`. `SlipReader` takes the decoder as a type
+/// parameter, so one set up with a limit, like `SlipDecoder::with_limit`,
+/// can be given to it directly.
+pub trait FrameDecoder {
+  /// Pushes a single byte of encoded data, returning the frame it
+  /// completes, which stays borrowed until the next byte is pushed.
+  fn push_decode(&mut self, byte: u8) -> Option<Result<&[u8], SlipError>>;
+}
@@ -0,0 +1,128 @@
+use super::*;
+use crate::async_core::{FrameReader, FrameWriter};
+use futures_io::{AsyncRead, AsyncWrite};
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+
+/// A SLIP framed connection over any `futures_io::AsyncRead` and
+/// `futures_io::AsyncWrite`.
+///
+/// This is the counterpart of `SlipStream` for runtimes like async-std and
+/// smol that use the `futures_io` traits rather than tokio's, and shares
+/// the same framing logic.
+///
+/// # Example:
+///
+/// ```rust
+/// use futures::executor::block_on;
+/// use futures::io::Cursor;
+/// use simple_slip::FuturesSlipStream;
+///
+/// let mut stream = FuturesSlipStream::new(Cursor::new(Vec::new()));
+///
+/// block_on(stream.send(&[0x01, 0xC0])).unwrap();
+/// stream.get_mut().set_position(0);
+///
+/// assert_eq!(block_on(stream.next_frame()).unwrap(), Some(vec![0x01, 0xC0]));
+/// ```
+#[derive(Debug)]
+pub struct FuturesSlipStream<T> {
+  inner: T,
+  reader: FrameReader,
+  writer: FrameWriter,
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> FuturesSlipStream<T> {
+  /// Creates a new framed connection over `inner`.
+  pub fn new(inner: T) -> Self {
+    Self::with_decoder(inner, SlipDecoder::new())
+  }
+
+  /// Creates a new framed connection over `inner` that decodes with
+  /// `decoder`, for example one created with `SlipDecoder::with_limit`.
+  pub fn with_decoder(inner: T, decoder: SlipDecoder) -> Self {
+    Self {
+      inner,
+      reader: FrameReader::new(decoder),
+      writer: FrameWriter::default(),
+    }
+  }
+
+  /// Encodes `payload` as a single frame, then writes and flushes it.
+  ///
+  /// # Cancel safety
+  ///
+  /// If the future is dropped after it was first polled, the frame stays
+  /// buffered and is written out ahead of the frame from the next `send`.
+  pub async fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+    let Self { inner, writer, .. } = self;
+    writer.push(payload);
+
+    poll_fn(|cx| writer.poll_write(0, |buffer| Pin::new(&mut *inner).poll_write(cx, buffer)))
+      .await?;
+    poll_fn(|cx| Pin::new(&mut *inner).poll_flush(cx)).await
+  }
+
+  /// Receives the next complete frame.
+  ///
+  /// Returns `None` once the connection has been closed. A frame that isn't
+  /// encoded correctly is returned as an `io::ErrorKind::InvalidData` error,
+  /// after which the following frames can still be received.
+  ///
+  /// # Cancel safety
+  ///
+  /// This method is cancel safe. Every byte read is handed to the decoder
+  /// before the future yields, so if it's dropped part way through a frame,
+  /// for example by losing a `select!` race, the partial frame is kept and
+  /// the next call carries on from where this one stopped.
+  pub async fn next_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+    let Self { inner, reader, .. } = self;
+
+    poll_fn(|cx| reader.poll_frame(|buffer| Pin::new(&mut *inner).poll_read(cx, buffer)))
+      .await
+      .transpose()
+  }
+
+  /// Returns a reference to the inner connection.
+  pub fn get_ref(&self) -> &T {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner connection.
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.inner
+  }
+
+  /// Consumes the `FuturesSlipStream`, returning the inner connection.
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use futures::executor::block_on;
+  use futures::io::Cursor;
+
+  #[test]
+  fn exchanges_frames_over_cursor() {
+    let mut stream = FuturesSlipStream::new(Cursor::new(Vec::new()));
+
+    block_on(async {
+      stream.send(&[0x01, ESC, 0x02]).await.unwrap();
+      stream.send(&[END; 3]).await.unwrap();
+    });
+    stream.get_mut().set_position(0);
+
+    block_on(async {
+      assert_eq!(
+        stream.next_frame().await.unwrap(),
+        Some(vec![0x01, ESC, 0x02])
+      );
+      assert_eq!(stream.next_frame().await.unwrap(), Some(vec![END; 3]));
+      assert_eq!(stream.next_frame().await.unwrap(), None);
+    });
+  }
+}
@@ -0,0 +1,301 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// The first byte of every frame sent by a `Handshake`, which sets them
+/// apart from data.
+pub const HANDSHAKE_MAGIC: u8 = 0xFE;
+
+// The length of a handshake frame: the magic byte, a status byte, a version
+// byte, a byte of `HeaderFlags`, then the MTU as a big-endian `u16`.
+const HANDSHAKE_LEN: usize = 6;
+
+// The bits of the status byte, set when the sender has the receiver's
+// capabilities, and when it knows the receiver has its own.
+const HAVE_PEER: u8 = 0x01;
+const ACKED: u8 = 0x02;
+
+/// What one end of a link supports, as exchanged by a `Handshake`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Capabilities {
+  /// The newest `FrameHeader` version understood.
+  pub version: u8,
+  /// The features that can be used, such as `HeaderFlags::CHECKSUM` and
+  /// `HeaderFlags::MUX`.
+  pub flags: HeaderFlags,
+  /// The longest payload that can be received, as given to
+  /// `SlipConfig::mtu`.
+  pub mtu: u16,
+}
+
+impl Capabilities {
+  /// Creates capabilities for the current `HEADER_VERSION`, with the given
+  /// features and MTU.
+  pub const fn new(flags: HeaderFlags, mtu: u16) -> Self {
+    Self {
+      version: HEADER_VERSION,
+      flags,
+      mtu,
+    }
+  }
+
+  /// Returns what both ends can use: the older version, the features they
+  /// have in common, and the smaller MTU.
+  pub fn negotiate(&self, peer: &Capabilities) -> Capabilities {
+    let header = self.header().negotiate(&peer.header());
+
+    Capabilities {
+      version: header.version,
+      flags: header.flags,
+      mtu: self.mtu.min(peer.mtu),
+    }
+  }
+
+  /// Returns the `FrameHeader` to put in front of payloads sent with these
+  /// capabilities.
+  pub const fn header(&self) -> FrameHeader {
+    FrameHeader::new(self.version, self.flags)
+  }
+}
+
+/// Agrees on the features and MTU to use with the other end of a link, by
+/// exchanging `Capabilities` when the link starts, so they don't have to be
+/// configured the same on both sides.
+///
+/// This is a state machine that doesn't do any I/O itself, like
+/// `Keepalive`. The caller encodes and sends each frame returned by
+/// `poll_transmit`, and gives every decoded frame received to `receive`,
+/// which picks out the handshake frames. Each side sends its capabilities
+/// every `interval` until the other has acknowledged them, and answers
+/// handshake frames until the other side has everything it needs, so it
+/// copes with either side starting first and with frames being lost. Time
+/// is measured by a `now` timestamp passed in by the caller, in whatever
+/// monotonic unit it likes.
+///
+/// Handshake frames start with `HANDSHAKE_MAGIC`, so no data should be
+/// sent until `agreed` returns the capabilities to use.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{Capabilities, Handshake, HeaderFlags};
+///
+/// let mut alice = Handshake::new(Capabilities::new(HeaderFlags::CHECKSUM, 1006), 100);
+/// let mut bob = Handshake::new(
+///   Capabilities::new(HeaderFlags::CHECKSUM | HeaderFlags::MUX, 296),
+///   100,
+/// );
+///
+/// let hello = alice.poll_transmit(0).unwrap();
+/// assert_eq!(alice.poll_transmit(50), None);
+///
+/// assert_eq!(bob.receive(&hello), Ok(true));
+/// let reply = bob.poll_transmit(50).unwrap();
+/// assert_eq!(alice.receive(&reply), Ok(true));
+/// assert!(alice.is_complete());
+///
+/// let ack = alice.poll_transmit(50).unwrap();
+/// assert_eq!(bob.receive(&ack), Ok(true));
+/// assert!(bob.is_complete());
+/// assert_eq!(bob.poll_transmit(200), None);
+///
+/// let agreed = alice.agreed().unwrap();
+/// assert_eq!(agreed, Capabilities::new(HeaderFlags::CHECKSUM, 296));
+/// assert_eq!(bob.agreed(), Some(agreed));
+///
+/// // A data frame isn't part of the handshake.
+/// assert_eq!(alice.receive(&[0x01]), Ok(false));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handshake {
+  local: Capabilities,
+  peer: Option<Capabilities>,
+  interval: u64,
+  last_sent: Option<u64>,
+  acked: bool,
+  reply_pending: bool,
+}
+
+impl Handshake {
+  /// Creates a handshake offering `local`, sending it again after
+  /// `interval` until the peer acknowledges it.
+  pub fn new(local: Capabilities, interval: u64) -> Self {
+    Self {
+      local,
+      peer: None,
+      interval,
+      last_sent: None,
+      acked: false,
+      reply_pending: false,
+    }
+  }
+
+  /// Starts the handshake again, such as after the link has gone down,
+  /// forgetting what the peer supports.
+  pub fn reset(&mut self) {
+    self.peer = None;
+    self.last_sent = None;
+    self.acked = false;
+    self.reply_pending = false;
+  }
+
+  /// Handles a frame received from the other side, returning `true` if it
+  /// was a handshake frame, which should be dropped rather than handled as
+  /// data.
+  ///
+  /// Returns `SlipError::InvalidHeader` if the frame starts with
+  /// `HANDSHAKE_MAGIC` but isn't a handshake frame.
+  pub fn receive(&mut self, frame: &[u8]) -> Result<bool, SlipError> {
+    if frame.first() != Some(&HANDSHAKE_MAGIC) {
+      return Ok(false);
+    }
+
+    let &[_, status, version, flags, mtu_hi, mtu_lo] = frame else {
+      return Err(SlipError::InvalidHeader);
+    };
+    if status & !(HAVE_PEER | ACKED) != 0 {
+      return Err(SlipError::InvalidHeader);
+    }
+
+    self.peer = Some(Capabilities {
+      version,
+      flags: HeaderFlags::from_bits(flags),
+      mtu: u16::from_be_bytes([mtu_hi, mtu_lo]),
+    });
+    self.acked |= status & HAVE_PEER != 0;
+    // Answer until the peer has our capabilities and knows we have its.
+    self.reply_pending |= status != HAVE_PEER | ACKED;
+    Ok(true)
+  }
+
+  /// Returns the next handshake frame to send at `now`, if there is one,
+  /// to be encoded into a frame.
+  ///
+  /// Call this until it returns `None`, and again by `next_deadline`.
+  pub fn poll_transmit(&mut self, now: u64) -> Option<Vec<u8>> {
+    let due = !self.acked
+      && self
+        .last_sent
+        .is_none_or(|sent| now.saturating_sub(sent) >= self.interval);
+    if !self.reply_pending && !due {
+      return None;
+    }
+
+    self.reply_pending = false;
+    self.last_sent = Some(now);
+
+    let mut frame = Vec::with_capacity(HANDSHAKE_LEN);
+    let status = match (self.peer.is_some(), self.acked) {
+      (false, _) => 0,
+      (true, false) => HAVE_PEER,
+      (true, true) => HAVE_PEER | ACKED,
+    };
+    frame.extend_from_slice(&[
+      HANDSHAKE_MAGIC,
+      status,
+      self.local.version,
+      self.local.flags.bits(),
+    ]);
+    frame.extend_from_slice(&self.local.mtu.to_be_bytes());
+    Some(frame)
+  }
+
+  /// Returns when `poll_transmit` next has a frame to send, which may
+  /// already have passed, or `None` if it's only waiting to answer the
+  /// peer.
+  pub fn next_deadline(&self) -> Option<u64> {
+    match self.last_sent {
+      _ if self.reply_pending => Some(0),
+      _ if self.acked => None,
+      None => Some(0),
+      Some(sent) => Some(sent.saturating_add(self.interval)),
+    }
+  }
+
+  /// Returns `true` once both sides know what the other supports.
+  pub fn is_complete(&self) -> bool {
+    self.acked && self.peer.is_some()
+  }
+
+  /// Returns the capabilities both sides can use, once the peer's have
+  /// been received.
+  pub fn agreed(&self) -> Option<Capabilities> {
+    self.peer.map(|peer| self.local.negotiate(&peer))
+  }
+
+  /// Returns the capabilities the peer sent, if they've been received.
+  pub fn peer(&self) -> Option<Capabilities> {
+    self.peer
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn completes_over_a_lossy_link() {
+    let mut alice = Handshake::new(Capabilities::new(HeaderFlags::MUX, 1006), 10);
+    let mut bob = Handshake::new(
+      Capabilities {
+        version: 2,
+        flags: HeaderFlags::MUX | HeaderFlags::FRAGMENT,
+        mtu: 2048,
+      },
+      10,
+    );
+
+    // Drops every third frame each way, and delivers the rest encoded.
+    let mut sent = 0;
+    for now in 0..100 {
+      while let Some(frame) = alice.poll_transmit(now) {
+        sent += 1;
+        if sent % 3 != 0 {
+          let frame = decode(encode(&frame).unwrap()).unwrap();
+          assert_eq!(bob.receive(&frame), Ok(true));
+        }
+      }
+      while let Some(frame) = bob.poll_transmit(now) {
+        sent += 1;
+        if sent % 3 != 0 {
+          assert_eq!(alice.receive(&frame), Ok(true));
+        }
+      }
+    }
+
+    assert!(alice.is_complete() && bob.is_complete());
+    assert_eq!(
+      alice.agreed(),
+      Some(Capabilities::new(HeaderFlags::MUX, 1006))
+    );
+    assert_eq!(bob.agreed(), alice.agreed());
+    assert_eq!(bob.peer().unwrap().mtu, 1006);
+    assert_eq!((alice.next_deadline(), bob.next_deadline()), (None, None));
+    assert!(sent < 10);
+  }
+
+  #[test]
+  fn rejects_bad_handshake_frames() {
+    let mut handshake = Handshake::new(Capabilities::new(HeaderFlags::empty(), 296), 10);
+    assert_eq!(handshake.next_deadline(), Some(0));
+    assert_eq!(
+      handshake.poll_transmit(5),
+      Some(vec![HANDSHAKE_MAGIC, 0, HEADER_VERSION, 0, 0x01, 0x28])
+    );
+    assert_eq!(handshake.next_deadline(), Some(15));
+
+    assert_eq!(
+      handshake.receive(&[HANDSHAKE_MAGIC, 0, 1]),
+      Err(SlipError::InvalidHeader)
+    );
+    assert_eq!(
+      handshake.receive(&[HANDSHAKE_MAGIC, 4, 1, 0, 0, 0]),
+      Err(SlipError::InvalidHeader)
+    );
+    assert_eq!(handshake.receive(&[]), Ok(false));
+    assert_eq!(handshake.agreed(), None);
+
+    handshake.reset();
+    assert_eq!(handshake.next_deadline(), Some(0));
+  }
+}
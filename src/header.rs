@@ -0,0 +1,213 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// The length of a `FrameHeader`: a version byte, then a byte of
+/// `HeaderFlags`.
+pub const FRAME_HEADER_LEN: usize = 2;
+
+/// The newest header version this crate understands.
+pub const HEADER_VERSION: u8 = 1;
+
+/// The features applied to a payload, as carried in a `FrameHeader`.
+///
+/// Each bit says the payload is wrapped in one of the crate's layers, so
+/// the receiver knows to unwrap it. Bits that aren't named here are kept,
+/// so a newer peer's flags can still be compared and negotiated down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HeaderFlags(u8);
+
+impl HeaderFlags {
+  /// The payload starts with a `SlipMux` channel id.
+  pub const MUX: Self = Self(0x01);
+  /// The payload is a fragment made by a `Fragmenter`.
+  pub const FRAGMENT: Self = Self(0x02);
+  /// The payload ends with a checksum, as added by `encode_with_checksum`.
+  pub const CHECKSUM: Self = Self(0x04);
+  /// The payload is a `ReliableSlip` packet.
+  pub const RELIABLE: Self = Self(0x08);
+
+  /// Returns no flags.
+  pub const fn empty() -> Self {
+    Self(0)
+  }
+
+  /// Returns the flags with the given bits, named or not.
+  pub const fn from_bits(bits: u8) -> Self {
+    Self(bits)
+  }
+
+  /// Returns the flags as a byte.
+  pub const fn bits(self) -> u8 {
+    self.0
+  }
+
+  /// Returns `true` if every flag in `other` is set.
+  pub const fn contains(self, other: Self) -> bool {
+    self.0 & other.0 == other.0
+  }
+
+  /// Returns the flags set in either.
+  pub const fn union(self, other: Self) -> Self {
+    Self(self.0 | other.0)
+  }
+
+  /// Returns the flags set in both.
+  pub const fn intersection(self, other: Self) -> Self {
+    Self(self.0 & other.0)
+  }
+}
+
+impl core::ops::BitOr for HeaderFlags {
+  type Output = Self;
+
+  fn bitor(self, other: Self) -> Self {
+    self.union(other)
+  }
+}
+
+impl core::ops::BitAnd for HeaderFlags {
+  type Output = Self;
+
+  fn bitand(self, other: Self) -> Self {
+    self.intersection(other)
+  }
+}
+
+/// A small header put at the start of each payload, giving its version and
+/// which features were applied to it.
+///
+/// It's opt-in, so it's only sent between peers that both expect it. Each
+/// side describes what it supports with a header of its own, and
+/// `negotiate` picks the version and flags both understand, so new
+/// features can be added to one side without breaking the other. Each
+/// header received is checked against the supported one with `check`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{FrameHeader, HeaderFlags};
+///
+/// let ours = FrameHeader::new(1, HeaderFlags::MUX | HeaderFlags::CHECKSUM);
+/// let theirs = FrameHeader::new(2, HeaderFlags::CHECKSUM | HeaderFlags::FRAGMENT);
+///
+/// let agreed = ours.negotiate(&theirs);
+/// assert_eq!(agreed, FrameHeader::new(1, HeaderFlags::CHECKSUM));
+///
+/// let encoded = agreed.encode(&[0x01, 0x02]);
+/// assert_eq!(encoded, [0xC0, 0x01, 0x04, 0x01, 0x02, 0xC0]);
+///
+/// let (header, payload) = FrameHeader::decode(&encoded).unwrap();
+/// assert_eq!(theirs.check(&header), Ok(()));
+/// assert_eq!(payload, [0x01, 0x02]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrameHeader {
+  /// The version of the header, and of the layout of the payload after it.
+  pub version: u8,
+  /// The features applied to the payload.
+  pub flags: HeaderFlags,
+}
+
+impl FrameHeader {
+  /// Creates a header with the given version and flags.
+  pub const fn new(version: u8, flags: HeaderFlags) -> Self {
+    Self { version, flags }
+  }
+
+  /// Returns the header as it's sent, at the start of the payload.
+  pub const fn to_bytes(&self) -> [u8; FRAME_HEADER_LEN] {
+    [self.version, self.flags.bits()]
+  }
+
+  /// Returns the header both sides can use, with the older version and the
+  /// flags they have in common, given what the peer supports.
+  pub fn negotiate(&self, peer: &FrameHeader) -> FrameHeader {
+    FrameHeader {
+      version: self.version.min(peer.version),
+      flags: self.flags & peer.flags,
+    }
+  }
+
+  /// Checks that a `received` header is one this side supports, with a
+  /// version no newer and no flags it doesn't have.
+  ///
+  /// Returns `SlipError::InvalidHeader` if it isn't.
+  pub fn check(&self, received: &FrameHeader) -> Result<(), SlipError> {
+    if received.version > self.version || !self.flags.contains(received.flags) {
+      return Err(SlipError::InvalidHeader);
+    }
+
+    Ok(())
+  }
+
+  /// Encodes `payload` as a single frame, with the header in front of it.
+  pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+    encode_parts(&[&self.to_bytes(), payload])
+  }
+
+  /// Decodes a single encoded frame, splitting off its header.
+  ///
+  /// Returns the errors `decode` does, or `SlipError::InvalidHeader` if
+  /// the frame is too short to hold a header.
+  pub fn decode<T: AsRef<[u8]>>(encoded_buffer: T) -> Result<(Self, Vec<u8>), SlipError> {
+    let mut frame = decode(encoded_buffer)?;
+    let header = Self::split(&frame)?.0;
+    frame.drain(..FRAME_HEADER_LEN);
+
+    Ok((header, frame))
+  }
+
+  /// Splits an already decoded frame into its header and payload.
+  ///
+  /// Returns `SlipError::InvalidHeader` if the frame is too short to hold
+  /// a header.
+  pub fn split(frame: &[u8]) -> Result<(Self, &[u8]), SlipError> {
+    let (&[version, flags], payload) = frame
+      .split_first_chunk::<FRAME_HEADER_LEN>()
+      .ok_or(SlipError::InvalidHeader)?;
+
+    Ok((Self::new(version, HeaderFlags::from_bits(flags)), payload))
+  }
+}
+
+impl Default for FrameHeader {
+  fn default() -> Self {
+    Self::new(HEADER_VERSION, HeaderFlags::empty())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn negotiates_down_to_what_both_support() {
+    let old = FrameHeader::default();
+    let new = FrameHeader::new(3, HeaderFlags::from_bits(0x80) | HeaderFlags::MUX);
+
+    let agreed = new.negotiate(&old);
+    assert_eq!(agreed, old.negotiate(&new));
+    assert_eq!(agreed, FrameHeader::new(1, HeaderFlags::empty()));
+    assert_eq!(old.check(&agreed), Ok(()));
+    assert_eq!(old.check(&new), Err(SlipError::InvalidHeader));
+    assert_eq!(
+      old.check(&FrameHeader::new(1, HeaderFlags::MUX)),
+      Err(SlipError::InvalidHeader)
+    );
+  }
+
+  #[test]
+  fn round_trips_payloads() {
+    let header = FrameHeader::new(END, HeaderFlags::from_bits(ESC));
+    let encoded = header.encode(&[END]);
+
+    assert_eq!(FrameHeader::decode(&encoded), Ok((header, vec![END])));
+    assert_eq!(
+      FrameHeader::decode(encode([0x01]).unwrap()),
+      Err(SlipError::InvalidHeader)
+    );
+    assert!(FrameHeader::split(&[0x01, 0x02]).unwrap().1.is_empty());
+  }
+}
@@ -0,0 +1,94 @@
+use super::*;
+use heapless::Vec;
+
+/// Encodes data following the SLIP protocol into a `heapless::Vec`.
+///
+/// This never allocates, making it suitable for firmware without an
+/// allocator. If the encoded frame doesn't fit in `N` bytes,
+/// `SlipError::BufferTooSmall` is returned.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_heapless;
+///
+/// let result: heapless::Vec<u8, 16> = encode_heapless(&[0x01, 0xDB, 0x49, 0xC0, 0x15]).unwrap();
+///
+/// assert_eq!(result, [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0]);
+/// assert!(encode_heapless::<8>(&[0x01, 0xDB, 0x49, 0xC0, 0x15]).is_err());
+/// ```
+pub fn encode_heapless<const N: usize>(raw_buffer: &[u8]) -> Result<Vec<u8, N>, SlipError> {
+  let mut encoded_buffer: Vec<u8, N> = Vec::new();
+
+  encode_to(raw_buffer, &mut encoded_buffer)?;
+
+  Ok(encoded_buffer)
+}
+
+/// Decodes data following the SLIP protocol into a `heapless::Vec`.
+///
+/// Like `decode_in_place`, everything up to the first `END` (0xC0) byte is
+/// skipped and the rest is decoded. If the decoded data doesn't fit in `N`
+/// bytes, `SlipError::BufferTooSmall` is returned.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::decode_heapless;
+///
+/// let input: [u8; 9] = [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+/// let result: heapless::Vec<u8, 8> = decode_heapless(&input).unwrap();
+///
+/// assert_eq!(result, [0x01, 0xDB, 0x49, 0xC0, 0x15]);
+/// assert!(decode_heapless::<4>(&input).is_err());
+/// ```
+pub fn decode_heapless<const N: usize>(encoded_buffer: &[u8]) -> Result<Vec<u8, N>, SlipError> {
+  let mut decoded_buffer: Vec<u8, N> = Vec::new();
+
+  decode_to(encoded_buffer, &mut decoded_buffer)?;
+
+  Ok(decoded_buffer)
+}
+
+/// Runs out with `SlipError::BufferTooSmall` once all `N` bytes are used.
+impl<const N: usize> ByteSink for Vec<u8, N> {
+  type Error = SlipError;
+
+  fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SlipError> {
+    self
+      .extend_from_slice(bytes)
+      .map_err(|_| SlipError::BufferTooSmall)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_through_heapless() {
+    let input: [u8; 5] = [0x01, ESC, 0x49, END, 0x15];
+
+    let encoded: Vec<u8, 9> = encode_heapless(&input).unwrap();
+    assert_eq!(encoded, encode(input).unwrap()[..]);
+
+    let decoded: Vec<u8, 5> = decode_heapless(&encoded).unwrap();
+    assert_eq!(decoded, input);
+  }
+
+  #[test]
+  fn errors_on_overflow() {
+    let input: [u8; 5] = [0x01, ESC, 0x49, END, 0x15];
+    let encoded = encode(input).unwrap();
+
+    assert_eq!(encode_heapless::<8>(&input), Err(SlipError::BufferTooSmall));
+    assert_eq!(
+      decode_heapless::<4>(&encoded),
+      Err(SlipError::BufferTooSmall)
+    );
+    assert_eq!(
+      decode_heapless::<8>(&[END, 0x01, ESC, 0x02]),
+      Err(SlipError::InvalidEscapeByte { found: 0x02 })
+    );
+  }
+}
@@ -0,0 +1,172 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// A change in whether a link supervised by `Keepalive` is alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LinkEvent {
+  /// Something was received again after the link went down.
+  LinkUp,
+  /// Nothing has been received within the timeout.
+  LinkDown,
+}
+
+/// Supervises a link, sending a heartbeat frame when it's been quiet and
+/// reporting it down when nothing has been received for too long.
+///
+/// This is a state machine that doesn't do any I/O itself. The caller
+/// reports traffic with `on_sent` and `on_received`, sends the heartbeat
+/// whenever `poll_heartbeat` returns it, and checks `poll` for the link
+/// going down or coming back up. Time is measured by a `now` timestamp
+/// passed in by the caller, in whatever monotonic unit it likes, starting
+/// from 0 or from the last `reset`.
+///
+/// `SlipConnection` and `SlipStream` take care of all of this when given a
+/// `Keepalive` with `set_keepalive`, counting time in milliseconds.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{Keepalive, LinkEvent};
+///
+/// let mut keepalive = Keepalive::new(&[0xFF], 100, 250);
+///
+/// assert_eq!(keepalive.poll_heartbeat(50), None);
+/// assert_eq!(keepalive.poll_heartbeat(100), Some(&[0xFF][..]));
+///
+/// assert!(keepalive.on_received(&[0xFF], 120));
+/// assert!(!keepalive.on_received(&[0x01], 150));
+///
+/// assert_eq!(keepalive.poll(300), None);
+/// assert_eq!(keepalive.poll(400), Some(LinkEvent::LinkDown));
+/// keepalive.on_received(&[0x02], 450);
+/// assert_eq!(keepalive.poll(450), Some(LinkEvent::LinkUp));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keepalive {
+  heartbeat: Vec<u8>,
+  interval: u64,
+  timeout: u64,
+  last_sent: u64,
+  last_seen: u64,
+  up: bool,
+  reported_up: bool,
+}
+
+impl Keepalive {
+  /// Creates a supervisor that sends `heartbeat` after `interval` without
+  /// sending anything, and reports the link down after `timeout` without
+  /// receiving anything.
+  ///
+  /// The heartbeat should be a payload that's never sent as data, and not
+  /// empty, as empty frames are skipped by default.
+  pub fn new(heartbeat: &[u8], interval: u64, timeout: u64) -> Self {
+    Self {
+      heartbeat: heartbeat.to_vec(),
+      interval,
+      timeout,
+      last_sent: 0,
+      last_seen: 0,
+      up: true,
+      reported_up: true,
+    }
+  }
+
+  /// Restarts both timers from `now`, treating the link as up.
+  pub fn reset(&mut self, now: u64) {
+    self.last_sent = now;
+    self.last_seen = now;
+    self.up = true;
+    self.reported_up = true;
+  }
+
+  /// Records that a frame was sent at `now`, so no heartbeat is needed
+  /// until `interval` after it.
+  pub fn on_sent(&mut self, now: u64) {
+    self.last_sent = self.last_sent.max(now);
+  }
+
+  /// Records that `frame` was received at `now`, returning `true` if it's
+  /// the heartbeat, which should be dropped rather than handled as data.
+  pub fn on_received(&mut self, frame: &[u8], now: u64) -> bool {
+    self.last_seen = self.last_seen.max(now);
+    self.up = true;
+
+    frame == self.heartbeat
+  }
+
+  /// Returns the heartbeat if nothing has been sent for `interval`, and
+  /// records it as sent at `now`.
+  pub fn poll_heartbeat(&mut self, now: u64) -> Option<&[u8]> {
+    if now.saturating_sub(self.last_sent) < self.interval {
+      return None;
+    }
+
+    self.last_sent = now;
+    Some(&self.heartbeat)
+  }
+
+  /// Returns a `LinkEvent` if the link has gone down or come back up since
+  /// the last call.
+  pub fn poll(&mut self, now: u64) -> Option<LinkEvent> {
+    if self.up && now.saturating_sub(self.last_seen) >= self.timeout {
+      self.up = false;
+    }
+    if self.up == self.reported_up {
+      return None;
+    }
+
+    self.reported_up = self.up;
+    Some(if self.up {
+      LinkEvent::LinkUp
+    } else {
+      LinkEvent::LinkDown
+    })
+  }
+
+  /// Returns `true` unless nothing has been received within the timeout.
+  pub fn is_up(&self) -> bool {
+    self.up
+  }
+
+  /// Returns when `poll_heartbeat` or `poll` next has something to do,
+  /// so the caller knows how long it can wait.
+  pub fn next_deadline(&self) -> u64 {
+    let heartbeat = self.last_sent.saturating_add(self.interval);
+    if !self.up {
+      return heartbeat;
+    }
+
+    heartbeat.min(self.last_seen.saturating_add(self.timeout))
+  }
+
+  /// Returns the heartbeat payload.
+  pub fn heartbeat(&self) -> &[u8] {
+    &self.heartbeat
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_sends_heartbeats_when_quiet() {
+    let mut keepalive = Keepalive::new(&[0x00], 10, 30);
+    keepalive.reset(100);
+
+    keepalive.on_sent(105);
+    assert_eq!(keepalive.next_deadline(), 115);
+    assert_eq!(keepalive.poll_heartbeat(114), None);
+    assert_eq!(keepalive.poll_heartbeat(115), Some(&[0x00][..]));
+    assert_eq!(keepalive.poll_heartbeat(124), None);
+    assert_eq!(keepalive.next_deadline(), 125);
+
+    keepalive.on_received(&[0x01], 124);
+    assert_eq!(keepalive.poll(153), None);
+    assert_eq!(keepalive.poll(154), Some(LinkEvent::LinkDown));
+    assert_eq!(keepalive.poll(500), None);
+    assert!(!keepalive.is_up());
+    assert_eq!(keepalive.next_deadline(), 125);
+  }
+}
@@ -0,0 +1,202 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// The command in the type byte of a `KissFrame`, telling the TNC what to
+/// do with the payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KissCommand {
+  /// The payload is a packet to send or that was received.
+  Data,
+  /// Sets the keyup delay, in units of 10ms.
+  TxDelay,
+  /// Sets the persistence parameter used for CSMA.
+  Persistence,
+  /// Sets the slot interval, in units of 10ms.
+  SlotTime,
+  /// Sets the time to hold the transmitter up after the frame, in units
+  /// of 10ms.
+  TxTail,
+  /// Turns full duplex on if the payload is non-zero.
+  FullDuplex,
+  /// A hardware specific command.
+  SetHardware,
+  /// Makes the TNC leave KISS mode. Its type byte is always 0xFF, so its
+  /// port is always 15.
+  Return,
+}
+
+impl KissCommand {
+  // Returns the command's low nibble of the type byte.
+  fn code(self) -> u8 {
+    match self {
+      KissCommand::Data => 0x0,
+      KissCommand::TxDelay => 0x1,
+      KissCommand::Persistence => 0x2,
+      KissCommand::SlotTime => 0x3,
+      KissCommand::TxTail => 0x4,
+      KissCommand::FullDuplex => 0x5,
+      KissCommand::SetHardware => 0x6,
+      KissCommand::Return => 0xF,
+    }
+  }
+
+  // Returns the command with the given low nibble of the type byte.
+  fn from_code(code: u8) -> Option<Self> {
+    let command = match code {
+      0x0 => KissCommand::Data,
+      0x1 => KissCommand::TxDelay,
+      0x2 => KissCommand::Persistence,
+      0x3 => KissCommand::SlotTime,
+      0x4 => KissCommand::TxTail,
+      0x5 => KissCommand::FullDuplex,
+      0x6 => KissCommand::SetHardware,
+      0xF => KissCommand::Return,
+      _ => return None,
+    };
+
+    Some(command)
+  }
+}
+
+/// A KISS frame, as used to talk to amateur radio TNCs.
+///
+/// KISS is SLIP framing with a type byte in front of the payload, holding
+/// the TNC port in its high nibble and a `KissCommand` in its low nibble,
+/// so the frames are encoded and decoded with the same escaping as the
+/// rest of the crate. Frames can also be read off a stream with
+/// `SlipDecoder`, and turned into a `KissFrame` with `from_payload`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{KissCommand, KissFrame};
+///
+/// let frame = KissFrame::data(1, &[0x82, 0xC0]);
+/// let encoded = frame.encode().unwrap();
+/// assert_eq!(encoded, [0xC0, 0x10, 0x82, 0xDB, 0xDC, 0xC0]);
+///
+/// let decoded = KissFrame::decode(&encoded).unwrap();
+/// assert_eq!(decoded.port, 1);
+/// assert_eq!(decoded.command, KissCommand::Data);
+/// assert_eq!(decoded.payload, [0x82, 0xC0]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct KissFrame {
+  /// The TNC port, from 0 to 15.
+  pub port: u8,
+  /// What the TNC should do with the payload.
+  pub command: KissCommand,
+  /// The packet, or the command's parameter.
+  pub payload: Vec<u8>,
+}
+
+impl KissFrame {
+  /// Creates a frame carrying a data packet on `port`.
+  pub fn data(port: u8, payload: &[u8]) -> Self {
+    Self {
+      port,
+      command: KissCommand::Data,
+      payload: payload.to_vec(),
+    }
+  }
+
+  /// Returns the type byte at the start of the frame.
+  ///
+  /// Returns `SlipError::InvalidHeader` if the port is more than 15.
+  pub fn type_byte(&self) -> Result<u8, SlipError> {
+    match self.command {
+      KissCommand::Return => Ok(0xFF),
+      _ if self.port > 0x0F => Err(SlipError::InvalidHeader),
+      command => Ok(self.port << 4 | command.code()),
+    }
+  }
+
+  /// Encodes the frame, type byte first.
+  ///
+  /// Returns `SlipError::InvalidHeader` if the port is more than 15.
+  pub fn encode(&self) -> Result<Vec<u8>, SlipError> {
+    Ok(encode_parts(&[&[self.type_byte()?], &self.payload]))
+  }
+
+  /// Decodes a single encoded KISS frame.
+  ///
+  /// Returns the errors `decode` does, or `SlipError::InvalidHeader` if the
+  /// frame is missing its type byte or has an unknown command.
+  pub fn decode<T: AsRef<[u8]>>(encoded_buffer: T) -> Result<Self, SlipError> {
+    Self::from_payload(&decode(encoded_buffer)?)
+  }
+
+  /// Splits an already decoded frame into its type byte and payload.
+  ///
+  /// Returns `SlipError::InvalidHeader` if the frame is empty or has an
+  /// unknown command.
+  pub fn from_payload(frame: &[u8]) -> Result<Self, SlipError> {
+    let (&type_byte, payload) = frame.split_first().ok_or(SlipError::InvalidHeader)?;
+    let command = match type_byte {
+      0xFF => KissCommand::Return,
+      _ => KissCommand::from_code(type_byte & 0x0F)
+        .filter(|command| *command != KissCommand::Return)
+        .ok_or(SlipError::InvalidHeader)?,
+    };
+
+    Ok(Self {
+      port: type_byte >> 4,
+      command,
+      payload: payload.to_vec(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_every_command() {
+    let commands = [
+      KissCommand::Data,
+      KissCommand::TxDelay,
+      KissCommand::Persistence,
+      KissCommand::SlotTime,
+      KissCommand::TxTail,
+      KissCommand::FullDuplex,
+      KissCommand::SetHardware,
+    ];
+
+    for (port, command) in commands.into_iter().enumerate() {
+      let frame = KissFrame {
+        port: port as u8 * 2,
+        command,
+        payload: vec![END, ESC, port as u8],
+      };
+      assert_eq!(KissFrame::decode(frame.encode().unwrap()), Ok(frame));
+    }
+
+    let frame = KissFrame {
+      port: 0x0F,
+      command: KissCommand::Return,
+      payload: Vec::new(),
+    };
+    assert_eq!(frame.encode().unwrap(), [END, 0xFF, END]);
+    assert_eq!(KissFrame::decode([END, 0xFF, END]), Ok(frame));
+  }
+
+  #[test]
+  fn rejects_bad_type_bytes() {
+    assert_eq!(
+      KissFrame::data(16, &[]).encode(),
+      Err(SlipError::InvalidHeader)
+    );
+    assert_eq!(KissFrame::from_payload(&[]), Err(SlipError::InvalidHeader));
+    assert_eq!(
+      KissFrame::from_payload(&[0x07]),
+      Err(SlipError::InvalidHeader)
+    );
+    assert_eq!(
+      KissFrame::from_payload(&[0x2F]),
+      Err(SlipError::InvalidHeader)
+    );
+  }
+}
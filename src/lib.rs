@@ -57,13 +57,196 @@
 //! assert_eq!(result, expected);
 //! assert_eq!(remainder, [0xC0, 0x01]);
 //! ```
+//!
+//! Decoding a stream of data in chunks
+//!
+//! ```rust
+//! use simple_slip::SlipDecoder;
+//!
+//! let mut decoder = SlipDecoder::new();
+//!
+//! decoder.push(&[0xC0, 0x01, 0xDB, 0xDD, 0x49]);
+//! decoder.push(&[0xDB, 0xDC, 0x15, 0xC0, 0xC0, 0x02]);
+//!
+//! let frames: Vec<Vec<u8>> = decoder.drain().map(|frame| frame.unwrap()).collect();
+//!
+//! assert_eq!(frames, [vec![0x01, 0xDB, 0x49, 0xC0, 0x15]]);
+//! ```
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "allocator-api2")]
+mod allocator;
+#[cfg(any(feature = "futures-io", feature = "tokio"))]
+mod async_core;
+#[cfg(feature = "futures-io")]
+mod async_sink;
+#[cfg(feature = "futures-io")]
+mod async_stream;
+mod borrowed;
+#[cfg(feature = "bytes")]
+mod bytes_buf;
+#[cfg(feature = "memmap2")]
+mod capture;
+mod checksum;
+mod cobs;
+#[cfg(feature = "tokio")]
+mod codec;
+mod config;
+#[cfg(feature = "std")]
+mod connection;
 mod constants;
+#[cfg(feature = "crc")]
+mod crc32;
+#[cfg(feature = "cslip")]
+mod cslip;
 mod decoder;
+mod dedup;
+#[cfg(feature = "deflate")]
+mod deflate;
+mod diagnose;
+mod dma;
+#[cfg(feature = "embedded-io-async")]
+mod embedded_async;
+#[cfg(feature = "embedded-io")]
+mod embedded_blocking;
+#[cfg(feature = "embedded-io")]
+mod embedded_core;
 mod encoder;
 mod error;
+mod fragment;
+mod frame_queue;
+mod framer;
+#[cfg(feature = "futures-io")]
+mod futures_slip_stream;
+mod handshake;
+mod header;
+#[cfg(feature = "heapless")]
+mod heapless_vec;
+mod keepalive;
+mod kiss;
+mod machine;
+mod multi_byte;
+mod mux;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+mod reader;
+mod reliable;
+mod scan;
+#[cfg(feature = "embedded-hal-nb")]
+mod serial_nb;
+mod sink;
+#[cfg(feature = "slip6")]
+mod slip6;
+#[cfg(feature = "tokio")]
+mod slip_stream;
+#[cfg(feature = "smallvec")]
+mod small_vec;
+mod static_decoder;
+mod stream_decoder;
+mod stream_encoder;
+mod transform;
+#[cfg(all(feature = "tun", target_os = "linux"))]
+mod tun;
+#[cfg(feature = "usbd-serial")]
+mod usb_serial;
+#[cfg(feature = "tokio")]
+mod worker;
+#[cfg(feature = "std")]
+mod writer;
 
+#[cfg(feature = "allocator-api2")]
+pub use allocator::{decode_in, encode_in};
+#[cfg(feature = "futures-io")]
+pub use async_sink::SlipFrameSink;
+#[cfg(feature = "futures-io")]
+pub use async_stream::SlipFrameStream;
+pub use borrowed::{borrowed_frames, BorrowedFrame, BorrowedFrames, UnescapedBytes};
+#[cfg(feature = "bytes")]
+pub use bytes_buf::{
+  decode_buf, decode_bytes, decode_from_bytes, decode_packets_bytes, encode_to_bytes, BytesFrame,
+};
+#[cfg(feature = "memmap2")]
+pub use capture::{Capture, CaptureFrames};
+pub use checksum::{
+  decode_with_checksum, decode_with_crc16, encode_with_checksum, encode_with_crc16, Crc16,
+  Fletcher16, FrameChecksum, Xor8,
+};
+pub use cobs::{cobs_decode, cobs_encode, Cobs, CobsDecoder};
+#[cfg(feature = "tokio")]
+pub use codec::SlipCodec;
+pub use config::{EmptyFramePolicy, InvalidEscapePolicy, MalformedPolicy, SlipConfig};
+#[cfg(feature = "std")]
+pub use connection::{SlipConnection, SlipTransport};
 pub use constants::*;
-pub use decoder::{decode, decode_packets};
-pub use encoder::encode;
-pub use error::SlipError;
+#[cfg(feature = "crc")]
+pub use crc32::Crc32;
+#[cfg(feature = "cslip")]
+pub use cslip::{CslipCompressor, CslipDecompressor};
+pub use decoder::{
+  decode, decode_detailed, decode_in_place, decode_into, decode_iter, decode_lossy, decode_packets,
+  decode_packets_checked, decode_packets_consumed, decode_to, try_decode, try_decode_alloc,
+  DecodeStatus,
+};
+pub use dedup::DedupFilter;
+#[cfg(feature = "deflate")]
+pub use deflate::Deflate;
+pub use diagnose::{diagnose, DecodeFinding, DecodeReport};
+pub use dma::DmaSlipDecoder;
+#[cfg(feature = "embedded-io-async")]
+pub use embedded_async::EmbeddedSlip;
+#[cfg(feature = "embedded-io")]
+pub use embedded_blocking::BlockingEmbeddedSlip;
+#[cfg(feature = "embedded-io")]
+pub use embedded_core::EmbeddedSlipError;
+pub use encoder::{
+  encode, encode_append, encode_iter, encode_parts, encode_to, encode_to_slice, try_encode,
+};
+pub use error::{DecodeError, SlipError};
+pub use fragment::{Fragmenter, Reassembler, FRAGMENT_HEADER_LEN};
+pub use frame_queue::{FrameConsumer, FrameProducer, SlipFrameQueue};
+pub use framer::{FrameDecoder, Framer};
+#[cfg(feature = "futures-io")]
+pub use futures_slip_stream::FuturesSlipStream;
+pub use handshake::{Capabilities, Handshake, HANDSHAKE_MAGIC};
+pub use header::{FrameHeader, HeaderFlags, FRAME_HEADER_LEN, HEADER_VERSION};
+#[cfg(feature = "heapless")]
+pub use heapless_vec::{decode_heapless, encode_heapless};
+pub use keepalive::{Keepalive, LinkEvent};
+pub use kiss::{KissCommand, KissFrame};
+pub use machine::{SlipEvent, SlipMachine};
+pub use multi_byte::MultiByteSlip;
+pub use mux::SlipMux;
+#[cfg(feature = "rayon")]
+pub use parallel::decode_packets_parallel;
+#[cfg(feature = "std")]
+pub use pool::{FramePool, PooledFrame};
+#[cfg(feature = "std")]
+pub use reader::{frames, DecodingReader, Frames, ReadTimeout, SlipReader};
+pub use reliable::{ReliableSlip, RELIABLE_HEADER_LEN};
+#[cfg(feature = "embedded-hal-nb")]
+pub use serial_nb::{NbSlip, NbSlipError};
+pub use sink::ByteSink;
+#[cfg(feature = "slip6")]
+pub use slip6::{slip6_decode, slip6_encode, Slip6, Slip6Decoder, SLIP6_END};
+#[cfg(feature = "tokio")]
+pub use slip_stream::SlipStream;
+#[cfg(feature = "smallvec")]
+pub use small_vec::{decode_smallvec, encode_smallvec};
+pub use static_decoder::StaticSlipDecoder;
+pub use stream_decoder::{OverflowPolicy, SlipDecoder};
+pub use stream_encoder::{EncodeStats, SlipEncoder};
+pub use transform::{decode_with_transform, encode_with_transform, PayloadTransform, Stack};
+#[cfg(all(feature = "tun", target_os = "linux"))]
+pub use tun::{TunBridge, TunDevice};
+#[cfg(feature = "usbd-serial")]
+pub use usb_serial::{UsbSerial, UsbSlip, UsbSlipError};
+#[cfg(feature = "tokio")]
+pub use worker::{spawn_decoder, DecodeStats, DecodeWorker};
+#[cfg(feature = "std")]
+pub use writer::{encode_io_slices, EncodingWriter, IoSink, SlipWriter};
@@ -0,0 +1,232 @@
+use super::*;
+use core::ops::Range;
+
+/// An event produced by `SlipMachine::advance`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SlipEvent {
+  /// Every buffered byte has been processed without completing a frame.
+  NeedMoreData,
+  /// A frame has been decoded into the given range of the buffer.
+  Frame(Range<usize>),
+  /// A frame was discarded because it wasn't encoded correctly.
+  Error(SlipError),
+}
+
+/// A sans-IO state machine for decoding the SLIP protocol.
+///
+/// The machine never allocates or performs any IO itself. Instead, the
+/// caller owns a buffer that received bytes are appended to, and the
+/// machine decodes frames in place within that buffer, which is always
+/// possible as a decoded frame is never longer than its encoding.
+///
+/// Once `advance` returns `SlipEvent::NeedMoreData`, `compact` moves the
+/// frame in progress to the front of the buffer so that more data can be
+/// appended after it.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{SlipEvent, SlipMachine};
+///
+/// let mut machine = SlipMachine::new();
+/// let mut buffer = [0u8; 16];
+/// let mut filled = 0;
+/// let mut frames: Vec<Vec<u8>> = Vec::new();
+///
+/// for chunk in [&[0xC0, 0x01, 0xDB][..], &[0xDD, 0x49, 0xC0, 0x02][..]] {
+///   buffer[filled..filled + chunk.len()].copy_from_slice(chunk);
+///   filled += chunk.len();
+///
+///   loop {
+///     match machine.advance(&mut buffer[..filled]) {
+///       SlipEvent::Frame(range) => frames.push(buffer[range].to_vec()),
+///       SlipEvent::Error(_) => {}
+///       SlipEvent::NeedMoreData => break,
+///     }
+///   }
+///
+///   filled = machine.compact(&mut buffer[..filled]);
+/// }
+///
+/// assert_eq!(frames, [[0x01, 0xDB, 0x49]]);
+/// assert_eq!(filled, 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SlipMachine {
+  start: usize,
+  write: usize,
+  read: usize,
+  synced: bool,
+  escaped: bool,
+  error: Option<SlipError>,
+}
+
+impl SlipMachine {
+  /// Creates a new machine, waiting for the first `END` (0xC0) byte.
+  pub const fn new() -> Self {
+    Self {
+      start: 0,
+      write: 0,
+      read: 0,
+      synced: false,
+      escaped: false,
+      error: None,
+    }
+  }
+
+  // Creates a machine that's already had an `END` byte, at the start of
+  // the buffer.
+  pub(crate) const fn synced() -> Self {
+    Self {
+      synced: true,
+      ..Self::new()
+    }
+  }
+  /// Processes the bytes in `buffer` that haven't been seen yet.
+  ///
+  /// `buffer` must contain the same data at the same positions as the
+  /// previous call, optionally followed by newly received bytes. Ranges
+  /// returned in `SlipEvent::Frame` stay valid until `compact` is called.
+  pub fn advance(&mut self, buffer: &mut [u8]) -> SlipEvent {
+    while self.read < buffer.len() {
+      let byte = buffer[self.read];
+      self.read += 1;
+
+      if let Some(event) = self.step(buffer, byte) {
+        return event;
+      }
+    }
+
+    SlipEvent::NeedMoreData
+  }
+
+  // Handles `byte`, which has just been read from `buffer`, returning the
+  // frame or error it completes. An `END` or `ESC` byte can also be handed
+  // over without being in `buffer`, as neither is ever written to it.
+  pub(crate) fn step(&mut self, buffer: &mut [u8], byte: u8) -> Option<SlipEvent> {
+    if !self.synced {
+      self.synced = byte == END;
+      self.start = self.read;
+      self.write = self.read;
+      return None;
+    }
+
+    match byte {
+      END => {
+        let frame = self.start..self.write;
+        let error = match self.error.take() {
+          None if self.escaped => Some(SlipError::TruncatedEscape),
+          error => error,
+        };
+        self.escaped = false;
+        self.start = self.read;
+        self.write = self.read;
+
+        if let Some(error) = error {
+          return Some(SlipEvent::Error(error));
+        } else if !frame.is_empty() {
+          return Some(SlipEvent::Frame(frame));
+        }
+      }
+      _ if self.error.is_some() => {}
+      _ if self.escaped => {
+        self.escaped = false;
+        match byte {
+          ESC_END => self.emit(buffer, END),
+          ESC_ESC => self.emit(buffer, ESC),
+          found => self.fail(SlipError::InvalidEscapeByte { found }),
+        }
+      }
+      ESC => self.escaped = true,
+      _ => self.emit(buffer, byte),
+    }
+
+    None
+  }
+
+  // Drops the frame in progress, reporting `error` for it at its `END`
+  // byte unless it's already failed.
+  pub(crate) fn fail(&mut self, error: SlipError) {
+    if self.synced {
+      self.write = self.start;
+      self.error.get_or_insert(error);
+    }
+  }
+
+  // Returns the number of decoded bytes held for the frame in progress.
+  pub(crate) fn partial_len(&self) -> usize {
+    self.write - self.start
+  }
+
+  /// Moves the frame in progress, and any bytes that haven't been
+  /// processed yet, to the front of `buffer`.
+  ///
+  /// Returns the number of bytes at the front of `buffer` that are still
+  /// in use. New data should be appended after them.
+  pub fn compact(&mut self, buffer: &mut [u8]) -> usize {
+    let partial = self.write - self.start;
+    let unread = buffer.len().saturating_sub(self.read);
+
+    buffer.copy_within(self.start..self.write, 0);
+    buffer.copy_within(self.read..self.read + unread, partial);
+
+    self.start = 0;
+    self.write = partial;
+    self.read = partial;
+
+    partial + unread
+  }
+
+  fn emit(&mut self, buffer: &mut [u8], byte: u8) {
+    buffer[self.write] = byte;
+    self.write += 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(machine: &mut SlipMachine, buffer: &mut [u8]) -> Vec<Result<Vec<u8>, SlipError>> {
+    let mut frames = Vec::new();
+    loop {
+      match machine.advance(buffer) {
+        SlipEvent::Frame(range) => frames.push(Ok(buffer[range].to_vec())),
+        SlipEvent::Error(error) => frames.push(Err(error)),
+        SlipEvent::NeedMoreData => return frames,
+      }
+    }
+  }
+
+  #[test]
+  fn decodes_frames_in_place() {
+    let mut buffer: Vec<u8> = vec![
+      0xA1, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END, END, 0x02, ESC, 0x03, END,
+      0x04, END,
+    ];
+    let mut machine = SlipMachine::new();
+
+    let frames = run(&mut machine, &mut buffer);
+
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].as_ref().unwrap(), &[0x01, ESC, 0x49, END, 0x15]);
+    assert!(frames[1].is_err());
+    assert_eq!(frames[2].as_ref().unwrap(), &[0x04]);
+  }
+
+  #[test]
+  fn keeps_partial_frame_when_compacting() {
+    let mut buffer = [0u8; 8];
+    buffer[..5].copy_from_slice(&[0xA1, END, 0x01, ESC, ESC_END]);
+    let mut machine = SlipMachine::new();
+
+    assert!(run(&mut machine, &mut buffer[..5]).is_empty());
+    let filled = machine.compact(&mut buffer[..5]);
+    assert_eq!(&buffer[..filled], &[0x01, END]);
+
+    buffer[filled..filled + 2].copy_from_slice(&[0x15, END]);
+    let frames = run(&mut machine, &mut buffer[..filled + 2]);
+    assert_eq!(frames, [Ok(vec![0x01, END, 0x15])]);
+  }
+}
@@ -0,0 +1,243 @@
+use super::*;
+use crate::encoder::escape_to;
+use alloc::vec::Vec;
+
+/// SLIP framing with a delimiter made up of several bytes, as used by some
+/// proprietary serial protocols.
+///
+/// Payloads are escaped just like in SLIP, except that it's the first byte
+/// of the delimiter that's escaped as `ESC ESC_END`, rather than `END`
+/// (0xC0). That byte never appears on its own inside an encoded frame, so
+/// neither can the delimiter, and frames are split wherever the whole
+/// delimiter sequence is found. `ESC`, `ESC_END` and `ESC_ESC` come from a
+/// `SlipConfig`, whose own `END` byte is ignored.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::MultiByteSlip;
+///
+/// let slip = MultiByteSlip::new(&[0x7E, 0x81]);
+///
+/// let encoded = slip.encode(&[0x01, 0x7E, 0x81]);
+/// assert_eq!(encoded, [0x7E, 0x81, 0x01, 0xDB, 0xDC, 0x81, 0x7E, 0x81]);
+/// assert_eq!(slip.decode(&encoded).unwrap(), [0x01, 0x7E, 0x81]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiByteSlip {
+  delimiter: Vec<u8>,
+  config: SlipConfig,
+}
+
+impl MultiByteSlip {
+  /// Creates a framing that delimits frames with `delimiter`, using the
+  /// standard SLIP escape bytes.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `delimiter` is empty, or starts with the `ESC`, `ESC_END`
+  /// or `ESC_ESC` byte.
+  pub fn new(delimiter: &[u8]) -> Self {
+    Self::with_config(delimiter, SlipConfig::new())
+  }
+
+  /// Creates a framing that delimits frames with `delimiter`, using the
+  /// escape bytes from `config`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `delimiter` is empty, or starts with the config's `ESC`,
+  /// `ESC_END` or `ESC_ESC` byte, any of which can appear in an escape
+  /// sequence in front of the rest of the delimiter.
+  pub fn with_config(delimiter: &[u8], config: SlipConfig) -> Self {
+    assert!(!delimiter.is_empty(), "the delimiter must not be empty");
+    assert!(
+      ![config.esc, config.esc_end, config.esc_esc].contains(&delimiter[0]),
+      "the delimiter must not start with an escape byte"
+    );
+
+    Self {
+      delimiter: delimiter.to_vec(),
+      config: config.end(delimiter[0]),
+    }
+  }
+
+  /// Returns the delimiter sequence.
+  pub fn delimiter(&self) -> &[u8] {
+    &self.delimiter
+  }
+
+  /// Encodes `raw_buffer` as a single frame, with the delimiter before
+  /// and after it.
+  pub fn encode(&self, raw_buffer: &[u8]) -> Vec<u8> {
+    let mut encoded_buffer: Vec<u8> =
+      Vec::with_capacity(raw_buffer.len() + 2 * self.delimiter.len());
+
+    encoded_buffer.extend_from_slice(&self.delimiter);
+    // Appending to a `Vec` never fails.
+    let _ = escape_to(&self.config, raw_buffer, &mut encoded_buffer);
+    encoded_buffer.extend_from_slice(&self.delimiter);
+
+    encoded_buffer
+  }
+
+  /// Decodes the first frame in `encoded_buffer`, which starts after the
+  /// first delimiter and runs up to the next one, or the end of the buffer.
+  ///
+  /// Returns `SlipError::NoEndDelimiter` if there's no delimiter at all.
+  pub fn decode(&self, encoded_buffer: &[u8]) -> Result<Vec<u8>, SlipError> {
+    let mut rest = self
+      .split_after_delimiter(encoded_buffer)
+      .ok_or(SlipError::NoEndDelimiter)?;
+
+    // Skip the empty frames between back-to-back delimiters.
+    while let Some(next) = rest.strip_prefix(self.delimiter.as_slice()) {
+      rest = next;
+    }
+    let body = match self.find_delimiter(rest) {
+      Some(idx) => &rest[..idx],
+      None => rest,
+    };
+
+    self.decode_body(body)
+  }
+
+  /// Decodes the complete frames in `encoded_buffer`, returning them along
+  /// with the remainder that doesn't make up a complete frame yet.
+  ///
+  /// Like `SlipConfig::decode_packets`, everything before the first
+  /// delimiter is skipped, as are empty frames, and a frame that isn't
+  /// encoded correctly is returned as an error in its place.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::MultiByteSlip;
+  ///
+  /// let slip = MultiByteSlip::new(&[0x7E, 0x81]);
+  /// let input = [0x7E, 0x81, 0x01, 0x7E, 0x81, 0x7E, 0x81, 0x02, 0x7E];
+  ///
+  /// let (packets, remainder) = slip.decode_packets(&input);
+  ///
+  /// assert_eq!(packets, [Ok(vec![0x01])]);
+  /// assert_eq!(remainder, [0x7E, 0x81, 0x02, 0x7E]);
+  /// ```
+  pub fn decode_packets(
+    &self,
+    encoded_buffer: &[u8],
+  ) -> (Vec<Result<Vec<u8>, SlipError>>, Vec<u8>) {
+    let mut packets: Vec<Result<Vec<u8>, SlipError>> = Vec::new();
+
+    let mut start = match self.find_delimiter(encoded_buffer) {
+      Some(idx) => idx,
+      None => return (packets, encoded_buffer.to_vec()),
+    };
+    loop {
+      let body_start = start + self.delimiter.len();
+      let len = match self.find_delimiter(&encoded_buffer[body_start..]) {
+        Some(len) => len,
+        None => break,
+      };
+
+      if len > 0 {
+        packets.push(self.decode_body(&encoded_buffer[body_start..body_start + len]));
+      }
+      start = body_start + len;
+    }
+
+    // A trailing delimiter with nothing after it has nothing left to start.
+    let remainder = match &encoded_buffer[start..] {
+      rest if rest == self.delimiter.as_slice() => &[],
+      rest => rest,
+    };
+
+    (packets, remainder.to_vec())
+  }
+
+  fn decode_body(&self, body: &[u8]) -> Result<Vec<u8>, SlipError> {
+    let mut decoded_buffer: Vec<u8> = Vec::with_capacity(body.len());
+    let mut rest = body;
+
+    while let Some(idx) = self.config.find_special(rest) {
+      decoded_buffer.extend_from_slice(&rest[..idx]);
+      // The delimiter's first byte is always escaped, so it can only
+      // appear on its own in a corrupt frame, where it's reported like a
+      // byte that can't be escaped.
+      if rest[idx] != self.config.esc {
+        return Err(SlipError::InvalidEscapeByte { found: rest[idx] });
+      }
+      let found = rest.get(idx + 1).copied();
+      match found.and_then(|byte| self.config.unescape(byte)) {
+        Some(byte) => decoded_buffer.push(byte),
+        None => return Err(SlipError::invalid_escape(found, self.config.end)),
+      }
+      rest = &rest[idx + 2..];
+    }
+    decoded_buffer.extend_from_slice(rest);
+
+    Ok(decoded_buffer)
+  }
+
+  fn find_delimiter(&self, haystack: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(idx) = self.config.find_end(&haystack[offset..]) {
+      if haystack[offset + idx..].starts_with(&self.delimiter) {
+        return Some(offset + idx);
+      }
+      offset += idx + 1;
+    }
+
+    None
+  }
+
+  fn split_after_delimiter<'a>(&self, haystack: &'a [u8]) -> Option<&'a [u8]> {
+    let idx = self.find_delimiter(haystack)?;
+
+    Some(&haystack[idx + self.delimiter.len()..])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_through_packets() {
+    let slip = MultiByteSlip::new(&[0x7E, 0x81, 0x7E]);
+    let payloads: [&[u8]; 3] = [&[0x7E, 0x81, 0x7E, 0x81], &[ESC, END, 0x7E], &[0x01]];
+
+    let mut input: Vec<u8> = vec![0xA1, 0x81];
+    for payload in payloads {
+      input.extend(slip.encode(payload));
+    }
+    input.extend([0x01, 0x7E, 0x02, 0x7E, 0x81, 0x7E, 0x03]);
+
+    let (packets, remainder) = slip.decode_packets(&input);
+    assert_eq!(
+      packets,
+      [
+        Ok(payloads[0].to_vec()),
+        Ok(payloads[1].to_vec()),
+        Ok(payloads[2].to_vec()),
+        Err(SlipError::InvalidEscapeByte { found: 0x7E })
+      ]
+    );
+    assert_eq!(remainder, [0x7E, 0x81, 0x7E, 0x03]);
+    assert_eq!(slip.decode(&input), Ok(payloads[0].to_vec()));
+    assert_eq!(slip.decode(&input[..2]), Err(SlipError::NoEndDelimiter));
+  }
+
+  #[test]
+  fn keeps_escape_bytes_out_of_the_delimiter_start() {
+    for byte in [ESC, ESC_END, ESC_ESC] {
+      let result = std::panic::catch_unwind(|| MultiByteSlip::new(&[byte, 0x01]));
+      assert!(result.is_err());
+    }
+
+    // The escape bytes are fine anywhere after the first.
+    let slip = MultiByteSlip::new(&[0x01, ESC_END]);
+    let payload = [ESC_END, 0x01, 0x01, ESC_END, ESC];
+    let encoded = slip.encode(&payload);
+    assert_eq!(slip.decode_packets(&encoded).0, [Ok(payload.to_vec())]);
+  }
+}
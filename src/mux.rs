@@ -0,0 +1,152 @@
+use super::*;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+
+/// Carries several independent channels of frames over one link, by
+/// prefixing each frame with a channel id byte.
+///
+/// Frames for a channel are made with `SlipMux::encode`. Incoming bytes
+/// are given to `push`, which decodes them and sorts the frames into a
+/// queue per channel, to be taken off with `pop`. This way one serial
+/// cable can carry, say, a console, telemetry and firmware updates at the
+/// same time.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::SlipMux;
+///
+/// const CONSOLE: u8 = 0;
+/// const TELEMETRY: u8 = 1;
+///
+/// let mut link = SlipMux::encode(TELEMETRY, &[0x10, 0x20]).unwrap();
+/// link.extend(SlipMux::encode(CONSOLE, b"hi").unwrap());
+/// assert_eq!(link[..5], [0xC0, 0x01, 0x10, 0x20, 0xC0]);
+///
+/// let mut mux = SlipMux::new();
+/// mux.push(&link);
+///
+/// assert_eq!(mux.pop(CONSOLE), Some(b"hi".to_vec()));
+/// assert_eq!(mux.pop(TELEMETRY), Some(vec![0x10, 0x20]));
+/// assert_eq!(mux.pop(TELEMETRY), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlipMux {
+  decoder: SlipDecoder,
+  queues: BTreeMap<u8, VecDeque<Vec<u8>>>,
+  capacity: usize,
+  dropped: u64,
+}
+
+impl SlipMux {
+  /// Creates a demultiplexer with unbounded queues.
+  pub fn new() -> Self {
+    Self::with_capacity(usize::MAX)
+  }
+
+  /// Creates a demultiplexer that queues up to `capacity` frames per
+  /// channel, so a channel that's never read from can't use up memory.
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      decoder: SlipDecoder::new(),
+      queues: BTreeMap::new(),
+      capacity,
+      dropped: 0,
+    }
+  }
+
+  /// Encodes `payload` as a single frame on `channel`.
+  pub fn encode(channel: u8, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+    Ok(encode_parts(&[&[channel], payload]))
+  }
+
+  /// Decodes `chunk` of the incoming bytes, queueing each frame it
+  /// completes on its channel.
+  ///
+  /// Frames that don't decode, have no channel id, or arrive for a full
+  /// queue are dropped, and counted by `dropped`.
+  pub fn push(&mut self, chunk: &[u8]) {
+    self.decoder.push(chunk);
+
+    while let Some(frame) = self.decoder.next_frame() {
+      let queued = frame.and_then(|frame| self.push_frame(&frame));
+      if queued.is_err() {
+        self.dropped += 1;
+      }
+    }
+  }
+
+  /// Queues an already decoded `frame` on its channel, returning the
+  /// channel.
+  ///
+  /// Returns `SlipError::InvalidHeader` if the frame is empty, so has no
+  /// channel id, or `SlipError::QueueFull` if the channel's queue is full.
+  pub fn push_frame(&mut self, frame: &[u8]) -> Result<u8, SlipError> {
+    let (&channel, payload) = frame.split_first().ok_or(SlipError::InvalidHeader)?;
+
+    let queue = self.queues.entry(channel).or_default();
+    if queue.len() >= self.capacity {
+      return Err(SlipError::QueueFull);
+    }
+    queue.push_back(payload.to_vec());
+
+    Ok(channel)
+  }
+
+  /// Takes the oldest frame queued on `channel`.
+  pub fn pop(&mut self, channel: u8) -> Option<Vec<u8>> {
+    self.queues.get_mut(&channel)?.pop_front()
+  }
+
+  /// Takes every frame queued on `channel`, oldest first.
+  pub fn drain(&mut self, channel: u8) -> impl Iterator<Item = Vec<u8>> + '_ {
+    self
+      .queues
+      .get_mut(&channel)
+      .into_iter()
+      .flat_map(|queue| queue.drain(..))
+  }
+
+  /// Returns the number of frames queued on `channel`.
+  pub fn pending(&self, channel: u8) -> usize {
+    self.queues.get(&channel).map_or(0, VecDeque::len)
+  }
+
+  /// Returns the number of incoming frames dropped by `push`.
+  pub fn dropped(&self) -> u64 {
+    self.dropped
+  }
+}
+
+impl Default for SlipMux {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn demultiplexes_interleaved_channels() {
+    let mut link = Vec::new();
+    for idx in 0..6u8 {
+      link.extend(SlipMux::encode(idx % 3, &[idx, END, ESC]).unwrap());
+    }
+    link.extend([END, ESC, 0x01, END]);
+
+    let mut mux = SlipMux::with_capacity(1);
+    for chunk in link.chunks(5) {
+      mux.push(chunk);
+    }
+
+    assert_eq!((mux.pending(0), mux.pending(1), mux.pending(3)), (1, 1, 0));
+    assert_eq!(mux.drain(2).collect::<Vec<_>>(), [vec![2, END, ESC]]);
+    assert_eq!(mux.pop(2), None);
+    assert_eq!(mux.dropped(), 4);
+
+    assert_eq!(mux.push_frame(&[]), Err(SlipError::InvalidHeader));
+    assert_eq!(mux.push_frame(&[2, 0x42]), Ok(2));
+  }
+}
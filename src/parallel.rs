@@ -0,0 +1,57 @@
+use crate::decoder::{decode_packet, split_packets};
+use rayon::prelude::*;
+
+/// Decodes data following the SLIP protocol into delimited packets, using
+/// every core.
+///
+/// This returns exactly what `decode_packets` does, but after a quick scan
+/// for the frame boundaries, the frames themselves are decoded in parallel
+/// on the rayon thread pool. It's meant for large buffers holding many
+/// frames, such as a logged capture, where decoding is otherwise CPU-bound.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_packets, decode_packets_parallel, encode};
+///
+/// let mut input: Vec<u8> = Vec::new();
+/// for idx in 0..1000u32 {
+///   input.extend(encode(&idx.to_le_bytes()).unwrap());
+/// }
+/// input.extend([0xC0, 0x01]);
+///
+/// let (result, remainder) = decode_packets_parallel(&input);
+///
+/// assert_eq!(result.len(), 1000);
+/// assert_eq!(remainder, [0xC0, 0x01]);
+/// assert_eq!((result, remainder), decode_packets(&input));
+/// ```
+pub fn decode_packets_parallel(encoded_buffer: &[u8]) -> (Vec<Vec<u8>>, Vec<u8>) {
+  let mut frames: Vec<&[u8]> = Vec::new();
+  let consumed = split_packets(encoded_buffer, |frame| frames.push(frame));
+
+  let packets = frames
+    .into_par_iter()
+    .filter_map(|frame| decode_packet(frame).ok())
+    .collect();
+
+  (packets, encoded_buffer[consumed..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{decode_packets, encode, END, ESC, ESC_END};
+
+  #[test]
+  fn decodes_like_decode_packets() {
+    let mut input: Vec<u8> = vec![0xA1, END, END];
+    for idx in 0..5000u32 {
+      let payload: Vec<u8> = idx.to_le_bytes().repeat((idx % 7) as usize);
+      input.extend(encode(&payload).unwrap());
+    }
+    input.extend([END, 0x01, ESC, ESC_END, 0x02, END, END, 0x03, ESC]);
+
+    assert_eq!(decode_packets_parallel(&input), decode_packets(&input));
+  }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+/// A pool of reusable buffers for decoded frames.
+///
+/// `get` hands out a `PooledFrame`, an empty `Vec<u8>` that keeps the
+/// capacity of whichever frame last used it, and dropping the `PooledFrame`
+/// clears the buffer and puts it back in the pool. A long-running process
+/// decoding frames at a steady rate then settles on a fixed set of buffers
+/// and stops allocating altogether. The pool is cheap to clone and can be
+/// shared between threads, with every clone handing out the same buffers.
+///
+/// At most `max_buffers` buffers are kept, so a burst of frames doesn't tie
+/// up memory forever. Buffers returned to a full pool are freed as usual.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{FramePool, SlipDecoder};
+///
+/// let pool = FramePool::new(8);
+/// let mut decoder = SlipDecoder::new();
+/// let mut frames = Vec::new();
+///
+/// for byte in [0xC0, 0x01, 0xDB, 0xDC, 0xC0, 0x02, 0xC0] {
+///   if let Some(Ok(frame)) = decoder.push_byte(byte) {
+///     frames.push(pool.copy_from(frame));
+///   }
+/// }
+///
+/// assert_eq!(*frames[0], [0x01, 0xC0]);
+/// assert_eq!(*frames[1], [0x02]);
+///
+/// drop(frames);
+/// assert_eq!(pool.available(), 2);
+/// ```
+#[derive(Clone)]
+pub struct FramePool {
+  shared: Arc<Shared>,
+}
+
+struct Shared {
+  buffers: Mutex<Vec<Vec<u8>>>,
+  max_buffers: usize,
+}
+
+impl FramePool {
+  /// Creates a new, empty pool that keeps at most `max_buffers` buffers.
+  pub fn new(max_buffers: usize) -> Self {
+    Self {
+      shared: Arc::new(Shared {
+        buffers: Mutex::new(Vec::new()),
+        max_buffers,
+      }),
+    }
+  }
+
+  /// Takes an empty buffer from the pool, allocating a new one if the pool
+  /// has none left.
+  pub fn get(&self) -> PooledFrame {
+    let buffer = self.lock().pop().unwrap_or_default();
+
+    PooledFrame {
+      buffer,
+      shared: Arc::clone(&self.shared),
+    }
+  }
+
+  /// Takes a buffer from the pool and copies `frame` into it.
+  pub fn copy_from(&self, frame: &[u8]) -> PooledFrame {
+    let mut pooled = self.get();
+    pooled.extend_from_slice(frame);
+    pooled
+  }
+
+  /// Returns the number of buffers waiting in the pool to be reused.
+  pub fn available(&self) -> usize {
+    self.lock().len()
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Vec<u8>>> {
+    self.shared.lock()
+  }
+}
+
+impl fmt::Debug for FramePool {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("FramePool")
+      .field("available", &self.available())
+      .field("max_buffers", &self.shared.max_buffers)
+      .finish()
+  }
+}
+
+impl Shared {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Vec<u8>>> {
+    // The buffers are only ever pushed and popped, so they can't be left in
+    // a bad state by a thread that panicked while holding the lock.
+    self
+      .buffers
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+/// A frame buffer borrowed from a `FramePool`, which is returned to the
+/// pool when dropped.
+///
+/// It dereferences to the `Vec<u8>` it wraps, so it can be read and written
+/// like any other frame.
+pub struct PooledFrame {
+  buffer: Vec<u8>,
+  shared: Arc<Shared>,
+}
+
+impl PooledFrame {
+  /// Takes the buffer out of the pool for good, returning it as a plain
+  /// `Vec<u8>`.
+  pub fn into_vec(mut self) -> Vec<u8> {
+    std::mem::take(&mut self.buffer)
+  }
+}
+
+impl Deref for PooledFrame {
+  type Target = Vec<u8>;
+
+  fn deref(&self) -> &Vec<u8> {
+    &self.buffer
+  }
+}
+
+impl DerefMut for PooledFrame {
+  fn deref_mut(&mut self) -> &mut Vec<u8> {
+    &mut self.buffer
+  }
+}
+
+impl AsRef<[u8]> for PooledFrame {
+  fn as_ref(&self) -> &[u8] {
+    &self.buffer
+  }
+}
+
+impl fmt::Debug for PooledFrame {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.buffer, f)
+  }
+}
+
+impl Drop for PooledFrame {
+  fn drop(&mut self) {
+    if self.buffer.capacity() == 0 {
+      return;
+    }
+
+    let mut buffers = self.shared.lock();
+    if buffers.len() < self.shared.max_buffers {
+      let mut buffer = std::mem::take(&mut self.buffer);
+      buffer.clear();
+      buffers.push(buffer);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reuses_returned_buffers() {
+    let pool = FramePool::new(1);
+
+    let frame = pool.copy_from(&[0x01; 100]);
+    let ptr = frame.as_ptr();
+    drop(frame);
+
+    let frame = pool.get();
+    assert!(frame.is_empty());
+    assert!(frame.capacity() >= 100);
+    assert_eq!(frame.as_ptr(), ptr);
+    assert_eq!(pool.available(), 0);
+
+    let other = pool.copy_from(&[0x02]);
+    drop(frame);
+    drop(other);
+    assert_eq!(pool.available(), 1);
+  }
+
+  #[test]
+  fn into_vec_keeps_the_buffer() {
+    let pool = FramePool::new(4);
+
+    let frame = pool.copy_from(&[0x01, 0x02]).into_vec();
+
+    assert_eq!(frame, [0x01, 0x02]);
+    assert_eq!(pool.available(), 0);
+  }
+}
@@ -0,0 +1,562 @@
+use super::*;
+use std::io::{self, BufRead, Read};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+pub(crate) const READ_BUFFER_SIZE: usize = 512;
+
+/// A reader that can stop blocking after a timeout.
+///
+/// This is implemented for `std::net::TcpStream`, and on unix for
+/// `std::os::unix::net::UnixStream`. It can be implemented for other types,
+/// like serial ports, to make `SlipReader::read_frame_timeout` and
+/// `SlipConnection::recv_timeout` available for them.
+pub trait ReadTimeout {
+  /// Sets how long a read may block before failing with
+  /// `io::ErrorKind::WouldBlock` or `io::ErrorKind::TimedOut`. `None` blocks
+  /// indefinitely.
+  fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl ReadTimeout for TcpStream {
+  fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+    TcpStream::set_read_timeout(self, timeout)
+  }
+}
+
+#[cfg(unix)]
+impl ReadTimeout for std::os::unix::net::UnixStream {
+  fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+    std::os::unix::net::UnixStream::set_read_timeout(self, timeout)
+  }
+}
+
+/// Reads frames following the SLIP protocol from any `std::io::Read`.
+///
+/// Data is read from the inner reader in chunks and fed through a
+/// `SlipDecoder`, so partial frames and any data read past the end of a
+/// frame are kept until the next call to `read_frame`. Any other
+/// `FrameDecoder`, such as a `CobsDecoder`, can be given to `with_decoder`
+/// in its place to read frames in that framing instead.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::SlipReader;
+///
+/// let input: &[u8] = &[0xC0, 0x01, 0xDB, 0xDD, 0xC0, 0xC0, 0x02, 0xC0];
+/// let mut reader = SlipReader::new(input);
+///
+/// assert_eq!(reader.read_frame().unwrap(), [0x01, 0xDB]);
+/// assert_eq!(reader.read_frame().unwrap(), [0x02]);
+/// assert!(reader.read_frame().is_err());
+/// ```
+#[derive(Debug)]
+pub struct SlipReader<R, D = SlipDecoder> {
+  inner: R,
+  decoder: D,
+  buffer: ReadBuffer,
+}
+
+impl<R: Read + ReadTimeout, D: FrameDecoder> SlipReader<R, D> {
+  /// Reads the next complete frame, giving up once `timeout` has passed.
+  ///
+  /// If no complete frame arrives in time, an `io::ErrorKind::TimedOut`
+  /// error is returned. Any part of a frame received so far is kept, so a
+  /// later call picks up where this one left off. The inner reader is left
+  /// with no read timeout set.
+  pub fn read_frame_timeout(&mut self, timeout: Duration) -> io::Result<Vec<u8>> {
+    read_frame_timeout_with(
+      &mut self.decoder,
+      &mut self.buffer,
+      &mut self.inner,
+      |inner, buffer| inner.read(buffer),
+      timeout,
+    )
+  }
+}
+
+impl<R: Read> SlipReader<R> {
+  /// Creates a new reader over `inner`.
+  pub fn new(inner: R) -> Self {
+    Self::with_decoder(inner, SlipDecoder::new())
+  }
+}
+
+impl<R: Read, D: FrameDecoder> SlipReader<R, D> {
+  /// Creates a new reader over `inner` that decodes with `decoder`, for
+  /// example one created with `SlipDecoder::with_limit`, or a `CobsDecoder`.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{CobsDecoder, SlipReader};
+  ///
+  /// let input: &[u8] = &[0x02, 0xC0, 0x01, 0x00, 0x01, 0x00];
+  /// let mut reader = SlipReader::with_decoder(input, CobsDecoder::new());
+  ///
+  /// assert_eq!(reader.read_frame().unwrap(), [0xC0, 0x00]);
+  /// assert_eq!(reader.read_frame().unwrap(), []);
+  /// ```
+  pub fn with_decoder(inner: R, decoder: D) -> Self {
+    Self {
+      inner,
+      decoder,
+      buffer: ReadBuffer::new(),
+    }
+  }
+
+  /// Reads the next complete frame, blocking on the inner reader as needed.
+  ///
+  /// A frame that isn't encoded correctly is returned as an
+  /// `io::ErrorKind::InvalidData` error, after which the following frames
+  /// can still be read. Reaching the end of the inner reader returns an
+  /// `io::ErrorKind::UnexpectedEof` error.
+  pub fn read_frame(&mut self) -> io::Result<Vec<u8>> {
+    let inner = &mut self.inner;
+
+    read_frame_with(&mut self.decoder, &mut self.buffer, |buffer| {
+      inner.read(buffer)
+    })
+  }
+
+  /// Returns a reference to the inner reader.
+  pub fn get_ref(&self) -> &R {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader.
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.inner
+  }
+
+  /// Consumes the `SlipReader`, returning the inner reader.
+  ///
+  /// Any buffered data that hasn't been returned as a frame is lost.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+/// Returns an iterator over the frames read from `reader`.
+///
+/// Data is decoded directly from the reader's internal buffer using
+/// `fill_buf` and `consume`, so it isn't copied into an intermediate buffer
+/// first. Iteration ends when the reader does, dropping any partial frame.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::frames;
+///
+/// let input: &[u8] = &[0xC0, 0x01, 0xC0, 0xC0, 0x02, 0xDB, 0xDC, 0xC0, 0xC0, 0x03];
+///
+/// let result: Vec<Vec<u8>> = frames(input).collect::<Result<_, _>>().unwrap();
+///
+/// assert_eq!(result, [vec![0x01], vec![0x02, 0xC0]]);
+/// ```
+pub fn frames<R: BufRead>(reader: R) -> Frames<R> {
+  Frames {
+    inner: reader,
+    decoder: SlipDecoder::new(),
+    done: false,
+  }
+}
+
+/// An iterator over the frames read from a `BufRead`, created by `frames`.
+#[derive(Debug)]
+pub struct Frames<R> {
+  inner: R,
+  decoder: SlipDecoder,
+  done: bool,
+}
+
+impl<R: BufRead> Iterator for Frames<R> {
+  type Item = io::Result<Vec<u8>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(frame) = self.decoder.next_frame() {
+        return Some(frame.map_err(io::Error::from));
+      } else if self.done {
+        return None;
+      }
+
+      let available = match self.inner.fill_buf() {
+        Ok(available) => available,
+        Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+        Err(error) => return Some(Err(error)),
+      };
+
+      let read = available.len();
+      self.done = read == 0;
+      self.decoder.push(available);
+      self.inner.consume(read);
+    }
+  }
+}
+
+/// An adapter that decodes SLIP encoded data read through it.
+///
+/// Encoded data is read from the inner reader, and the decoded payload of
+/// each frame is returned as one continuous stream of bytes. This allows
+/// an existing parser built on `std::io::Read` to be tunneled over a SLIP
+/// link.
+///
+/// A single `read` never returns data from more than one frame, and
+/// `frame_index` reports which frame the last data returned belongs to, so
+/// frame boundaries can still be tracked when they matter.
+///
+/// Each frame is decoded in full before any of it is returned, so a frame
+/// that turns out not to be encoded correctly is never passed on. It's
+/// returned as an `io::ErrorKind::InvalidData` error in its place, and
+/// decoding carries on with the next frame, just like `SlipReader`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::DecodingReader;
+/// use std::io::Read;
+///
+/// let input: &[u8] = &[0xC0, b'h', b'i', 0xC0, 0xC0, 0xDB, 0xDC, 0xC0];
+/// let mut reader = DecodingReader::new(input);
+/// let mut buffer = [0u8; 8];
+///
+/// assert_eq!(reader.read(&mut buffer).unwrap(), 2);
+/// assert_eq!(&buffer[..2], b"hi");
+/// assert_eq!(reader.frame_index(), 0);
+///
+/// assert_eq!(reader.read(&mut buffer).unwrap(), 1);
+/// assert_eq!(buffer[0], 0xC0);
+/// assert_eq!(reader.frame_index(), 1);
+/// ```
+#[derive(Debug)]
+pub struct DecodingReader<R> {
+  inner: R,
+  decoder: SlipDecoder,
+  buffer: ReadBuffer,
+  frame: Vec<u8>,
+  position: usize,
+  frames: u64,
+  frame_index: u64,
+}
+
+impl<R: Read> DecodingReader<R> {
+  /// Creates a new adapter over `inner`.
+  pub fn new(inner: R) -> Self {
+    Self {
+      inner,
+      decoder: SlipDecoder::new(),
+      buffer: ReadBuffer::new(),
+      frame: Vec::new(),
+      position: 0,
+      frames: 0,
+      frame_index: 0,
+    }
+  }
+
+  /// Returns the index of the frame that the data returned by the last
+  /// `read` belongs to, counting from zero.
+  pub fn frame_index(&self) -> u64 {
+    self.frame_index
+  }
+
+  /// Returns a reference to the inner reader.
+  pub fn get_ref(&self) -> &R {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader.
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.inner
+  }
+
+  /// Consumes the adapter, returning the inner reader.
+  ///
+  /// Any buffered data that hasn't been returned yet is lost.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+  fn read(&mut self, output: &mut [u8]) -> io::Result<usize> {
+    if output.is_empty() {
+      return Ok(0);
+    }
+
+    while self.position == self.frame.len() {
+      match self.buffer.next_frame(&mut self.decoder) {
+        Some(frame) => {
+          let index = self.frames;
+          self.frames += 1;
+          self.frame = frame?;
+          self.position = 0;
+          self.frame_index = index;
+        }
+        None => match self.buffer.fill(|bytes| self.inner.read(bytes)) {
+          // A frame cut off by the end of the input is never completed.
+          Ok(0) => return Ok(0),
+          Ok(_) => {}
+          Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+          Err(error) => return Err(error),
+        },
+      }
+    }
+
+    let rest = &self.frame[self.position..];
+    let len = rest.len().min(output.len());
+    output[..len].copy_from_slice(&rest[..len]);
+    self.position += len;
+    Ok(len)
+  }
+}
+
+// The bytes read for a `FrameDecoder`, and how far it has decoded them.
+#[derive(Debug)]
+pub(crate) struct ReadBuffer {
+  bytes: Vec<u8>,
+  start: usize,
+  end: usize,
+}
+
+impl ReadBuffer {
+  pub(crate) fn new() -> Self {
+    Self {
+      bytes: vec![0; READ_BUFFER_SIZE],
+      start: 0,
+      end: 0,
+    }
+  }
+
+  // Feeds the bytes not decoded yet to `decoder`, stopping after the first
+  // frame it completes, so the rest are kept for the next frame.
+  fn next_frame<D: FrameDecoder>(&mut self, decoder: &mut D) -> Option<io::Result<Vec<u8>>> {
+    while self.start < self.end {
+      let byte = self.bytes[self.start];
+      self.start += 1;
+      if let Some(frame) = decoder.push_decode(byte) {
+        return Some(frame.map(<[u8]>::to_vec).map_err(io::Error::from));
+      }
+    }
+
+    None
+  }
+
+  // Fills the buffer with `read`, once every byte in it has been decoded.
+  fn fill<F>(&mut self, read: F) -> io::Result<usize>
+  where
+    F: FnOnce(&mut [u8]) -> io::Result<usize>,
+  {
+    let read = read(&mut self.bytes)?;
+    self.start = 0;
+    self.end = read;
+    Ok(read)
+  }
+}
+
+pub(crate) fn read_frame_with<D, F>(
+  decoder: &mut D,
+  buffer: &mut ReadBuffer,
+  mut read: F,
+) -> io::Result<Vec<u8>>
+where
+  D: FrameDecoder,
+  F: FnMut(&mut [u8]) -> io::Result<usize>,
+{
+  loop {
+    if let Some(frame) = buffer.next_frame(decoder) {
+      return frame;
+    }
+
+    match buffer.fill(&mut read) {
+      Ok(0) => return Err(unexpected_eof()),
+      Ok(_) => {}
+      Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+      Err(error) => return Err(error),
+    }
+  }
+}
+
+pub(crate) fn read_frame_timeout_with<D, T, F>(
+  decoder: &mut D,
+  buffer: &mut ReadBuffer,
+  inner: &mut T,
+  mut read: F,
+  timeout: Duration,
+) -> io::Result<Vec<u8>>
+where
+  D: FrameDecoder,
+  T: ReadTimeout,
+  F: FnMut(&mut T, &mut [u8]) -> io::Result<usize>,
+{
+  let deadline = Instant::now() + timeout;
+
+  let result = loop {
+    if let Some(frame) = buffer.next_frame(decoder) {
+      break frame;
+    }
+
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+      break Err(timed_out());
+    } else if let Err(error) = inner.set_read_timeout(Some(remaining)) {
+      break Err(error);
+    }
+
+    match buffer.fill(|bytes| read(inner, bytes)) {
+      Ok(0) => break Err(unexpected_eof()),
+      Ok(_) => {}
+      Err(error) => match error.kind() {
+        io::ErrorKind::Interrupted => {}
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => break Err(timed_out()),
+        _ => break Err(error),
+      },
+    }
+  };
+
+  inner.set_read_timeout(None)?;
+  result
+}
+
+fn unexpected_eof() -> io::Error {
+  io::Error::new(
+    io::ErrorKind::UnexpectedEof,
+    "reader ended before a complete frame",
+  )
+}
+
+pub(crate) fn timed_out() -> io::Error {
+  io::Error::new(
+    io::ErrorKind::TimedOut,
+    "no complete frame received before the timeout",
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Trickle<'a>(&'a [u8]);
+
+  impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      match self.0.split_first() {
+        Some((byte, rest)) if !buf.is_empty() => {
+          buf[0] = *byte;
+          self.0 = rest;
+          Ok(1)
+        }
+        _ => Ok(0),
+      }
+    }
+  }
+
+  #[test]
+  fn reads_frames_one_byte_at_a_time() {
+    let input: Vec<u8> = vec![
+      0xA1, END, 0x01, ESC, ESC_END, END, 0x02, ESC, 0x03, END, 0x04, END,
+    ];
+    let mut reader = SlipReader::new(Trickle(&input));
+
+    assert_eq!(reader.read_frame().unwrap(), [0x01, END]);
+    assert_eq!(
+      reader.read_frame().unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+    assert_eq!(reader.read_frame().unwrap(), [0x04]);
+    assert_eq!(
+      reader.read_frame().unwrap_err().kind(),
+      io::ErrorKind::UnexpectedEof
+    );
+  }
+
+  #[test]
+  fn reads_cobs_frames() {
+    let mut input = cobs_encode(&[0x00, END, 0x01]);
+    input.extend([0x03, 0x01, 0x00]);
+    input.extend(cobs_encode(&[0x02]));
+    let mut reader = SlipReader::with_decoder(Trickle(&input), CobsDecoder::new());
+
+    assert_eq!(reader.read_frame().unwrap(), [0x00, END, 0x01]);
+    assert_eq!(
+      reader.read_frame().unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+    assert_eq!(reader.read_frame().unwrap(), [0x02]);
+  }
+
+  #[test]
+  fn keeps_partial_frame_across_timeouts() {
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut sender = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let mut reader = SlipReader::new(listener.accept().unwrap().0);
+
+    sender.write_all(&[END, 0x01, ESC]).unwrap();
+    let error = reader
+      .read_frame_timeout(Duration::from_millis(20))
+      .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+
+    sender.write_all(&[ESC_END, END]).unwrap();
+    let frame = reader.read_frame_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(frame, [0x01, END]);
+  }
+
+  #[test]
+  fn iterates_frames_from_buf_read() {
+    let input: Vec<u8> = vec![
+      END, 0x01, END, 0x02, ESC, 0x03, END, 0x04, ESC, ESC_ESC, END,
+    ];
+    let reader = io::BufReader::with_capacity(3, input.as_slice());
+
+    let results: Vec<io::Result<Vec<u8>>> = frames(reader).collect();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), &[0x01]);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap(), &[0x04, ESC]);
+  }
+
+  #[test]
+  fn decodes_payload_stream_without_spanning_frames() {
+    let input: Vec<u8> = vec![
+      0xA1, END, 0x01, 0x02, END, END, 0x03, ESC, ESC_ESC, 0x04, END, 0x05,
+    ];
+    let mut reader = DecodingReader::new(Trickle(&input));
+    let mut buffer = [0u8; 2];
+    let mut reads: Vec<(u64, Vec<u8>)> = Vec::new();
+
+    loop {
+      let read = reader.read(&mut buffer).unwrap();
+      if read == 0 {
+        break;
+      }
+      reads.push((reader.frame_index(), buffer[..read].to_vec()));
+    }
+
+    // The last frame is never finished, so none of it is returned.
+    assert_eq!(
+      reads,
+      [(0, vec![0x01, 0x02]), (1, vec![0x03, ESC]), (1, vec![0x04])]
+    );
+  }
+
+  #[test]
+  fn skips_corrupt_frames_in_payload_stream() {
+    let input: Vec<u8> = vec![END, 0x01, 0x02, ESC, 0x03, 0x04, END, 0x05, END];
+    let mut reader = DecodingReader::new(Trickle(&input));
+    let mut buffer = [0u8; 8];
+
+    let error = reader.read(&mut buffer).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    assert_eq!(reader.read(&mut buffer).unwrap(), 1);
+    assert_eq!(buffer[0], 0x05);
+    assert_eq!(reader.frame_index(), 1);
+    assert_eq!(reader.read(&mut buffer).unwrap(), 0);
+  }
+}
@@ -0,0 +1,270 @@
+use super::*;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+// The first byte of each packet, giving its kind.
+const DATA: u8 = 0x00;
+const ACK: u8 = 0x01;
+
+/// The length of the header at the start of each `ReliableSlip` packet: a
+/// byte giving the packet's kind, then its 8-bit sequence number.
+pub const RELIABLE_HEADER_LEN: usize = 2;
+
+/// Delivers payloads over a lossy link in order, without gaps or
+/// duplicates, by numbering them and retransmitting those that aren't
+/// acknowledged in time.
+///
+/// This is a state machine that doesn't do any I/O itself, so it works the
+/// same over any transport, with or without `std`. Payloads given to `send`
+/// are turned into packets by `poll_transmit`, which are encoded into
+/// frames and written by the caller, while the decoded frames received from
+/// the other side are given to `receive`. Time is measured by a `now`
+/// timestamp passed in by the caller, in whatever monotonic unit it likes.
+///
+/// Each data packet carries a sequence number, and the receiver answers
+/// with a cumulative ack of the next sequence number it expects, accepting
+/// only packets in order. Up to `window` packets can be waiting for an ack
+/// at once, and each is sent again if it isn't acked within `timeout`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::ReliableSlip;
+///
+/// let mut alice = ReliableSlip::new(4, 100);
+/// let mut bob = ReliableSlip::new(4, 100);
+///
+/// alice.send(&[0x01, 0x02]);
+/// let lost = alice.poll_transmit(0).unwrap();
+///
+/// // Nothing is sent again until the timeout has passed.
+/// assert_eq!(alice.poll_transmit(50), None);
+/// let packet = alice.poll_transmit(100).unwrap();
+/// assert_eq!(packet, lost);
+///
+/// assert_eq!(bob.receive(&packet), Ok(Some(vec![0x01, 0x02])));
+/// let ack = bob.poll_transmit(100).unwrap();
+/// assert_eq!(alice.receive(&ack), Ok(None));
+///
+/// assert!(alice.is_idle());
+/// assert_eq!(alice.retransmits(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReliableSlip {
+  window: usize,
+  timeout: u64,
+  queued: VecDeque<Vec<u8>>,
+  unacked: VecDeque<Unacked>,
+  next_seq: u8,
+  expected: u8,
+  ack_pending: bool,
+  retransmits: u64,
+}
+
+// A data packet sent and waiting for an ack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Unacked {
+  packet: Vec<u8>,
+  sent: u64,
+}
+
+impl ReliableSlip {
+  /// Creates an endpoint allowing up to `window` packets to be waiting for
+  /// an ack at once, and sending packets again after `timeout`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `window` is 0 or more than 127, half the sequence numbers.
+  pub fn new(window: usize, timeout: u64) -> Self {
+    assert!(
+      (1..=127).contains(&window),
+      "window must be between 1 and 127"
+    );
+
+    Self {
+      window,
+      timeout,
+      queued: VecDeque::new(),
+      unacked: VecDeque::new(),
+      next_seq: 0,
+      expected: 0,
+      ack_pending: false,
+      retransmits: 0,
+    }
+  }
+
+  /// Queues `payload` to be sent.
+  pub fn send(&mut self, payload: &[u8]) {
+    self.queued.push_back(payload.to_vec());
+  }
+
+  /// Handles a packet received from the other side, returning its payload
+  /// if it's the next one in order.
+  ///
+  /// Data packets are acked by the next call to `poll_transmit`, including
+  /// duplicates and packets that arrive out of order, which are dropped.
+  /// Returns `SlipError::InvalidHeader` if the packet's header is cut short
+  /// or of an unknown kind.
+  pub fn receive(&mut self, packet: &[u8]) -> Result<Option<Vec<u8>>, SlipError> {
+    let (&[kind, seq], payload) = packet
+      .split_first_chunk::<RELIABLE_HEADER_LEN>()
+      .ok_or(SlipError::InvalidHeader)?;
+
+    match kind {
+      DATA => {
+        self.ack_pending = true;
+        if seq != self.expected {
+          return Ok(None);
+        }
+
+        self.expected = self.expected.wrapping_add(1);
+        Ok(Some(payload.to_vec()))
+      }
+      ACK => {
+        // Everything before `seq` has arrived, so drop the packets it
+        // covers, ignoring stale acks that cover none.
+        let acked = usize::from(seq.wrapping_sub(self.first_unacked_seq()));
+        if acked <= self.unacked.len() {
+          self.unacked.drain(..acked);
+        }
+        Ok(None)
+      }
+      _ => Err(SlipError::InvalidHeader),
+    }
+  }
+
+  /// Returns the next packet to send at `now`, if there is one, to be
+  /// encoded into a frame.
+  ///
+  /// Acks are sent first, then packets whose ack has timed out, then newly
+  /// queued payloads while the window has room. Call this until it returns
+  /// `None`, and again by `next_timeout`.
+  pub fn poll_transmit(&mut self, now: u64) -> Option<Vec<u8>> {
+    if self.ack_pending {
+      self.ack_pending = false;
+      return Some(alloc::vec![ACK, self.expected]);
+    }
+
+    let timeout = self.timeout;
+    let due = self
+      .unacked
+      .iter_mut()
+      .find(|unacked| now.saturating_sub(unacked.sent) >= timeout);
+    if let Some(unacked) = due {
+      unacked.sent = now;
+      self.retransmits += 1;
+      return Some(unacked.packet.clone());
+    }
+
+    if self.unacked.len() == self.window {
+      return None;
+    }
+    let payload = self.queued.pop_front()?;
+    let mut packet = Vec::with_capacity(RELIABLE_HEADER_LEN + payload.len());
+    packet.extend_from_slice(&[DATA, self.next_seq]);
+    packet.extend_from_slice(&payload);
+    self.next_seq = self.next_seq.wrapping_add(1);
+
+    self.unacked.push_back(Unacked {
+      packet: packet.clone(),
+      sent: now,
+    });
+    Some(packet)
+  }
+
+  /// Returns when the next packet waiting for an ack times out, so the
+  /// caller knows when to call `poll_transmit` again.
+  pub fn next_timeout(&self) -> Option<u64> {
+    self
+      .unacked
+      .iter()
+      .map(|unacked| unacked.sent)
+      .min()
+      .map(|sent| sent.saturating_add(self.timeout))
+  }
+
+  /// Returns the number of packets sent and waiting for an ack.
+  pub fn in_flight(&self) -> usize {
+    self.unacked.len()
+  }
+
+  /// Returns `true` if every payload has been sent and acked.
+  pub fn is_idle(&self) -> bool {
+    self.queued.is_empty() && self.unacked.is_empty()
+  }
+
+  /// Returns the number of times a packet was sent again after timing out.
+  pub fn retransmits(&self) -> u64 {
+    self.retransmits
+  }
+
+  // Returns the sequence number of the oldest packet waiting for an ack, or
+  // of the next one to be sent if there are none.
+  fn first_unacked_seq(&self) -> u8 {
+    self.next_seq.wrapping_sub(self.unacked.len() as u8)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn delivers_in_order_over_a_lossy_link() {
+    let mut alice = ReliableSlip::new(3, 10);
+    let mut bob = ReliableSlip::new(3, 10);
+    let payloads: Vec<Vec<u8>> = (0..=255).map(|byte| vec![byte, END, ESC]).collect();
+    for payload in &payloads {
+      alice.send(payload);
+    }
+
+    // Drops every third packet each way, and delivers the rest encoded.
+    let mut received = Vec::new();
+    let mut sent = 0;
+    for now in 0..10_000 {
+      while let Some(packet) = alice.poll_transmit(now) {
+        sent += 1;
+        if sent % 3 != 0 {
+          let frame = decode(encode(&packet).unwrap()).unwrap();
+          received.extend(bob.receive(&frame).unwrap());
+        }
+      }
+      while let Some(packet) = bob.poll_transmit(now) {
+        sent += 1;
+        if sent % 3 != 0 {
+          alice.receive(&packet).unwrap();
+        }
+      }
+      if alice.is_idle() {
+        break;
+      }
+    }
+
+    assert_eq!(received, payloads);
+    assert!(alice.retransmits() > 0);
+    assert_eq!(alice.next_timeout(), None);
+  }
+
+  #[test]
+  fn respects_the_window_and_rejects_bad_packets() {
+    let mut alice = ReliableSlip::new(2, 10);
+    for byte in 0..3 {
+      alice.send(&[byte]);
+    }
+
+    assert_eq!(alice.poll_transmit(0), Some(vec![DATA, 0, 0]));
+    assert_eq!(alice.poll_transmit(1), Some(vec![DATA, 1, 1]));
+    assert_eq!(alice.poll_transmit(1), None);
+    assert_eq!((alice.in_flight(), alice.next_timeout()), (2, Some(10)));
+
+    assert_eq!(alice.receive(&[ACK, 1]), Ok(None));
+    assert_eq!(alice.receive(&[ACK, 0]), Ok(None));
+    assert_eq!(alice.in_flight(), 1);
+    assert_eq!(alice.poll_transmit(2), Some(vec![DATA, 2, 2]));
+
+    assert_eq!(alice.receive(&[DATA]), Err(SlipError::InvalidHeader));
+    assert_eq!(alice.receive(&[0x02, 0]), Err(SlipError::InvalidHeader));
+    assert_eq!(alice.receive(&[DATA, 1, 0x01]), Ok(None));
+    assert_eq!(alice.poll_transmit(3), Some(vec![ACK, 0]));
+  }
+}
@@ -0,0 +1,130 @@
+use super::*;
+
+/// Returns the index of the first `END` (0xC0) byte in `haystack`.
+///
+/// With the `memchr` feature enabled this scans a word or vector at a time
+/// rather than byte by byte, which makes splitting large buffers that are
+/// mostly payload much faster.
+#[cfg(feature = "memchr")]
+pub(crate) fn find_end(haystack: &[u8]) -> Option<usize> {
+  memchr::memchr(END, haystack)
+}
+
+#[cfg(not(feature = "memchr"))]
+pub(crate) fn find_end(haystack: &[u8]) -> Option<usize> {
+  haystack.iter().position(|byte| *byte == END)
+}
+
+/// Returns the index of the first `END` (0xC0) or `ESC` (0xDB) byte in
+/// `haystack`, which is where a run of bytes that can be copied as is ends
+/// when encoding or decoding.
+///
+/// With the `simd` feature enabled on x86_64 or aarch64 this compares 16
+/// bytes at a time using SSE2 or NEON, both of which are always available
+/// on those targets.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+pub(crate) fn find_special(haystack: &[u8]) -> Option<usize> {
+  use core::arch::x86_64::*;
+
+  let mut chunks = haystack.chunks_exact(16);
+  let mut offset = 0;
+  for chunk in &mut chunks {
+    // SAFETY: SSE2 is part of the x86_64 baseline, and the unaligned load
+    // reads exactly the 16 bytes of `chunk`.
+    let mask = unsafe {
+      let bytes = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+      let ends = _mm_cmpeq_epi8(bytes, _mm_set1_epi8(END as i8));
+      let escs = _mm_cmpeq_epi8(bytes, _mm_set1_epi8(ESC as i8));
+      _mm_movemask_epi8(_mm_or_si128(ends, escs))
+    };
+    if mask != 0 {
+      return Some(offset + mask.trailing_zeros() as usize);
+    }
+    offset += 16;
+  }
+
+  find_special_scalar(chunks.remainder()).map(|idx| offset + idx)
+}
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+pub(crate) fn find_special(haystack: &[u8]) -> Option<usize> {
+  use core::arch::aarch64::*;
+
+  let mut chunks = haystack.chunks_exact(16);
+  let mut offset = 0;
+  for chunk in &mut chunks {
+    // SAFETY: NEON is part of the aarch64 baseline, and the load reads
+    // exactly the 16 bytes of `chunk`.
+    let found = unsafe {
+      let bytes = vld1q_u8(chunk.as_ptr());
+      let ends = vceqq_u8(bytes, vdupq_n_u8(END));
+      let escs = vceqq_u8(bytes, vdupq_n_u8(ESC));
+      vmaxvq_u8(vorrq_u8(ends, escs)) != 0
+    };
+    if found {
+      return find_special_scalar(chunk).map(|idx| offset + idx);
+    }
+    offset += 16;
+  }
+
+  find_special_scalar(chunks.remainder()).map(|idx| offset + idx)
+}
+
+#[cfg(all(
+  feature = "memchr",
+  not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64")))
+))]
+pub(crate) fn find_special(haystack: &[u8]) -> Option<usize> {
+  memchr::memchr2(END, ESC, haystack)
+}
+
+#[cfg(not(any(
+  feature = "memchr",
+  all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))
+)))]
+pub(crate) fn find_special(haystack: &[u8]) -> Option<usize> {
+  find_special_scalar(haystack)
+}
+
+// Unused when only the `memchr` feature provides `find_special`.
+#[allow(dead_code)]
+fn find_special_scalar(haystack: &[u8]) -> Option<usize> {
+  haystack
+    .iter()
+    .position(|byte| *byte == END || *byte == ESC)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_end_bytes() {
+    let mut haystack: Vec<u8> = (0..200).map(|idx| (idx % 0xC0) as u8 | 0x01).collect();
+    assert_eq!(find_end(&haystack), None);
+
+    haystack[37] = END;
+    haystack[150] = END;
+    assert_eq!(find_end(&haystack), Some(37));
+    assert_eq!(find_end(&haystack[38..]), Some(112));
+  }
+
+  #[test]
+  fn finds_special_bytes_at_every_position() {
+    for len in 0..40 {
+      let haystack: Vec<u8> = vec![0x01; len];
+      assert_eq!(find_special(&haystack), None);
+
+      for idx in 0..len {
+        for special in [END, ESC] {
+          let mut haystack = haystack.clone();
+          haystack[idx] = special;
+          if idx + 1 < len {
+            haystack[len - 1] = END;
+          }
+          assert_eq!(find_special(&haystack), Some(idx), "{} of {}", idx, len);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,255 @@
+use super::*;
+use alloc::vec::Vec;
+use core::fmt;
+use embedded_hal_nb::nb;
+use embedded_hal_nb::serial::{ErrorType, Read, Write};
+
+/// An error from receiving frames over an `embedded_hal_nb` serial port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NbSlipError<E> {
+  /// The serial port failed.
+  Serial(E),
+  /// A frame was received that wasn't encoded correctly.
+  Decode(SlipError),
+}
+
+impl<E: fmt::Debug> fmt::Display for NbSlipError<E> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      NbSlipError::Serial(error) => write!(f, "serial error: {:?}", error),
+      NbSlipError::Decode(error) => fmt::Display::fmt(error, f),
+    }
+  }
+}
+
+/// Sends and receives frames following the SLIP protocol over a
+/// non-blocking `embedded_hal_nb` serial port.
+///
+/// `read_frame` pumps every byte the port has available into a
+/// `SlipDecoder`, returning `nb::Error::WouldBlock` until a frame is
+/// complete. Frames to send are queued with `queue_frame` and written a
+/// byte at a time by `flush`, which returns `nb::Error::WouldBlock` while
+/// the port can't take any more data. Both can be called from a polling
+/// loop, or wrapped in `nb::block!` to wait.
+///
+/// # Example:
+///
+/// ```rust
+/// use embedded_hal_nb::nb;
+/// use embedded_hal_nb::serial::{ErrorKind, ErrorType, Read, Write};
+/// use simple_slip::NbSlip;
+///
+/// struct Loopback(std::collections::VecDeque<u8>);
+///
+/// impl ErrorType for Loopback {
+///   type Error = ErrorKind;
+/// }
+///
+/// impl Read for Loopback {
+///   fn read(&mut self) -> nb::Result<u8, ErrorKind> {
+///     self.0.pop_front().ok_or(nb::Error::WouldBlock)
+///   }
+/// }
+///
+/// impl Write for Loopback {
+///   fn write(&mut self, byte: u8) -> nb::Result<(), ErrorKind> {
+///     self.0.push_back(byte);
+///     Ok(())
+///   }
+///
+///   fn flush(&mut self) -> nb::Result<(), ErrorKind> {
+///     Ok(())
+///   }
+/// }
+///
+/// let mut slip = NbSlip::new(Loopback(Default::default()));
+/// assert_eq!(slip.read_frame(), Err(nb::Error::WouldBlock));
+///
+/// slip.queue_frame(&[0x01, 0xC0]);
+/// nb::block!(slip.flush()).unwrap();
+///
+/// assert_eq!(nb::block!(slip.read_frame()).unwrap(), [0x01, 0xC0]);
+/// ```
+#[derive(Debug)]
+pub struct NbSlip<S> {
+  serial: S,
+  decoder: SlipDecoder,
+  encoder: SlipEncoder,
+  pending: Option<u8>,
+}
+
+impl<S: ErrorType> NbSlip<S> {
+  /// Creates a new `NbSlip` over `serial`.
+  pub fn new(serial: S) -> Self {
+    Self::with_decoder(serial, SlipDecoder::new())
+  }
+
+  /// Creates a new `NbSlip` over `serial` that decodes with `decoder`, for
+  /// example one created with `SlipDecoder::with_capacity`.
+  pub fn with_decoder(serial: S, decoder: SlipDecoder) -> Self {
+    Self {
+      serial,
+      decoder,
+      encoder: SlipEncoder::new(),
+      pending: None,
+    }
+  }
+
+  /// Queues `payload` to be encoded as a single frame and written by
+  /// `flush`.
+  pub fn queue_frame(&mut self, payload: &[u8]) {
+    // The encoder has no MTU, so it takes any payload.
+    let _ = self.encoder.push(payload);
+  }
+
+  /// Returns a reference to the serial port.
+  pub fn get_ref(&self) -> &S {
+    &self.serial
+  }
+
+  /// Returns a mutable reference to the serial port.
+  pub fn get_mut(&mut self) -> &mut S {
+    &mut self.serial
+  }
+
+  /// Consumes the `NbSlip`, returning the serial port.
+  ///
+  /// Any buffered data that hasn't been returned as a frame or written out
+  /// is lost.
+  pub fn into_inner(self) -> S {
+    self.serial
+  }
+}
+
+impl<S: Read> NbSlip<S> {
+  /// Reads bytes from the serial port until a frame is complete.
+  ///
+  /// Returns `nb::Error::WouldBlock` once the port has no more data
+  /// available, keeping any partial frame for the next call. A frame that
+  /// isn't encoded correctly is returned as `NbSlipError::Decode`, after
+  /// which the following frames can still be read.
+  pub fn read_frame(&mut self) -> nb::Result<Vec<u8>, NbSlipError<S::Error>> {
+    loop {
+      if let Some(frame) = self.decoder.next_frame() {
+        return frame.map_err(|error| nb::Error::Other(NbSlipError::Decode(error)));
+      }
+
+      let byte = self
+        .serial
+        .read()
+        .map_err(|error| error.map(NbSlipError::Serial))?;
+      self.decoder.push(&[byte]);
+    }
+  }
+}
+
+impl<S: Write> NbSlip<S> {
+  /// Writes the queued frames to the serial port and flushes it.
+  ///
+  /// Returns `nb::Error::WouldBlock` while the port can't take any more
+  /// data, picking up where it left off on the next call.
+  pub fn flush(&mut self) -> nb::Result<(), S::Error> {
+    loop {
+      let byte = match self.pending {
+        Some(byte) => byte,
+        None => {
+          let mut byte = [0u8; 1];
+          if self.encoder.pull(&mut byte) == 0 {
+            break;
+          }
+          byte[0]
+        }
+      };
+
+      self.pending = Some(byte);
+      self.serial.write(byte)?;
+      self.pending = None;
+    }
+
+    self.serial.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use embedded_hal_nb::serial::ErrorKind;
+
+  #[derive(Default)]
+  struct Flaky {
+    input: Vec<u8>,
+    output: Vec<u8>,
+    ready: bool,
+  }
+
+  impl ErrorType for Flaky {
+    type Error = ErrorKind;
+  }
+
+  impl Read for Flaky {
+    fn read(&mut self) -> nb::Result<u8, ErrorKind> {
+      self.ready = !self.ready;
+      if !self.ready || self.input.is_empty() {
+        return Err(nb::Error::WouldBlock);
+      }
+
+      Ok(self.input.remove(0))
+    }
+  }
+
+  impl Write for Flaky {
+    fn write(&mut self, byte: u8) -> nb::Result<(), ErrorKind> {
+      self.ready = !self.ready;
+      if !self.ready {
+        return Err(nb::Error::WouldBlock);
+      }
+
+      self.output.push(byte);
+      Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), ErrorKind> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn writes_frames_through_would_block() {
+    let payloads: [&[u8]; 2] = [&[0x01, ESC, 0x49, END, 0x15], &[END]];
+    let mut slip = NbSlip::new(Flaky::default());
+
+    for payload in payloads {
+      slip.queue_frame(payload);
+    }
+    let mut blocked = 0;
+    while let Err(nb::Error::WouldBlock) = slip.flush() {
+      blocked += 1;
+    }
+
+    let expected: Vec<u8> = payloads
+      .iter()
+      .flat_map(|payload| encode(payload).unwrap())
+      .collect();
+    assert_eq!(slip.into_inner().output, expected);
+    assert!(blocked > 0);
+  }
+
+  #[test]
+  fn reads_frames_through_would_block() {
+    let serial = Flaky {
+      input: vec![END, 0x01, ESC, ESC_END, END, END, 0x02, ESC, 0x03, END],
+      ..Flaky::default()
+    };
+    let mut slip = NbSlip::new(serial);
+
+    assert_eq!(nb::block!(slip.read_frame()), Ok(vec![0x01, END]));
+    assert_eq!(
+      nb::block!(slip.read_frame()),
+      Err(NbSlipError::Decode(SlipError::InvalidEscapeByte {
+        found: 0x03
+      }))
+    );
+    assert_eq!(slip.read_frame(), Err(nb::Error::WouldBlock));
+  }
+}
@@ -0,0 +1,96 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// A destination that encoded or decoded bytes are written to.
+///
+/// `encode_to` and `decode_to` write their output through this trait, so
+/// the same code path fills a `Vec<u8>`, a fixed `&mut [u8]` buffer, a
+/// `heapless::Vec`, a `BytesMut`, or any `std::io::Write` through an
+/// `IoSink`, depending on the features enabled. Implementing it for another
+/// buffer type is all it takes to encode and decode straight into that too.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{encode_to, ByteSink, SlipError};
+///
+/// struct Checksum(u8);
+///
+/// impl ByteSink for Checksum {
+///   type Error = SlipError;
+///
+///   fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SlipError> {
+///     self.0 = bytes.iter().fold(self.0, |sum, byte| sum.wrapping_add(*byte));
+///     Ok(())
+///   }
+/// }
+///
+/// let mut checksum = Checksum(0);
+/// encode_to(&[0x01, 0x02], &mut checksum).unwrap();
+///
+/// assert_eq!(checksum.0, 0xC0u8.wrapping_mul(2).wrapping_add(0x03));
+/// ```
+pub trait ByteSink {
+  /// The error returned when the sink can't take any more bytes.
+  type Error;
+
+  /// Appends `bytes` to the sink.
+  fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+  /// Hints that around `additional` more bytes are about to be written, so
+  /// a growable sink can allocate once up front. Does nothing by default.
+  fn reserve(&mut self, additional: usize) {
+    let _ = additional;
+  }
+}
+
+impl ByteSink for Vec<u8> {
+  type Error = SlipError;
+
+  fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SlipError> {
+    self.extend_from_slice(bytes);
+    Ok(())
+  }
+
+  fn reserve(&mut self, additional: usize) {
+    Vec::reserve(self, additional);
+  }
+}
+
+/// Writes to the start of the slice, then shrinks it to the part that's
+/// left, just like `std::io::Write` does for `&mut [u8]`. Runs out with
+/// `SlipError::BufferTooSmall`.
+impl ByteSink for &mut [u8] {
+  type Error = SlipError;
+
+  fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SlipError> {
+    if bytes.len() > self.len() {
+      return Err(SlipError::BufferTooSmall);
+    }
+
+    let (head, tail) = core::mem::take(self).split_at_mut(bytes.len());
+    head.copy_from_slice(bytes);
+    *self = tail;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn slices_shrink_as_they_are_written() {
+    let mut buffer = [0u8; 4];
+    let mut sink: &mut [u8] = &mut buffer;
+
+    assert_eq!(sink.write_bytes(&[0x01, 0x02]), Ok(()));
+    assert_eq!(
+      sink.write_bytes(&[0x03, 0x04, 0x05]),
+      Err(SlipError::BufferTooSmall)
+    );
+    assert_eq!(sink.write_bytes(&[0x03]), Ok(()));
+    assert_eq!(sink.len(), 1);
+    assert_eq!(buffer, [0x01, 0x02, 0x03, 0x00]);
+  }
+}
@@ -0,0 +1,266 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// The byte that delimits SLIP6 frames, the character `p`.
+pub const SLIP6_END: u8 = 0x70;
+
+// The character for six bits of zeroes, `0`. The 64 characters from here up
+// to `SLIP6_END` each carry six bits of the payload.
+const BASE: u8 = 0x30;
+
+/// Encodes data using the SLIP6 encoding, where every six bits of the
+/// payload are sent as one printable character.
+///
+/// SLIP6 was used by Linux's `slip6` line discipline and old `sliplogin`
+/// setups, to carry IP over links that only pass printable text. Each
+/// character is 0x30 plus six bits of the payload, taking three bytes to
+/// four characters, and frames are delimited by `SLIP6_END` (`p`) at both
+/// ends, just like `END` in SLIP.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{slip6_decode, slip6_encode};
+///
+/// let encoded = slip6_encode(&[0xC0, 0x01]);
+///
+/// assert_eq!(encoded, b"p`04p");
+/// assert_eq!(slip6_decode(&encoded).unwrap(), [0xC0, 0x01]);
+/// ```
+pub fn slip6_encode(raw_buffer: &[u8]) -> Vec<u8> {
+  let mut encoded_buffer: Vec<u8> = Vec::with_capacity(raw_buffer.len() + raw_buffer.len() / 3 + 3);
+  // Appending to a `Vec` never fails.
+  let _ = slip6_encode_to(raw_buffer, &mut encoded_buffer);
+
+  encoded_buffer
+}
+
+/// Decodes the first SLIP6 frame in `encoded_buffer`, between its
+/// `SLIP6_END` delimiters, like `decode`.
+///
+/// Any characters that can't be part of a SLIP6 frame, such as line breaks,
+/// are ignored, as are leftover bits at the end of the frame. Returns
+/// `SlipError::NoEndDelimiter` if there's no complete frame.
+pub fn slip6_decode<T: AsRef<[u8]>>(encoded_buffer: T) -> Result<Vec<u8>, SlipError> {
+  let mut decoder = Slip6Decoder::new();
+  for byte in encoded_buffer.as_ref() {
+    if let Some(frame) = decoder.push_byte(*byte) {
+      return frame.map(<[u8]>::to_vec);
+    }
+  }
+
+  Err(SlipError::NoEndDelimiter)
+}
+
+// Writes `raw_buffer` to `sink` as a SLIP6 frame.
+fn slip6_encode_to<S: ByteSink>(raw_buffer: &[u8], sink: &mut S) -> Result<(), S::Error> {
+  sink.write_bytes(&[SLIP6_END])?;
+
+  // Every three bytes make exactly four characters, and a shorter group at
+  // the end is padded out with zero bits to the next character.
+  for group in raw_buffer.chunks(3) {
+    let bits = group.iter().enumerate().fold(0u32, |bits, (idx, byte)| {
+      bits | u32::from(*byte) << (16 - 8 * idx)
+    });
+    let mut chars = [0u8; 4];
+    for (idx, char) in chars.iter_mut().enumerate() {
+      *char = BASE + (bits >> (18 - 6 * idx) & 0x3F) as u8;
+    }
+    sink.write_bytes(&chars[..group.len() + 1])?;
+  }
+
+  sink.write_bytes(&[SLIP6_END])
+}
+
+/// The SLIP6 framing, as a `Framer` that encodes frames like
+/// `slip6_encode` and decodes them with a `Slip6Decoder`, so it can be
+/// given to `SlipWriter` and `SlipCodec`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{Framer, Slip6};
+///
+/// let mut encoded: Vec<u8> = Vec::new();
+/// Slip6.encode_frame(&[0xC0, 0x01], &mut encoded).unwrap();
+///
+/// assert_eq!(encoded, b"p`04p");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Slip6;
+
+impl Framer for Slip6 {
+  type Decoder = Slip6Decoder;
+
+  fn encode_frame<S>(&self, payload: &[u8], sink: &mut S) -> Result<(), S::Error>
+  where
+    S: ByteSink,
+    S::Error: From<SlipError>,
+  {
+    slip6_encode_to(payload, sink)
+  }
+
+  fn max_overhead(&self, payload_len: usize) -> usize {
+    payload_len.div_ceil(3) + 2
+  }
+
+  fn decoder(&self) -> Slip6Decoder {
+    Slip6Decoder::new()
+  }
+}
+
+/// A streaming decoder for SLIP6 frames.
+///
+/// The frames are found by a `SlipDecoder` delimiting on `SLIP6_END`, so
+/// they're split exactly like SLIP frames, with anything before the first
+/// delimiter discarded, and then turned back from characters into bytes.
+/// Empty frames are returned rather than skipped, as that's how an empty
+/// payload is encoded. It's also a `FrameDecoder`, so it can be given to
+/// `SlipReader`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::Slip6Decoder;
+///
+/// let mut decoder = Slip6Decoder::new();
+///
+/// decoder.push(b"p`0");
+/// assert!(decoder.next_frame().is_none());
+///
+/// decoder.push(b"4pp");
+/// assert_eq!(decoder.next_frame().unwrap().unwrap(), [0xC0, 0x01]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Slip6Decoder {
+  framer: SlipDecoder,
+  frame: Vec<u8>,
+}
+
+impl Slip6Decoder {
+  /// Creates a new decoder with no buffered data.
+  pub fn new() -> Self {
+    Self::with_config(Self::config())
+  }
+
+  /// Creates a new decoder that rejects frames longer than `limit` bytes
+  /// once decoded, with `SlipError::FrameTooLarge`.
+  ///
+  /// The limit is checked against the characters received, so a frame
+  /// padded out with characters that are ignored can be rejected even if
+  /// it would have fit.
+  pub fn with_limit(limit: usize) -> Self {
+    Self::with_config(Self::config().max_frame_len(limit + limit.div_ceil(3)))
+  }
+
+  /// Pushes a chunk of encoded data into the decoder.
+  ///
+  /// Any frames completed by this chunk are queued and can be retrieved
+  /// with `next_frame`.
+  pub fn push(&mut self, chunk: &[u8]) {
+    self.framer.push(chunk);
+  }
+
+  /// Pushes a single byte of encoded data into the decoder, returning the
+  /// frame it completes directly rather than queueing it, like
+  /// `SlipDecoder::push_byte`.
+  pub fn push_byte(&mut self, byte: u8) -> Option<Result<&[u8], SlipError>> {
+    match self.framer.push_byte(byte)? {
+      Ok(chars) => {
+        self.frame.clear();
+        unpack(chars, &mut self.frame);
+        Some(Ok(&self.frame))
+      }
+      Err(error) => Some(Err(error)),
+    }
+  }
+
+  /// Returns the oldest complete frame, if there is one.
+  pub fn next_frame(&mut self) -> Option<Result<Vec<u8>, SlipError>> {
+    let chars = self.framer.next_frame()?;
+
+    Some(chars.map(|chars| {
+      let mut frame: Vec<u8> = Vec::with_capacity(chars.len() * 3 / 4);
+      unpack(&chars, &mut frame);
+      frame
+    }))
+  }
+
+  // Returns the config for finding SLIP6 frames, which never contain the
+  // `ESC` byte, so a stray one from line noise is dropped along with the
+  // rest of the noise.
+  fn config() -> SlipConfig {
+    SlipConfig::new()
+      .end(SLIP6_END)
+      .empty_frames(EmptyFramePolicy::Yield)
+      .invalid_escapes(InvalidEscapePolicy::Keep)
+  }
+
+  fn with_config(config: SlipConfig) -> Self {
+    Self {
+      framer: SlipDecoder::with_config(config),
+      frame: Vec::new(),
+    }
+  }
+}
+
+impl Default for Slip6Decoder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl FrameDecoder for Slip6Decoder {
+  fn push_decode(&mut self, byte: u8) -> Option<Result<&[u8], SlipError>> {
+    self.push_byte(byte)
+  }
+}
+
+// Turns the characters of a frame back into bytes, skipping any that aren't
+// SLIP6 characters.
+fn unpack(chars: &[u8], frame: &mut Vec<u8>) {
+  let mut bits: u32 = 0;
+  let mut len = 0;
+  for char in chars {
+    if !(BASE..SLIP6_END).contains(char) {
+      continue;
+    }
+
+    bits = bits << 6 | u32::from(char - BASE);
+    len += 6;
+    if len >= 8 {
+      len -= 8;
+      frame.push((bits >> len) as u8);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_every_length() {
+    let payload: Vec<u8> = (0..=255).rev().collect();
+
+    for len in 0..payload.len() {
+      let encoded = slip6_encode(&payload[..len]);
+      assert_eq!(encoded.len(), len + len.div_ceil(3) + 2);
+      assert!(encoded.iter().all(u8::is_ascii_graphic));
+      assert_eq!(slip6_decode(&encoded), Ok(payload[..len].to_vec()));
+    }
+  }
+
+  #[test]
+  fn ignores_line_noise() {
+    let mut decoder = Slip6Decoder::with_limit(4);
+    decoder.push(b"junkp`0\r\n4p");
+    decoder.push(&[SLIP6_END, ESC, 0x30, ESC, SLIP6_END]);
+    decoder.push(b"p0000000p");
+
+    assert_eq!(decoder.next_frame(), Some(Ok(vec![0xC0, 0x01])));
+    assert_eq!(decoder.next_frame(), Some(Ok(vec![])));
+    assert_eq!(decoder.next_frame(), Some(Err(SlipError::FrameTooLarge)));
+    assert_eq!(decoder.next_frame(), None);
+  }
+}
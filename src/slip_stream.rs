@@ -0,0 +1,296 @@
+use super::*;
+use crate::async_core::{FrameReader, FrameWriter};
+use crate::connection::millis_since;
+use crate::reader::timed_out;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+/// A SLIP framed connection over any tokio `AsyncRead` and `AsyncWrite`.
+///
+/// This bundles up encoding, decoding and buffering, so frames can be sent
+/// and received directly without assembling a codec, `Framed`, and the
+/// stream and sink halves by hand. `FuturesSlipStream` is its counterpart
+/// for the `futures_io` traits, and shares the same framing logic.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::SlipStream;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let (client, server) = tokio::io::duplex(64);
+/// let mut client = SlipStream::new(client);
+/// let mut server = SlipStream::new(server);
+///
+/// client.send(&[0x01, 0xC0]).await.unwrap();
+///
+/// assert_eq!(server.next_frame().await.unwrap(), Some(vec![0x01, 0xC0]));
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct SlipStream<T> {
+  inner: T,
+  reader: FrameReader,
+  writer: FrameWriter,
+  keepalive: Option<(Keepalive, Instant)>,
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> SlipStream<T> {
+  /// Creates a new framed connection over `inner`.
+  pub fn new(inner: T) -> Self {
+    Self::with_decoder(inner, SlipDecoder::new())
+  }
+
+  /// Creates a new framed connection over `inner` that decodes with
+  /// `decoder`, for example one created with `SlipDecoder::with_limit`.
+  pub fn with_decoder(inner: T, decoder: SlipDecoder) -> Self {
+    Self {
+      inner,
+      reader: FrameReader::new(decoder),
+      writer: FrameWriter::default(),
+      keepalive: None,
+    }
+  }
+
+  /// Supervises the link with `keepalive`, counting time in milliseconds
+  /// from now.
+  ///
+  /// Frames sent with `send` count as traffic, and `next_frame_supervised`
+  /// sends heartbeats, drops the ones received, and reports the link going
+  /// down.
+  pub fn set_keepalive(&mut self, keepalive: Keepalive) {
+    self.keepalive = Some((keepalive, Instant::now()));
+  }
+
+  /// Returns the `Keepalive` supervising the link, if there is one.
+  pub fn keepalive(&self) -> Option<&Keepalive> {
+    self.keepalive.as_ref().map(|(keepalive, _)| keepalive)
+  }
+
+  /// Encodes `payload` as a single frame, then writes and flushes it.
+  ///
+  /// # Cancel safety
+  ///
+  /// If the future is dropped after it was first polled, the frame stays
+  /// buffered and is written out ahead of the frame from the next `send`.
+  pub async fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+    let Self { inner, writer, .. } = self;
+    writer.push(payload);
+
+    poll_fn(|cx| writer.poll_write(0, |buffer| Pin::new(&mut *inner).poll_write(cx, buffer)))
+      .await?;
+    inner.flush().await?;
+    if let Some((keepalive, start)) = &mut self.keepalive {
+      keepalive.on_sent(millis_since(*start));
+    }
+    Ok(())
+  }
+
+  /// Receives the next complete frame.
+  ///
+  /// Returns `None` once the connection has been closed. A frame that isn't
+  /// encoded correctly is returned as an `io::ErrorKind::InvalidData` error,
+  /// after which the following frames can still be received.
+  ///
+  /// # Cancel safety
+  ///
+  /// This method is cancel safe. Every byte read is handed to the decoder
+  /// before the future yields, so if it's dropped part way through a frame,
+  /// for example by losing a `select!` race, the partial frame is kept and
+  /// the next call carries on from where this one stopped.
+  pub async fn next_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+    let Self { inner, reader, .. } = self;
+
+    poll_fn(|cx| reader.poll_frame(|buffer| poll_read(Pin::new(&mut *inner), cx, buffer)))
+      .await
+      .transpose()
+  }
+
+  /// Receives the next complete frame, giving up once `timeout` has passed.
+  ///
+  /// If no complete frame arrives in time, an `io::ErrorKind::TimedOut`
+  /// error is returned. As `next_frame` is cancel safe, any part of a frame
+  /// received so far is kept for the next call, which makes this suitable
+  /// for watchdog style supervision of a link.
+  pub async fn next_frame_timeout(&mut self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+    match tokio::time::timeout(timeout, self.next_frame()).await {
+      Ok(result) => result,
+      Err(_) => Err(timed_out()),
+    }
+  }
+
+  /// Receives the next complete frame, giving up once `deadline` is reached.
+  ///
+  /// This works just like `next_frame_timeout`, but with a fixed point in
+  /// time, so a deadline can be shared across several calls.
+  pub async fn next_frame_deadline(&mut self, deadline: Instant) -> io::Result<Option<Vec<u8>>> {
+    let deadline = tokio::time::Instant::from_std(deadline);
+
+    match tokio::time::timeout_at(deadline, self.next_frame()).await {
+      Ok(result) => result,
+      Err(_) => Err(timed_out()),
+    }
+  }
+
+  /// Receives the next frame that isn't a heartbeat, while supervising the
+  /// link with the `Keepalive` given to `set_keepalive`.
+  ///
+  /// Heartbeats are sent whenever nothing else has been sent for the
+  /// keepalive interval. If nothing at all is received within the keepalive
+  /// timeout, an `io::ErrorKind::NotConnected` error wrapping
+  /// `SlipError::LinkDown` is returned, and the next call carries on
+  /// waiting. Without a keepalive, this is the same as `next_frame`.
+  ///
+  /// # Cancel safety
+  ///
+  /// This method is cancel safe, in the same way as `next_frame`.
+  pub async fn next_frame_supervised(&mut self) -> io::Result<Option<Vec<u8>>> {
+    loop {
+      let Some((keepalive, start)) = &mut self.keepalive else {
+        return self.next_frame().await;
+      };
+      let now = millis_since(*start);
+      if keepalive.poll(now) == Some(LinkEvent::LinkDown) {
+        return Err(SlipError::LinkDown.into());
+      }
+      let heartbeat = keepalive.poll_heartbeat(now).map(<[u8]>::to_vec);
+      let deadline = *start + Duration::from_millis(keepalive.next_deadline());
+
+      if let Some(heartbeat) = heartbeat {
+        self.send(&heartbeat).await?;
+      }
+      match self.next_frame_deadline(deadline).await {
+        Ok(Some(frame)) => {
+          if let Some((keepalive, start)) = &mut self.keepalive {
+            if keepalive.on_received(&frame, millis_since(*start)) {
+              continue;
+            }
+          }
+          return Ok(Some(frame));
+        }
+        Err(error) if error.kind() == io::ErrorKind::TimedOut => {}
+        result => return result,
+      }
+    }
+  }
+
+  /// Returns a reference to the inner connection.
+  pub fn get_ref(&self) -> &T {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner connection.
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.inner
+  }
+
+  /// Consumes the `SlipStream`, returning the inner connection.
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+fn poll_read<R: AsyncRead>(
+  reader: Pin<&mut R>,
+  cx: &mut Context<'_>,
+  buffer: &mut [u8],
+) -> Poll<io::Result<usize>> {
+  let mut read_buffer = ReadBuf::new(buffer);
+  ready!(reader.poll_read(cx, &mut read_buffer))?;
+
+  Poll::Ready(Ok(read_buffer.filled().len()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn exchanges_frames_over_duplex() {
+    let (client, server) = tokio::io::duplex(4);
+    let mut client = SlipStream::new(client);
+    let mut server = SlipStream::new(server);
+
+    let sending = async {
+      client.send(&[0x01, ESC, 0x02]).await.unwrap();
+      client.send(&[END; 6]).await.unwrap();
+      drop(client);
+    };
+    let receiving = async {
+      let mut frames = Vec::new();
+      while let Some(frame) = server.next_frame().await.unwrap() {
+        frames.push(frame);
+      }
+      frames
+    };
+    let ((), frames) = tokio::join!(sending, receiving);
+
+    assert_eq!(frames, [vec![0x01, ESC, 0x02], vec![END; 6]]);
+  }
+
+  #[tokio::test]
+  async fn next_frame_is_cancel_safe() {
+    let (mut client, server) = tokio::io::duplex(64);
+    let mut server = SlipStream::new(server);
+    let mut chunks = [&[END, 0x01][..], &[ESC], &[ESC_END, 0x02], &[END]].into_iter();
+    let mut cancelled = 0;
+
+    let frame = loop {
+      tokio::select! {
+        frame = server.next_frame() => break frame.unwrap(),
+        _ = tokio::task::yield_now() => {
+          cancelled += 1;
+          if let Some(chunk) = chunks.next() {
+            client.write_all(chunk).await.unwrap();
+          }
+        }
+      }
+    };
+
+    assert_eq!(frame, Some(vec![0x01, END, 0x02]));
+    assert!(cancelled >= 4);
+  }
+
+  #[tokio::test]
+  async fn supervises_the_link() {
+    let (client, server) = tokio::io::duplex(64);
+    let mut client = SlipStream::new(client);
+    let mut server = SlipStream::new(server);
+    client.set_keepalive(Keepalive::new(&[0xFF], 20, 100));
+
+    let error = client.next_frame_supervised().await.unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotConnected);
+    assert_eq!(server.next_frame().await.unwrap(), Some(vec![0xFF]));
+
+    server.send(&[0xFF]).await.unwrap();
+    server.send(&[0x01]).await.unwrap();
+    let frame = client.next_frame_supervised().await.unwrap();
+    assert_eq!(frame, Some(vec![0x01]));
+    assert!(client.keepalive().unwrap().is_up());
+  }
+
+  #[tokio::test]
+  async fn keeps_partial_frame_across_timeouts() {
+    let (mut client, server) = tokio::io::duplex(64);
+    let mut server = SlipStream::new(server);
+
+    client.write_all(&[END, 0x01, ESC]).await.unwrap();
+    let error = server
+      .next_frame_timeout(Duration::from_millis(10))
+      .await
+      .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+
+    let deadline = Instant::now() + Duration::from_millis(10);
+    let error = server.next_frame_deadline(deadline).await.unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+
+    client.write_all(&[ESC_ESC, END]).await.unwrap();
+    let frame = server.next_frame_timeout(Duration::from_secs(5)).await;
+    assert_eq!(frame.unwrap(), Some(vec![0x01, ESC]));
+  }
+}
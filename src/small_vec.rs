@@ -0,0 +1,92 @@
+use super::*;
+use crate::encoder::encoded_len;
+use smallvec::SmallVec;
+
+/// Encodes data following the SLIP protocol into a `SmallVec`.
+///
+/// Encoded frames of up to `N` bytes are kept inline, and only larger ones
+/// are moved to the heap.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_smallvec;
+///
+/// let result = encode_smallvec::<16>(&[0x01, 0xDB, 0x49, 0xC0, 0x15]);
+///
+/// assert!(!result.spilled());
+/// assert_eq!(&result[..], [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0]);
+/// ```
+pub fn encode_smallvec<const N: usize>(raw_buffer: &[u8]) -> SmallVec<[u8; N]> {
+  let mut encoded_buffer: SmallVec<[u8; N]> = SmallVec::with_capacity(encoded_len(raw_buffer));
+
+  // Appending to a `SmallVec` never fails.
+  let _ = encode_to(raw_buffer, &mut encoded_buffer);
+
+  encoded_buffer
+}
+
+/// Decodes data following the SLIP protocol into a `SmallVec`.
+///
+/// This works just like `decode`, except that decoded frames of up to `N`
+/// bytes are kept inline, so a stream of small frames, such as sensor
+/// readings, is decoded without allocating. Larger frames are still
+/// decoded, but moved to the heap.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::decode_smallvec;
+///
+/// let input: Vec<u8> = vec![0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+///
+/// let result = decode_smallvec::<64>(&input).unwrap();
+///
+/// assert!(!result.spilled());
+/// assert_eq!(&result[..], [0x01, 0xDB, 0x49, 0xC0, 0x15]);
+/// ```
+pub fn decode_smallvec<const N: usize>(
+  encoded_buffer: &[u8],
+) -> Result<SmallVec<[u8; N]>, SlipError> {
+  let mut decoded_buffer: SmallVec<[u8; N]> = SmallVec::new();
+
+  decode_to(encoded_buffer, &mut decoded_buffer)?;
+
+  Ok(decoded_buffer)
+}
+
+impl<const N: usize> ByteSink for SmallVec<[u8; N]> {
+  type Error = SlipError;
+
+  fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SlipError> {
+    self.extend_from_slice(bytes);
+    Ok(())
+  }
+
+  // Escape sequences can make an encoding longer than `N` bytes even though
+  // the frame itself fits, so this doesn't reserve, and only spills to the
+  // heap once it's actually needed.
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keeps_small_frames_inline() {
+    let input: [u8; 5] = [0x01, ESC, 0x49, END, 0x15];
+
+    let encoded = encode_smallvec::<16>(&input);
+    let decoded = decode_smallvec::<8>(&encoded).unwrap();
+    assert!(!encoded.spilled());
+    assert!(!decoded.spilled());
+    assert_eq!(decoded.as_slice(), input);
+
+    let decoded = decode_smallvec::<5>(&encoded).unwrap();
+    assert!(!decoded.spilled());
+
+    let decoded = decode_smallvec::<2>(&encoded).unwrap();
+    assert!(decoded.spilled());
+    assert_eq!(decoded.as_slice(), input);
+  }
+}
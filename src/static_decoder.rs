@@ -0,0 +1,165 @@
+use super::*;
+
+/// A fixed-capacity streaming decoder for data following the SLIP protocol.
+///
+/// The frame in progress is kept in an inline `[u8; N]` buffer, so the
+/// decoder never allocates and can be created in a `const` context, for
+/// example to place it in a `static` on a bare-metal target. Frames longer
+/// than `N` bytes are rejected with `SlipError::FrameTooLarge`.
+///
+/// Like `SlipDecoder`, any bytes received before the first `END` (0xC0)
+/// byte are discarded.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{SlipError, StaticSlipDecoder};
+///
+/// let mut decoder: StaticSlipDecoder<4> = StaticSlipDecoder::new();
+/// let mut frames: Vec<Result<Vec<u8>, SlipError>> = Vec::new();
+///
+/// for byte in [0xC0, 0x01, 0xDB, 0xDC, 0xC0, 0x01, 0x02, 0x03, 0x04, 0x05, 0xC0] {
+///   if let Some(frame) = decoder.push_byte(byte) {
+///     frames.push(frame.map(|frame| frame.to_vec()));
+///   }
+/// }
+///
+/// assert_eq!(frames, [Ok(vec![0x01, 0xC0]), Err(SlipError::FrameTooLarge)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticSlipDecoder<const N: usize> {
+  // Received bytes are appended here and decoded in place by `machine`.
+  buffer: [u8; N],
+  filled: usize,
+  machine: SlipMachine,
+}
+
+impl<const N: usize> StaticSlipDecoder<N> {
+  /// Creates a new decoder with no buffered data.
+  pub const fn new() -> Self {
+    Self {
+      buffer: [0; N],
+      filled: 0,
+      machine: SlipMachine::new(),
+    }
+  }
+
+  /// Pushes a single byte of encoded data into the decoder.
+  ///
+  /// If the byte completes a frame, the frame is returned, and stays
+  /// borrowed from the decoder until the next byte is pushed. Frames
+  /// containing an invalid escape sequence are returned as a
+  /// `SlipError::InvalidEscapeByte` or `SlipError::TruncatedEscape` error,
+  /// and frames longer than `N` bytes as a `SlipError::FrameTooLarge` error.
+  pub fn push_byte(&mut self, byte: u8) -> Option<Result<&[u8], SlipError>> {
+    if self.filled == N {
+      self.filled = self.machine.compact(&mut self.buffer);
+    }
+
+    let event = if self.filled < N {
+      self.buffer[self.filled] = byte;
+      self.filled += 1;
+      self.machine.advance(&mut self.buffer[..self.filled])
+    } else {
+      // The frame in progress fills the buffer, so only an `END` or `ESC`
+      // byte, which are never written to it, can follow.
+      if byte != END && byte != ESC {
+        self.machine.fail(SlipError::FrameTooLarge);
+        self.filled = self.machine.compact(&mut self.buffer);
+      }
+      self
+        .machine
+        .step(&mut self.buffer, byte)
+        .unwrap_or(SlipEvent::NeedMoreData)
+    };
+
+    match event {
+      SlipEvent::Frame(range) => Some(Ok(&self.buffer[range])),
+      SlipEvent::Error(error) => Some(Err(error)),
+      SlipEvent::NeedMoreData => None,
+    }
+  }
+
+  /// Returns the number of decoded bytes held for the frame in progress.
+  pub fn partial_len(&self) -> usize {
+    self.machine.partial_len()
+  }
+
+  /// Returns the decoder to the state it was created in, dropping the frame
+  /// in progress and waiting for the next `END` (0xC0) byte.
+  pub fn reset(&mut self) {
+    self.filled = 0;
+    self.machine = SlipMachine::new();
+  }
+
+  // Takes over the frame in progress from `machine`, which has decoded all
+  // of `buffer`, such as when a frame carries on past the end of a DMA half.
+  pub(crate) fn resume(&mut self, mut machine: SlipMachine, buffer: &mut [u8]) {
+    let mut filled = machine.compact(buffer);
+    if filled > N {
+      machine.fail(SlipError::FrameTooLarge);
+      filled = machine.compact(&mut buffer[..filled]);
+    }
+
+    self.buffer[..filled].copy_from_slice(&buffer[..filled]);
+    self.filled = filled;
+    self.machine = machine;
+  }
+}
+
+impl<const N: usize> Default for StaticSlipDecoder<N> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run<const N: usize>(
+    decoder: &mut StaticSlipDecoder<N>,
+    input: &[u8],
+  ) -> Vec<Result<Vec<u8>, SlipError>> {
+    input
+      .iter()
+      .filter_map(|byte| {
+        let frame = decoder.push_byte(*byte)?;
+        Some(frame.map(|frame| frame.to_vec()))
+      })
+      .collect()
+  }
+
+  #[test]
+  fn decodes_like_slip_decoder() {
+    let input: Vec<u8> = vec![
+      0xA1, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END, END, 0x02, ESC, 0x03, END,
+      0x04, END, 0x05,
+    ];
+    let mut expected = SlipDecoder::new();
+    expected.push(&input);
+    let mut decoder: StaticSlipDecoder<8> = StaticSlipDecoder::new();
+
+    let frames = run(&mut decoder, &input);
+
+    assert_eq!(frames, expected.drain().collect::<Vec<_>>());
+    assert_eq!(decoder.partial_len(), 1);
+  }
+
+  #[test]
+  fn rejects_frames_over_capacity() {
+    static DECODER: StaticSlipDecoder<2> = StaticSlipDecoder::new();
+    let mut decoder = DECODER.clone();
+
+    let frames = run(
+      &mut decoder,
+      &[END, 0x01, 0x02, 0x03, END, 0x04, ESC, ESC_END, END],
+    );
+
+    assert_eq!(frames, [Err(SlipError::FrameTooLarge), Ok(vec![0x04, END])]);
+
+    let frames = run(&mut decoder, &[0x05, 0x06, ESC, END, 0x07, END]);
+    assert_eq!(frames, [Err(SlipError::TruncatedEscape), Ok(vec![0x07])]);
+    assert_eq!(decoder.partial_len(), 0);
+  }
+}
@@ -0,0 +1,599 @@
+use super::*;
+use crate::encoder::escape_to;
+use alloc::collections::vec_deque::Drain;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// What a `SlipDecoder` does when a frame outgrows its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OverflowPolicy {
+  /// Discard the frame and report it as `SlipError::FrameTooLarge`.
+  #[default]
+  Error,
+  /// Silently discard the frame.
+  DropFrame,
+  /// Keep the most recent bytes of the frame, discarding the oldest ones.
+  DropOldest,
+}
+
+/// A streaming decoder for data following the SLIP protocol.
+///
+/// Encoded data can be pushed into the decoder in chunks of any size, as
+/// it's read from a serial port or socket. Complete frames are queued up
+/// and can be drained as they become available, while a partially received
+/// frame is kept internally until the rest of it arrives.
+///
+/// Like `decode_packets`, any bytes received before the first `END` (0xC0)
+/// byte are discarded, as they may be the tail of a frame that was sent
+/// before we started listening.
+///
+/// A frame with an invalid escape sequence doesn't need any help from the
+/// caller to recover from. The decoder discards the rest of it, up to the
+/// next `END` byte, queues a single error in its place, counts it in
+/// `resyncs`, and carries on decoding the frames after it. A
+/// `MalformedPolicy` in the decoder's config can drop the frame instead,
+/// or keep its raw bytes.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::SlipDecoder;
+///
+/// let mut decoder = SlipDecoder::new();
+///
+/// decoder.push(&[0xC0, 0x01, 0xDB, 0xDD]);
+/// assert!(decoder.next_frame().is_none());
+///
+/// decoder.push(&[0x49, 0xDB, 0xDC, 0x15, 0xC0]);
+/// assert_eq!(decoder.next_frame().unwrap().unwrap(), [0x01, 0xDB, 0x49, 0xC0, 0x15]);
+/// ```
+///
+/// With the `serde` feature enabled, the decoder can be serialized along
+/// with its partial frame and queued frames, so decoding can be checkpointed
+/// and resumed after a restart without losing data.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SlipDecoder {
+  frame: Vec<u8>,
+  frames: VecDeque<Result<Vec<u8>, SlipError>>,
+  limit: Option<usize>,
+  overflow: OverflowPolicy,
+  synced: bool,
+  escaped: bool,
+  complete: bool,
+  error: Option<SlipError>,
+  after_frame: bool,
+  config: SlipConfig,
+  resyncs: u64,
+  raw: bool,
+  oldest: usize,
+}
+
+impl SlipDecoder {
+  /// Creates a new decoder with no buffered data.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a new decoder that holds frames of up to `capacity` bytes.
+  ///
+  /// The frame buffer is allocated up front and never grows, so frames
+  /// longer than `capacity` are rejected with `SlipError::FrameTooLarge`.
+  /// Combined with `push_byte`, this makes the decoder allocation free.
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      frame: Vec::with_capacity(capacity),
+      ..Self::with_limit(capacity, OverflowPolicy::Error)
+    }
+  }
+
+  /// Creates a new decoder that buffers at most `limit` bytes of the frame
+  /// in progress, handling larger frames according to `policy`.
+  ///
+  /// Without a limit, a peer that never sends an `END` (0xC0) byte can make
+  /// the decoder buffer data without bound.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{OverflowPolicy, SlipDecoder};
+  ///
+  /// let mut decoder = SlipDecoder::with_limit(2, OverflowPolicy::DropOldest);
+  /// decoder.push(&[0xC0, 0x01, 0x02, 0x03, 0xC0]);
+  ///
+  /// assert_eq!(decoder.next_frame().unwrap().unwrap(), [0x02, 0x03]);
+  /// ```
+  pub fn with_limit(limit: usize, policy: OverflowPolicy) -> Self {
+    Self {
+      limit: Some(limit),
+      overflow: policy,
+      ..Self::default()
+    }
+  }
+
+  /// Creates a new decoder that decodes according to `config`.
+  ///
+  /// If the config leaves out the leading `END` byte, the decoder starts
+  /// out treating the first byte pushed as the start of a frame. A
+  /// maximum frame length from the config is applied just like a limit
+  /// from `with_limit` with `OverflowPolicy::Error`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the config doesn't pass `SlipConfig::validate`.
+  pub fn with_config(config: SlipConfig) -> Self {
+    assert!(
+      config.validate().is_ok(),
+      "END, ESC, ESC_END and ESC_ESC must all be different bytes"
+    );
+    let prefix_len = 2 * usize::from(config.length_prefix);
+    Self {
+      synced: !config.leading_end,
+      limit: config
+        .max_frame_len
+        .map(|max| max.saturating_add(prefix_len)),
+      config,
+      ..Self::default()
+    }
+  }
+
+  /// Pushes a chunk of encoded data into the decoder.
+  ///
+  /// Any frames completed by this chunk are queued and can be retrieved
+  /// with `next_frame` or `drain`.
+  pub fn push(&mut self, chunk: &[u8]) {
+    for byte in chunk {
+      if let Some(result) = self.step(*byte) {
+        self.frames.push_back(result.map(|()| self.frame.to_vec()));
+      }
+    }
+  }
+
+  /// Pushes a single byte of encoded data into the decoder.
+  ///
+  /// If the byte completes a frame, the frame is returned directly rather
+  /// than being queued, and stays borrowed from the decoder until the next
+  /// byte is pushed. This never allocates when the decoder was created with
+  /// `with_capacity`, never panics, and does a fixed amount of work per
+  /// byte, so it's safe to call from an interrupt handler. The one
+  /// exception is the `END` byte ending a frame that overflowed with
+  /// `OverflowPolicy::DropOldest`, which puts the frame back in order once.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipDecoder;
+  ///
+  /// let mut decoder = SlipDecoder::with_capacity(16);
+  /// let mut frames: Vec<Vec<u8>> = Vec::new();
+  ///
+  /// for byte in [0xC0, 0x01, 0xDB, 0xDC, 0x02, 0xC0] {
+  ///   if let Some(Ok(frame)) = decoder.push_byte(byte) {
+  ///     frames.push(frame.to_vec());
+  ///   }
+  /// }
+  ///
+  /// assert_eq!(frames, [[0x01, 0xC0, 0x02]]);
+  /// ```
+  pub fn push_byte(&mut self, byte: u8) -> Option<Result<&[u8], SlipError>> {
+    self
+      .step(byte)
+      .map(|result| result.map(|()| self.frame.as_slice()))
+  }
+
+  /// Returns the oldest complete frame, if there is one.
+  ///
+  /// Frames containing an invalid escape sequence are returned as a
+  /// `SlipError::InvalidEscapeByte` or `SlipError::TruncatedEscape` error,
+  /// and frames that don't fit in the decoder's capacity as a
+  /// `SlipError::FrameTooLarge` error.
+  pub fn next_frame(&mut self) -> Option<Result<Vec<u8>, SlipError>> {
+    self.frames.pop_front()
+  }
+
+  /// Removes all complete frames from the decoder, oldest first.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipDecoder;
+  ///
+  /// let mut decoder = SlipDecoder::new();
+  /// decoder.push(&[0xC0, 0x01, 0xC0, 0x02, 0xC0, 0x03]);
+  ///
+  /// let frames: Vec<Vec<u8>> = decoder.drain().map(|frame| frame.unwrap()).collect();
+  ///
+  /// assert_eq!(frames, [[0x01], [0x02]]);
+  /// assert_eq!(decoder.partial_len(), 1);
+  /// ```
+  pub fn drain(&mut self) -> Drain<'_, Result<Vec<u8>, SlipError>> {
+    self.frames.drain(..)
+  }
+
+  /// Returns the number of decoded bytes held for the frame in progress.
+  pub fn partial_len(&self) -> usize {
+    if self.complete {
+      0
+    } else {
+      self.frame.len()
+    }
+  }
+
+  /// Returns the number of malformed frames, with an invalid escape
+  /// sequence, that the decoder has recovered from by skipping to the next
+  /// `END` (0xC0) byte, whatever the `MalformedPolicy` did with them.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipDecoder;
+  ///
+  /// let mut decoder = SlipDecoder::new();
+  /// decoder.push(&[0xC0, 0x01, 0xDB, 0x02, 0x03, 0xC0, 0x04, 0xC0]);
+  ///
+  /// assert!(decoder.next_frame().unwrap().is_err());
+  /// assert_eq!(decoder.next_frame().unwrap().unwrap(), [0x04]);
+  /// assert_eq!(decoder.resyncs(), 1);
+  /// ```
+  pub fn resyncs(&self) -> u64 {
+    self.resyncs
+  }
+
+  /// Drops the frame in progress and discards incoming data up to the
+  /// next `END` (0xC0) byte.
+  ///
+  /// Frames that were already complete stay queued. This is useful after a
+  /// glitch on the line, when the rest of the current frame can't be trusted.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipDecoder;
+  ///
+  /// let mut decoder = SlipDecoder::new();
+  /// decoder.push(&[0xC0, 0x01, 0x02]);
+  ///
+  /// decoder.resync();
+  /// decoder.push(&[0x03, 0xC0, 0x04, 0xC0]);
+  ///
+  /// assert_eq!(decoder.next_frame().unwrap().unwrap(), [0x04]);
+  /// ```
+  pub fn resync(&mut self) {
+    self.frame.clear();
+    self.oldest = 0;
+    self.synced = false;
+    self.escaped = false;
+    self.complete = false;
+    self.error = None;
+    self.after_frame = false;
+    self.raw = false;
+  }
+
+  /// Returns the decoder to the state it was created in, dropping the frame
+  /// in progress and any queued frames, and clearing `resyncs`.
+  pub fn reset(&mut self) {
+    self.resync();
+    self.synced = !self.config.leading_end;
+    self.frames.clear();
+    self.resyncs = 0;
+  }
+
+  fn step(&mut self, byte: u8) -> Option<Result<(), SlipError>> {
+    if self.complete {
+      self.frame.clear();
+      self.complete = false;
+    }
+
+    let config = self.config;
+    if !self.synced {
+      self.synced = byte == config.end;
+      return None;
+    }
+
+    match byte {
+      _ if byte == config.end => {
+        self.unwrap_frame();
+        let escaped = core::mem::replace(&mut self.escaped, false);
+        let after_frame = core::mem::replace(&mut self.after_frame, true);
+        let raw = core::mem::replace(&mut self.raw, false);
+        match self.error.take() {
+          Some(SlipError::FrameTooLarge) if self.overflow == OverflowPolicy::DropFrame => None,
+          Some(error)
+            if error.is_malformed() && config.malformed_frames == MalformedPolicy::Skip =>
+          {
+            None
+          }
+          Some(error) => Some(Err(error)),
+          None if escaped && config.invalid_escapes == InvalidEscapePolicy::Error => {
+            self.resyncs += 1;
+            match config.malformed_frames {
+              MalformedPolicy::Skip => {
+                self.frame.clear();
+                None
+              }
+              MalformedPolicy::Error => {
+                self.frame.clear();
+                Some(Err(SlipError::TruncatedEscape))
+              }
+              MalformedPolicy::KeepRaw => {
+                self.escape_frame(None);
+                self.complete = true;
+                Some(Ok(()))
+              }
+            }
+          }
+          None if raw => {
+            self.complete = true;
+            Some(Ok(()))
+          }
+          None if self.frame.is_empty() => {
+            // With a leading `END` on every frame, the `END` straight after
+            // a frame starts the next one rather than ending an empty frame.
+            if config.leading_end && after_frame {
+              self.after_frame = false;
+              return None;
+            }
+            match config.empty_frames {
+              EmptyFramePolicy::Skip => {
+                self.after_frame = false;
+                None
+              }
+              EmptyFramePolicy::Yield => {
+                self.complete = true;
+                Some(Ok(()))
+              }
+              EmptyFramePolicy::Error => Some(Err(SlipError::EmptyFrame)),
+            }
+          }
+          None => match config.strip_length_prefix(&mut self.frame) {
+            Ok(()) => {
+              self.complete = true;
+              Some(Ok(()))
+            }
+            Err(error) => {
+              self.frame.clear();
+              Some(Err(error))
+            }
+          },
+        }
+      }
+      _ if self.error.is_some() => None,
+      _ if self.raw => {
+        self.store(byte);
+        None
+      }
+      _ if self.escaped => {
+        self.escaped = false;
+        match config.unescape(byte) {
+          Some(byte) => self.store(byte),
+          None if config.malformed_frames == MalformedPolicy::KeepRaw => {
+            self.resyncs += 1;
+            self.escape_frame(Some(byte));
+            self.raw = true;
+          }
+          None => {
+            self.resyncs += 1;
+            self.fail(SlipError::invalid_escape(Some(byte), config.end));
+          }
+        }
+        None
+      }
+      _ if byte == config.esc => {
+        self.escaped = true;
+        None
+      }
+      _ => {
+        self.store(byte);
+        None
+      }
+    }
+  }
+
+  fn store(&mut self, byte: u8) {
+    match self.limit {
+      Some(limit) if self.frame.len() >= limit => match self.overflow {
+        OverflowPolicy::DropOldest if limit > 0 => {
+          // The full frame is used as a ring, overwriting the oldest byte,
+          // and only put back in order when it's needed.
+          self.frame[self.oldest] = byte;
+          self.oldest = (self.oldest + 1) % limit;
+        }
+        _ => self.fail(SlipError::FrameTooLarge),
+      },
+      _ => self.frame.push(byte),
+    }
+  }
+
+  fn fail(&mut self, error: SlipError) {
+    self.frame.clear();
+    self.oldest = 0;
+    self.error = Some(error);
+    self.raw = false;
+  }
+
+  // Turns the frame in progress back into the raw bytes that were received
+  // for it, ending with the invalid escape sequence of `ESC` and `found`.
+  fn escape_frame(&mut self, found: Option<u8>) {
+    self.unwrap_frame();
+    // Every byte decoded so far came from a valid escape sequence, so
+    // escaping them again gives back the bytes that were received.
+    let mut raw: Vec<u8> = Vec::with_capacity(self.frame.len() + 2);
+    // Appending to a `Vec` never fails.
+    let _ = escape_to(&self.config, &self.frame, &mut raw);
+    raw.push(self.config.esc);
+    raw.extend(found);
+    self.frame = raw;
+  }
+
+  // Puts a frame that overflowed with `OverflowPolicy::DropOldest` back in
+  // order, oldest byte first.
+  fn unwrap_frame(&mut self) {
+    self.frame.rotate_left(self.oldest);
+    self.oldest = 0;
+  }
+}
+
+impl FrameDecoder for SlipDecoder {
+  fn push_decode(&mut self, byte: u8) -> Option<Result<&[u8], SlipError>> {
+    self.push_byte(byte)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_frames_across_chunks() {
+    let input: Vec<u8> = vec![
+      0xA1, END, 0x01, ESC, ESC_ESC, 0x49, ESC, ESC_END, 0x15, END, END, 0x02, ESC, ESC_END, END,
+    ];
+    let mut decoder = SlipDecoder::new();
+
+    for chunk in input.chunks(4) {
+      decoder.push(chunk);
+    }
+
+    let frames: Vec<Result<Vec<u8>, SlipError>> = decoder.drain().collect();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].as_ref().unwrap(), &[0x01, ESC, 0x49, END, 0x15]);
+    assert_eq!(frames[1].as_ref().unwrap(), &[0x02, END]);
+    assert_eq!(decoder.partial_len(), 0);
+  }
+
+  #[test]
+  fn reports_invalid_frames_and_recovers() {
+    let mut decoder = SlipDecoder::new();
+    decoder.push(&[END, 0x01, ESC, 0x02, 0x03, END, 0x04, END]);
+
+    assert!(decoder.next_frame().unwrap().is_err());
+    assert_eq!(decoder.next_frame().unwrap().unwrap(), [0x04]);
+    assert!(decoder.next_frame().is_none());
+
+    decoder.push(&[0x05, ESC, END, 0x06, ESC, ESC_END, END]);
+    assert_eq!(
+      decoder.drain().collect::<Vec<_>>(),
+      [Err(SlipError::TruncatedEscape), Ok(vec![0x06, END])]
+    );
+    assert_eq!(decoder.resyncs(), 2);
+
+    decoder.reset();
+    assert_eq!(decoder.resyncs(), 0);
+  }
+
+  #[test]
+  fn applies_malformed_policy() {
+    let input: [u8; 13] = [
+      END, 0x01, ESC, ESC_END, ESC, 0x02, 0x03, END, 0x04, END, 0x05, ESC, END,
+    ];
+
+    let config = SlipConfig::new().malformed_frames(MalformedPolicy::Skip);
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&input);
+    assert_eq!(decoder.drain().collect::<Vec<_>>(), [Ok(vec![0x04])]);
+    assert_eq!(decoder.resyncs(), 2);
+
+    let config = SlipConfig::new().malformed_frames(MalformedPolicy::KeepRaw);
+    let expected = vec![
+      Ok(input[1..7].to_vec()),
+      Ok(vec![0x04]),
+      Ok(vec![0x05, ESC]),
+    ];
+    let mut decoder = SlipDecoder::with_config(config);
+    decoder.push(&input);
+    assert_eq!(decoder.drain().collect::<Vec<_>>(), expected);
+    assert_eq!(decoder.resyncs(), 2);
+    assert_eq!(config.decode_packets(&input).0, expected);
+  }
+
+  #[test]
+  fn push_byte_returns_frames_without_queueing() {
+    let mut decoder = SlipDecoder::with_capacity(4);
+    let mut results: Vec<Result<Vec<u8>, SlipError>> = Vec::new();
+
+    for byte in [
+      END, 0x01, ESC, ESC_ESC, END, 0x01, 0x02, 0x03, 0x04, 0x05, END, 0x06, END,
+    ] {
+      if let Some(result) = decoder.push_byte(byte) {
+        results.push(result.map(|frame| frame.to_vec()));
+      }
+    }
+
+    assert_eq!(
+      results,
+      [
+        Ok(vec![0x01, ESC]),
+        Err(SlipError::FrameTooLarge),
+        Ok(vec![0x06])
+      ]
+    );
+    assert!(decoder.next_frame().is_none());
+  }
+
+  #[test]
+  fn resync_and_reset_drop_partial_frames() {
+    let mut decoder = SlipDecoder::new();
+    decoder.push(&[END, 0x01, END, 0x02, ESC]);
+
+    decoder.resync();
+    decoder.push(&[ESC_END, 0x03, END, 0x04, END]);
+    assert_eq!(decoder.drain().count(), 2);
+
+    decoder.push(&[END, 0x05, END, 0x06]);
+    decoder.reset();
+    assert_eq!(decoder.partial_len(), 0);
+    assert!(decoder.next_frame().is_none());
+  }
+
+  #[test]
+  fn applies_overflow_policy() {
+    let input: [u8; 8] = [END, 0x01, 0x02, 0x03, 0x04, END, 0x05, END];
+
+    let mut decoder = SlipDecoder::with_limit(3, OverflowPolicy::Error);
+    decoder.push(&input);
+    assert_eq!(decoder.next_frame(), Some(Err(SlipError::FrameTooLarge)));
+    assert_eq!(decoder.next_frame(), Some(Ok(vec![0x05])));
+
+    let mut decoder = SlipDecoder::with_limit(3, OverflowPolicy::DropFrame);
+    decoder.push(&input);
+    assert_eq!(decoder.next_frame(), Some(Ok(vec![0x05])));
+    assert_eq!(decoder.next_frame(), None);
+
+    let mut decoder = SlipDecoder::with_limit(3, OverflowPolicy::DropOldest);
+    decoder.push(&input);
+    assert_eq!(decoder.next_frame(), Some(Ok(vec![0x02, 0x03, 0x04])));
+    assert_eq!(decoder.partial_len(), 0);
+
+    // A frame far over the limit keeps its last bytes, in order.
+    let long: Vec<u8> = (0..=255)
+      .cycle()
+      .take(100_000)
+      .filter(|byte| *byte < END)
+      .collect();
+    let mut decoder = SlipDecoder::with_limit(1000, OverflowPolicy::DropOldest);
+    decoder.push(&encode(&long).unwrap());
+    decoder.push(&[0x06, END]);
+    assert_eq!(
+      decoder.next_frame(),
+      Some(Ok(long[long.len() - 1000..].to_vec()))
+    );
+    assert_eq!(decoder.next_frame(), Some(Ok(vec![0x06])));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn resumes_from_serialized_state() {
+    let mut decoder = SlipDecoder::new();
+    decoder.push(&[END, 0x01, END, 0x02, ESC]);
+
+    let checkpoint = serde_json::to_string(&decoder).unwrap();
+    let mut restored: SlipDecoder = serde_json::from_str(&checkpoint).unwrap();
+    restored.push(&[ESC_END, 0x03, END]);
+
+    assert_eq!(restored.next_frame().unwrap().unwrap(), [0x01]);
+    assert_eq!(restored.next_frame().unwrap().unwrap(), [0x02, END, 0x03]);
+  }
+}
@@ -0,0 +1,288 @@
+use super::*;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// A streaming encoder for data following the SLIP protocol.
+///
+/// Payloads are queued with `push`, and their encoding is written out in
+/// caller-sized chunks with `pull`. A single frame can span as many calls
+/// to `pull` as needed, so the full encoded frame is never allocated.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::SlipEncoder;
+///
+/// let mut encoder = SlipEncoder::new();
+/// encoder.push(&[0x01, 0xDB, 0x49, 0xC0, 0x15]).unwrap();
+///
+/// let mut fifo = [0u8; 4];
+/// let mut output: Vec<u8> = Vec::new();
+///
+/// while !encoder.is_empty() {
+///   let written = encoder.pull(&mut fifo);
+///   output.extend_from_slice(&fifo[..written]);
+/// }
+///
+/// assert_eq!(output, [0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SlipEncoder {
+  payloads: VecDeque<Vec<u8>>,
+  position: usize,
+  flushed: bool,
+  started: bool,
+  pending: Option<u8>,
+  padding: usize,
+  config: SlipConfig,
+  stats: EncodeStats,
+}
+
+/// Counters describing the data written by a `SlipEncoder` so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EncodeStats {
+  /// The number of bytes written.
+  pub bytes_written: u64,
+  /// The number of frames fully written.
+  pub frames: u64,
+  /// The number of extra `END` bytes written to flush line noise, which
+  /// aren't part of any frame.
+  pub flush_ends: u64,
+}
+
+impl SlipEncoder {
+  /// Creates a new encoder with no queued payloads.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a new encoder that encodes with the bytes from `config`.
+  pub fn with_config(config: SlipConfig) -> Self {
+    Self {
+      config,
+      ..Self::default()
+    }
+  }
+
+  /// Queues a payload to be encoded as a single frame.
+  ///
+  /// Returns `SlipError::MtuExceeded` if the payload is longer than the
+  /// config's MTU, or too long for a length prefix, just like
+  /// `SlipConfig::encode`, and doesn't queue it.
+  pub fn push(&mut self, payload: &[u8]) -> Result<(), SlipError> {
+    self.config.check_mtu(payload)?;
+
+    match self.config.length_prefix_for(payload)? {
+      Some(prefix) => self.payloads.push_back([&prefix, payload].concat()),
+      None => self.payloads.push_back(payload.to_vec()),
+    }
+    Ok(())
+  }
+
+  /// Writes as much encoded data as fits into `output`.
+  ///
+  /// Returns the number of bytes written, which is only less than the
+  /// length of `output` once every queued payload has been fully encoded.
+  pub fn pull(&mut self, output: &mut [u8]) -> usize {
+    let mut written = 0;
+
+    while written < output.len() {
+      if self.padding > 0 {
+        let len = self.padding.min(output.len() - written);
+        output[written..written + len].fill(self.config.end);
+        self.padding -= len;
+        written += len;
+        continue;
+      }
+
+      let payload = match self.payloads.front() {
+        Some(payload) => payload,
+        None => break,
+      };
+
+      if !self.flushed {
+        self.flushed = true;
+        if !self.config.flush_end {
+          continue;
+        }
+        output[written] = self.config.end;
+        self.stats.flush_ends += 1;
+      } else if !self.started {
+        self.started = true;
+        if !self.config.leading_end {
+          continue;
+        }
+        output[written] = self.config.end;
+      } else if let Some(byte) = self.pending.take() {
+        output[written] = byte;
+      } else if self.position < payload.len() {
+        // Only scan as far as will fit, so pulling a large payload a few
+        // bytes at a time doesn't keep rescanning the rest of it.
+        let rest = &payload[self.position..];
+        let rest = &rest[..rest.len().min(output.len() - written)];
+        let run = self.config.find_special(rest).unwrap_or(rest.len());
+
+        if run > 0 {
+          output[written..written + run].copy_from_slice(&rest[..run]);
+          self.position += run;
+          written += run;
+          continue;
+        }
+
+        self.position += 1;
+        let [esc, escaped] = self.config.escape(rest[0]);
+        output[written] = esc;
+        self.pending = Some(escaped);
+      } else {
+        output[written] = self.config.end;
+        if let Some(block_len) = self.config.pad_to {
+          // Any length prefix is already part of the queued payload.
+          let frame_len = self.config.encoded_len(None, payload);
+          self.padding = (block_len - frame_len % block_len) % block_len;
+        }
+        self.payloads.pop_front();
+        self.position = 0;
+        self.flushed = false;
+        self.started = false;
+        self.stats.frames += 1;
+      }
+
+      written += 1;
+    }
+
+    self.stats.bytes_written += written as u64;
+    written
+  }
+
+  /// Returns counters describing the data written so far.
+  pub fn stats(&self) -> EncodeStats {
+    self.stats
+  }
+
+  /// Returns `true` when there is no encoded data left to pull.
+  pub fn is_empty(&self) -> bool {
+    self.payloads.is_empty() && self.padding == 0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encodes_frames_across_chunks() {
+    let mut encoder = SlipEncoder::new();
+    encoder.push(&[0x01, ESC, 0x49, END, 0x15]).unwrap();
+    encoder.push(&[0x02]).unwrap();
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut chunk = [0u8; 3];
+    while !encoder.is_empty() {
+      let written = encoder.pull(&mut chunk);
+      output.extend_from_slice(&chunk[..written]);
+    }
+
+    let mut expected: Vec<u8> = encode([0x01, ESC, 0x49, END, 0x15]).unwrap();
+    expected.extend(encode([0x02]).unwrap());
+    assert_eq!(output, expected);
+  }
+
+  #[test]
+  fn encodes_like_encode_at_every_chunk_size() {
+    let payload: Vec<u8> = (0..=255).chain(0..=255).collect();
+    let expected = encode(&payload).unwrap();
+
+    for chunk_len in 1..20 {
+      let mut encoder = SlipEncoder::new();
+      encoder.push(&payload).unwrap();
+
+      let mut output: Vec<u8> = Vec::new();
+      let mut chunk = vec![0u8; chunk_len];
+      while !encoder.is_empty() {
+        let written = encoder.pull(&mut chunk);
+        output.extend_from_slice(&chunk[..written]);
+      }
+
+      assert_eq!(output, expected, "chunk length {}", chunk_len);
+    }
+  }
+
+  #[test]
+  fn counts_flush_ends_separately() {
+    let config = SlipConfig::new().flush_end(true);
+    let mut encoder = SlipEncoder::with_config(config);
+    encoder.push(&[0x01]).unwrap();
+    encoder.push(&[END]).unwrap();
+
+    let mut chunk = [0u8; 16];
+    let written = encoder.pull(&mut chunk);
+
+    let mut expected = config.encode(&[0x01]).unwrap();
+    expected.extend(config.encode(&[END]).unwrap());
+    assert_eq!(&chunk[..written], expected);
+    assert_eq!(&expected[..4], [END, END, 0x01, END]);
+    assert_eq!(
+      encoder.stats(),
+      EncodeStats {
+        bytes_written: 9,
+        frames: 2,
+        flush_ends: 2,
+      }
+    );
+  }
+
+  #[test]
+  fn pads_like_encode() {
+    let payloads: [&[u8]; 3] = [&[0x01], &[END, ESC, 0x02, 0x03], &[0x04; 20]];
+    let configs = [
+      SlipConfig::new().pad_to(8),
+      SlipConfig::new().pad_to(3).flush_end(true),
+      SlipConfig::new().pad_to(16).length_prefix(true),
+    ];
+
+    for config in configs {
+      let mut expected: Vec<u8> = Vec::new();
+      for payload in payloads {
+        expected.extend(config.encode(payload).unwrap());
+      }
+
+      for chunk_len in [1, 5, 64] {
+        let mut encoder = SlipEncoder::with_config(config);
+        for payload in payloads {
+          encoder.push(payload).unwrap();
+        }
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut chunk = vec![0u8; chunk_len];
+        while !encoder.is_empty() {
+          let written = encoder.pull(&mut chunk);
+          output.extend_from_slice(&chunk[..written]);
+        }
+        assert_eq!(output, expected, "chunk length {}", chunk_len);
+      }
+    }
+  }
+
+  #[test]
+  fn rejects_payloads_over_mtu() {
+    let config = SlipConfig::new().mtu(4);
+    let mut encoder = SlipEncoder::with_config(config);
+
+    assert_eq!(encoder.push(&[0x01; 12]), Err(SlipError::MtuExceeded));
+    assert!(encoder.is_empty());
+    encoder.push(&[0x01; 4]).unwrap();
+
+    let mut chunk = [0u8; 16];
+    let written = encoder.pull(&mut chunk);
+    assert_eq!(chunk[..written], config.encode(&[0x01; 4]).unwrap());
+  }
+
+  #[test]
+  fn pulls_nothing_when_empty() {
+    let mut encoder = SlipEncoder::new();
+    let mut chunk = [0u8; 8];
+
+    assert_eq!(encoder.pull(&mut chunk), 0);
+  }
+}
@@ -0,0 +1,156 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// A reversible change to each payload, applied before it's encoded and
+/// reverted after it's decoded, such as compression or encryption.
+///
+/// Transforms can be stacked with `then`, and are run with
+/// `encode_with_transform` and `decode_with_transform`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_with_transform, encode_with_transform, PayloadTransform, SlipError};
+///
+/// struct Xor(u8);
+///
+/// impl PayloadTransform for Xor {
+///   fn apply(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+///     Ok(payload.iter().map(|byte| byte ^ self.0).collect())
+///   }
+///
+///   fn revert(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+///     self.apply(payload)
+///   }
+/// }
+///
+/// let transform = Xor(0x0F).then(Xor(0xF0));
+/// let encoded = encode_with_transform(&transform, &[0x01, 0x02]).unwrap();
+///
+/// assert_eq!(encoded, [0xC0, 0xFE, 0xFD, 0xC0]);
+/// assert_eq!(decode_with_transform(&transform, &encoded).unwrap(), [0x01, 0x02]);
+/// ```
+pub trait PayloadTransform {
+  /// Transforms a payload before it's encoded.
+  fn apply(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError>;
+
+  /// Undoes `apply` on a payload after it's decoded.
+  ///
+  /// Should return `SlipError::TransformFailed` if the payload couldn't
+  /// have come from `apply`.
+  fn revert(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError>;
+
+  /// Stacks `next` on top of this transform, so it's applied after this one
+  /// and reverted before it.
+  ///
+  /// For encrypt-then-frame with compression, that's
+  /// `compress.then(encrypt)`.
+  fn then<U: PayloadTransform>(self, next: U) -> Stack<Self, U>
+  where
+    Self: Sized,
+  {
+    Stack { first: self, next }
+  }
+}
+
+impl<X: PayloadTransform + ?Sized> PayloadTransform for &X {
+  fn apply(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+    (**self).apply(payload)
+  }
+
+  fn revert(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+    (**self).revert(payload)
+  }
+}
+
+/// Two transforms stacked together by `PayloadTransform::then`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stack<A, B> {
+  first: A,
+  next: B,
+}
+
+impl<A, B> Stack<A, B> {
+  /// Returns the transforms that were stacked, in the order they're
+  /// applied.
+  pub fn into_inner(self) -> (A, B) {
+    (self.first, self.next)
+  }
+}
+
+impl<A: PayloadTransform, B: PayloadTransform> PayloadTransform for Stack<A, B> {
+  fn apply(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+    self.next.apply(&self.first.apply(payload)?)
+  }
+
+  fn revert(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+    self.first.revert(&self.next.revert(payload)?)
+  }
+}
+
+/// Encodes data following the SLIP protocol, after running it through
+/// `transform`.
+pub fn encode_with_transform<X, T>(transform: &X, raw_buffer: T) -> Result<Vec<u8>, SlipError>
+where
+  X: PayloadTransform + ?Sized,
+  T: AsRef<[u8]>,
+{
+  encode(transform.apply(raw_buffer.as_ref())?)
+}
+
+/// Decodes data following the SLIP protocol that was encoded with
+/// `encode_with_transform`, reverting `transform` on the decoded payload.
+pub fn decode_with_transform<X, T>(transform: &X, encoded_buffer: T) -> Result<Vec<u8>, SlipError>
+where
+  X: PayloadTransform + ?Sized,
+  T: AsRef<[u8]>,
+{
+  transform.revert(&decode(encoded_buffer)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Prefixes the payload with its tag, rejecting payloads without it.
+  struct Tag(u8);
+
+  impl PayloadTransform for Tag {
+    fn apply(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+      let mut tagged = vec![self.0];
+      tagged.extend_from_slice(payload);
+      Ok(tagged)
+    }
+
+    fn revert(&self, payload: &[u8]) -> Result<Vec<u8>, SlipError> {
+      match payload.split_first() {
+        Some((&tag, rest)) if tag == self.0 => Ok(rest.to_vec()),
+        _ => Err(SlipError::TransformFailed),
+      }
+    }
+  }
+
+  #[test]
+  fn stacks_in_order() {
+    let transform = Tag(0x01).then(Tag(END)).then(Tag(0x03));
+    let encoded = encode_with_transform(&transform, [0x04]).unwrap();
+
+    assert_eq!(encoded, [END, 0x03, ESC, ESC_END, 0x01, 0x04, END]);
+    assert_eq!(decode_with_transform(&transform, &encoded), Ok(vec![0x04]));
+  }
+
+  #[test]
+  fn reports_failed_reverts() {
+    let encoded = encode_with_transform(&Tag(0x01), [0x02]).unwrap();
+    let transform: &dyn PayloadTransform = &Tag(0x02);
+
+    assert_eq!(
+      decode_with_transform(transform, &encoded),
+      Err(SlipError::TransformFailed)
+    );
+    assert_eq!(
+      decode_with_transform(&Tag(0x01).then(transform), &encoded),
+      Err(SlipError::TransformFailed)
+    );
+  }
+}
@@ -0,0 +1,322 @@
+use super::*;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::time::Duration;
+
+// The largest IP packet a TUN device can hand over in one read.
+const MAX_PACKET: usize = 65535;
+
+/// A Linux TUN device, a network interface whose IP packets are read and
+/// written by this process rather than sent out on hardware.
+///
+/// Each `read` returns exactly one IP packet, and each `write` sends one,
+/// with no extra packet information in front of it. The interface still
+/// needs an address and bringing up, such as with
+/// `ip addr add 10.0.0.1/24 peer 10.0.0.2 dev slip0` and
+/// `ip link set slip0 up`, before packets flow.
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// use simple_slip::TunDevice;
+///
+/// // Opening a TUN device needs `CAP_NET_ADMIN`.
+/// let tun = TunDevice::open("slip%d").unwrap();
+/// assert!(tun.name().starts_with("slip"));
+/// ```
+#[derive(Debug)]
+pub struct TunDevice {
+  file: File,
+  name: String,
+}
+
+impl TunDevice {
+  /// Creates the TUN interface `name`, or attaches to it if it already
+  /// exists, which needs `CAP_NET_ADMIN`.
+  ///
+  /// A `%d` in the name is replaced by the kernel with the first free
+  /// number, and an empty name picks the next `tun` interface. Returns an
+  /// `io::ErrorKind::InvalidInput` error if the name is too long or
+  /// contains a nul byte.
+  pub fn open(name: &str) -> io::Result<Self> {
+    if name.len() >= libc::IFNAMSIZ {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "interface name is too long",
+      ));
+    }
+    if name.contains('\0') {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "interface name contains a nul byte",
+      ));
+    }
+
+    let file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .open("/dev/net/tun")?;
+
+    // SAFETY: `ifreq` is plain old data, for which all zeroes is valid.
+    let mut request: libc::ifreq = unsafe { core::mem::zeroed() };
+    for (slot, byte) in request.ifr_name.iter_mut().zip(name.bytes()) {
+      *slot = byte as libc::c_char;
+    }
+    request.ifr_ifru.ifru_flags = (libc::IFF_TUN | libc::IFF_NO_PI) as libc::c_short;
+
+    // SAFETY: `TUNSETIFF` takes a pointer to an `ifreq`, which `request`
+    // is, and only lives for the call.
+    if unsafe { libc::ioctl(file.as_raw_fd(), libc::TUNSETIFF, &mut request) } < 0 {
+      return Err(io::Error::last_os_error());
+    }
+
+    // The kernel writes back the name it chose, always nul terminated.
+    let name = request
+      .ifr_name
+      .iter()
+      .take_while(|char| **char != 0)
+      .map(|char| char::from(*char as u8))
+      .collect();
+
+    Ok(Self { file, name })
+  }
+
+  /// Returns the name of the interface.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+}
+
+impl Read for TunDevice {
+  fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+    self.file.read(buffer)
+  }
+}
+
+impl Write for TunDevice {
+  fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+    self.file.write(buffer)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.file.flush()
+  }
+}
+
+impl AsRawFd for TunDevice {
+  fn as_raw_fd(&self) -> RawFd {
+    self.file.as_raw_fd()
+  }
+}
+
+/// Bridges the IP packets of a `TunDevice` to a serial port as SLIP frames,
+/// in both directions, making the serial line a network interface just as
+/// RFC 1055 intended.
+///
+/// Each packet read from the TUN device is encoded into a frame and
+/// written to the serial port, and each frame decoded from the serial port
+/// is written to the TUN device as a packet. Frames that aren't encoded
+/// correctly, and packets the kernel rejects, are dropped and counted,
+/// rather than stopping the bridge. The serial port can be anything that's
+/// a file descriptor, like a `File` for `/dev/ttyUSB0` that has already
+/// been set to raw mode at the right baud rate.
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// use simple_slip::{TunBridge, TunDevice};
+/// use std::fs::OpenOptions;
+///
+/// let tun = TunDevice::open("slip0").unwrap();
+/// let serial = OpenOptions::new()
+///   .read(true)
+///   .write(true)
+///   .open("/dev/ttyUSB0")
+///   .unwrap();
+///
+/// TunBridge::new(tun, serial).run().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TunBridge<S, D = TunDevice> {
+  tun: D,
+  serial: S,
+  decoder: SlipDecoder,
+  buffer: Vec<u8>,
+  frame: Vec<u8>,
+  dropped: u64,
+}
+
+impl<S, D> TunBridge<S, D>
+where
+  S: Read + Write + AsRawFd,
+  D: Read + Write + AsRawFd,
+{
+  /// Creates a bridge between the TUN device `tun` and `serial`.
+  pub fn new(tun: D, serial: S) -> Self {
+    Self {
+      tun,
+      serial,
+      decoder: SlipDecoder::with_limit(MAX_PACKET, OverflowPolicy::Error),
+      buffer: alloc::vec![0; MAX_PACKET],
+      frame: Vec::new(),
+      dropped: 0,
+    }
+  }
+
+  /// Bridges packets until either side fails or is closed, which is
+  /// returned as an `io::ErrorKind::UnexpectedEof` error.
+  pub fn run(&mut self) -> io::Result<()> {
+    loop {
+      self.pump(None)?;
+    }
+  }
+
+  /// Waits until either side has something to read, or `timeout` has
+  /// passed, then bridges whatever is ready, returning the number of
+  /// packets passed on.
+  ///
+  /// With a `timeout` of `None` it waits for as long as it takes.
+  pub fn pump(&mut self, timeout: Option<Duration>) -> io::Result<usize> {
+    let mut fds = [self.tun.as_raw_fd(), self.serial.as_raw_fd()].map(|fd| libc::pollfd {
+      fd,
+      events: libc::POLLIN,
+      revents: 0,
+    });
+    let timeout = timeout.map_or(-1, |timeout| {
+      timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int
+    });
+
+    // SAFETY: `fds` is an array of `fds.len()` `pollfd`s, and only lives
+    // for the call.
+    if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) } < 0 {
+      let error = io::Error::last_os_error();
+      return match error.kind() {
+        io::ErrorKind::Interrupted => Ok(0),
+        _ => Err(error),
+      };
+    }
+
+    let mut passed = 0;
+    if fds[0].revents != 0 {
+      passed += self.tun_to_serial()?;
+    }
+    if fds[1].revents != 0 {
+      passed += self.serial_to_tun()?;
+    }
+    Ok(passed)
+  }
+
+  /// Returns the number of frames and packets dropped, because a frame
+  /// wasn't encoded correctly or the kernel rejected the packet in it.
+  pub fn dropped(&self) -> u64 {
+    self.dropped
+  }
+
+  /// Returns a reference to the TUN device.
+  pub fn tun(&self) -> &D {
+    &self.tun
+  }
+
+  /// Returns a reference to the serial port.
+  pub fn serial(&self) -> &S {
+    &self.serial
+  }
+
+  /// Consumes the bridge, returning the TUN device and serial port.
+  pub fn into_inner(self) -> (D, S) {
+    (self.tun, self.serial)
+  }
+
+  // Reads one packet from the TUN device and writes it to the serial port.
+  fn tun_to_serial(&mut self) -> io::Result<usize> {
+    let len = read_some(&mut self.tun, &mut self.buffer)?;
+
+    self.frame.clear();
+    encode_append(&self.buffer[..len], &mut self.frame);
+    self.serial.write_all(&self.frame)?;
+    self.serial.flush()?;
+    Ok(1)
+  }
+
+  // Reads what's waiting on the serial port and writes each frame it
+  // completes to the TUN device.
+  fn serial_to_tun(&mut self) -> io::Result<usize> {
+    let len = read_some(&mut self.serial, &mut self.buffer)?;
+    self.decoder.push(&self.buffer[..len]);
+
+    let mut passed = 0;
+    while let Some(frame) = self.decoder.next_frame() {
+      let Ok(packet) = frame else {
+        self.dropped += 1;
+        continue;
+      };
+
+      match self.tun.write(&packet) {
+        Ok(_) => passed += 1,
+        Err(error) if error.kind() == io::ErrorKind::InvalidInput => self.dropped += 1,
+        Err(error) => return Err(error),
+      }
+    }
+    Ok(passed)
+  }
+}
+
+// Reads once from `inner`, which is ready to read, treating being closed as
+// an error.
+fn read_some<R: Read>(inner: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+  match inner.read(buffer)? {
+    0 => Err(io::Error::new(
+      io::ErrorKind::UnexpectedEof,
+      "bridged device was closed",
+    )),
+    len => Ok(len),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::os::unix::net::UnixStream;
+
+  #[test]
+  fn rejects_bad_interface_names() {
+    for (name, message) in [
+      ("slip_interface_0", "interface name is too long"),
+      ("slip\0", "interface name contains a nul byte"),
+    ] {
+      let error = TunDevice::open(name).unwrap_err();
+      assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+      assert_eq!(error.to_string(), message);
+    }
+  }
+
+  #[test]
+  fn bridges_packets_both_ways() {
+    let (tun, mut tun_peer) = UnixStream::pair().unwrap();
+    let (serial, mut serial_peer) = UnixStream::pair().unwrap();
+    let mut bridge = TunBridge::new(tun, serial);
+    let timeout = Some(Duration::from_secs(5));
+
+    tun_peer.write_all(&[0x45, END, ESC]).unwrap();
+    assert_eq!(bridge.pump(timeout).unwrap(), 1);
+    let mut frame = [0; 16];
+    let len = serial_peer.read(&mut frame).unwrap();
+    assert_eq!(frame[..len], encode([0x45, END, ESC]).unwrap());
+
+    serial_peer.write_all(&[END, ESC, 0x01, END]).unwrap();
+    serial_peer
+      .write_all(&encode([0x60, 0x00]).unwrap())
+      .unwrap();
+    assert_eq!(bridge.pump(timeout).unwrap(), 1);
+    let mut packet = [0; 16];
+    let len = tun_peer.read(&mut packet).unwrap();
+    assert_eq!(packet[..len], [0x60, 0x00]);
+    assert_eq!(bridge.dropped(), 1);
+
+    assert_eq!(bridge.pump(Some(Duration::ZERO)).unwrap(), 0);
+    drop(serial_peer);
+    let error = bridge.run().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+  }
+}
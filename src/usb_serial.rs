@@ -0,0 +1,293 @@
+use super::*;
+use alloc::vec::Vec;
+use core::borrow::BorrowMut;
+use core::fmt;
+use usb_device::bus::UsbBus;
+use usb_device::UsbError;
+use usbd_serial::SerialPort;
+
+/// The size of a full speed USB bulk packet.
+const USB_PACKET_SIZE: usize = 64;
+
+/// A USB CDC-ACM serial port, as implemented by `usbd_serial::SerialPort`.
+///
+/// Every method returns `UsbError::WouldBlock` when it can't make progress
+/// until the host next polls the device. It can be implemented for other
+/// CDC-ACM classes to use them with `UsbSlip`.
+pub trait UsbSerial {
+  /// Reads received bytes into `data`, returning how many were read.
+  fn read(&mut self, data: &mut [u8]) -> Result<usize, UsbError>;
+
+  /// Writes bytes from `data` into the port, returning how many were
+  /// written.
+  fn write(&mut self, data: &[u8]) -> Result<usize, UsbError>;
+
+  /// Sends any data written to the port to the host.
+  fn flush(&mut self) -> Result<(), UsbError>;
+}
+
+impl<B, RS, WS> UsbSerial for SerialPort<'_, B, RS, WS>
+where
+  B: UsbBus,
+  RS: BorrowMut<[u8]>,
+  WS: BorrowMut<[u8]>,
+{
+  fn read(&mut self, data: &mut [u8]) -> Result<usize, UsbError> {
+    SerialPort::read(self, data)
+  }
+
+  fn write(&mut self, data: &[u8]) -> Result<usize, UsbError> {
+    SerialPort::write(self, data)
+  }
+
+  fn flush(&mut self) -> Result<(), UsbError> {
+    SerialPort::flush(self)
+  }
+}
+
+/// An error from receiving frames over USB CDC-ACM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UsbSlipError {
+  /// The USB stack failed, or returned `UsbError::WouldBlock` because no
+  /// complete frame has been received yet.
+  Usb(UsbError),
+  /// A frame was received that wasn't encoded correctly.
+  Decode(SlipError),
+}
+
+impl fmt::Display for UsbSlipError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      UsbSlipError::Usb(error) => write!(f, "usb error: {:?}", error),
+      UsbSlipError::Decode(error) => fmt::Display::fmt(error, f),
+    }
+  }
+}
+
+/// Sends and receives frames following the SLIP protocol over a USB CDC-ACM
+/// serial port, such as a `usbd_serial::SerialPort`.
+///
+/// Received data is read a packet at a time and fed through a
+/// `SlipDecoder`. Frames to send are queued with `queue_frame` and written
+/// out by `flush` in packet sized chunks, keeping any part of a chunk the
+/// port couldn't take for the next call, so frames are never cut short when
+/// the port's buffer fills up. Both return `UsbError::WouldBlock` until they
+/// can make more progress, so they fit into the usual `usb_dev.poll` loop.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{UsbSerial, UsbSlip};
+/// use usb_device::UsbError;
+///
+/// # struct Loopback(Vec<u8>);
+/// #
+/// # impl UsbSerial for Loopback {
+/// #   fn read(&mut self, data: &mut [u8]) -> Result<usize, UsbError> {
+/// #     let len = data.len().min(self.0.len());
+/// #     match len {
+/// #       0 => Err(UsbError::WouldBlock),
+/// #       _ => Ok(self.0.drain(..len).zip(data).map(|(byte, slot)| *slot = byte).count()),
+/// #     }
+/// #   }
+/// #
+/// #   fn write(&mut self, data: &[u8]) -> Result<usize, UsbError> {
+/// #     self.0.extend_from_slice(data);
+/// #     Ok(data.len())
+/// #   }
+/// #
+/// #   fn flush(&mut self) -> Result<(), UsbError> {
+/// #     Ok(())
+/// #   }
+/// # }
+/// #
+/// // `serial` would usually be a `usbd_serial::SerialPort`.
+/// # let serial = Loopback(Vec::new());
+/// let mut slip = UsbSlip::new(serial);
+///
+/// slip.queue_frame(&[0x01, 0xC0]);
+/// slip.flush().unwrap();
+///
+/// assert_eq!(slip.read_frame().unwrap(), [0x01, 0xC0]);
+/// assert!(slip.read_frame().is_err());
+/// ```
+#[derive(Debug)]
+pub struct UsbSlip<P> {
+  port: P,
+  decoder: SlipDecoder,
+  encoder: SlipEncoder,
+  packet: [u8; USB_PACKET_SIZE],
+  written: usize,
+  filled: usize,
+}
+
+impl<P: UsbSerial> UsbSlip<P> {
+  /// Creates a new `UsbSlip` over `port`.
+  pub fn new(port: P) -> Self {
+    Self::with_decoder(port, SlipDecoder::new())
+  }
+
+  /// Creates a new `UsbSlip` over `port` that decodes with `decoder`, for
+  /// example one created with `SlipDecoder::with_capacity`.
+  pub fn with_decoder(port: P, decoder: SlipDecoder) -> Self {
+    Self {
+      port,
+      decoder,
+      encoder: SlipEncoder::new(),
+      packet: [0; USB_PACKET_SIZE],
+      written: 0,
+      filled: 0,
+    }
+  }
+
+  /// Reads from the port until a frame is complete.
+  ///
+  /// Returns `UsbSlipError::Usb(UsbError::WouldBlock)` once the port has no
+  /// more data available, keeping any partial frame for the next call. A
+  /// frame that isn't encoded correctly is returned as
+  /// `UsbSlipError::Decode`, after which the following frames can still be
+  /// read.
+  pub fn read_frame(&mut self) -> Result<Vec<u8>, UsbSlipError> {
+    let mut packet = [0u8; USB_PACKET_SIZE];
+
+    loop {
+      if let Some(frame) = self.decoder.next_frame() {
+        return frame.map_err(UsbSlipError::Decode);
+      }
+
+      let read = self.port.read(&mut packet).map_err(UsbSlipError::Usb)?;
+      self.decoder.push(&packet[..read]);
+    }
+  }
+
+  /// Queues `payload` to be encoded as a single frame and written by
+  /// `flush`.
+  pub fn queue_frame(&mut self, payload: &[u8]) {
+    // The encoder has no MTU, so it takes any payload.
+    let _ = self.encoder.push(payload);
+  }
+
+  /// Writes the queued frames to the port and sends them to the host.
+  ///
+  /// Returns `UsbError::WouldBlock` while the port can't take any more
+  /// data, picking up where it left off on the next call.
+  pub fn flush(&mut self) -> Result<(), UsbError> {
+    loop {
+      if self.written == self.filled {
+        self.filled = self.encoder.pull(&mut self.packet);
+        self.written = 0;
+        if self.filled == 0 {
+          break;
+        }
+      }
+
+      self.written += self.port.write(&self.packet[self.written..self.filled])?;
+    }
+
+    self.port.flush()
+  }
+
+  /// Returns a reference to the port.
+  pub fn get_ref(&self) -> &P {
+    &self.port
+  }
+
+  /// Returns a mutable reference to the port.
+  pub fn get_mut(&mut self) -> &mut P {
+    &mut self.port
+  }
+
+  /// Consumes the `UsbSlip`, returning the port.
+  ///
+  /// Any buffered data that hasn't been returned as a frame or written out
+  /// is lost.
+  pub fn into_inner(self) -> P {
+    self.port
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A port with room for a single packet, which the host collects
+  /// whenever the port is flushed.
+  #[derive(Default)]
+  struct Port {
+    input: Vec<u8>,
+    buffer: Vec<u8>,
+    host: Vec<u8>,
+  }
+
+  impl UsbSerial for Port {
+    fn read(&mut self, data: &mut [u8]) -> Result<usize, UsbError> {
+      let len = data.len().min(self.input.len()).min(USB_PACKET_SIZE);
+      if len == 0 {
+        return Err(UsbError::WouldBlock);
+      }
+
+      data[..len].copy_from_slice(&self.input[..len]);
+      self.input.drain(..len);
+      Ok(len)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<usize, UsbError> {
+      let len = data.len().min(USB_PACKET_SIZE - self.buffer.len());
+      if len == 0 {
+        return Err(UsbError::WouldBlock);
+      }
+
+      self.buffer.extend_from_slice(&data[..len]);
+      Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<(), UsbError> {
+      self.host.append(&mut self.buffer);
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn writes_frames_across_packets() {
+    let payloads: [Vec<u8>; 3] = [vec![0x01; 60], vec![END; 30], vec![0x02]];
+    let mut slip = UsbSlip::new(Port::default());
+
+    for payload in &payloads {
+      slip.queue_frame(payload);
+    }
+    while slip.flush() == Err(UsbError::WouldBlock) {
+      slip.get_mut().flush().unwrap();
+    }
+
+    let expected: Vec<u8> = payloads
+      .iter()
+      .flat_map(|payload| encode(payload).unwrap())
+      .collect();
+    assert_eq!(slip.into_inner().host, expected);
+  }
+
+  #[test]
+  fn reads_frames_across_packets() {
+    let payload: Vec<u8> = (0..=255).collect();
+    let mut input = encode(&payload).unwrap();
+    input.extend([END, 0x01, ESC, 0x02, END]);
+    let port = Port {
+      input,
+      ..Port::default()
+    };
+    let mut slip = UsbSlip::new(port);
+
+    assert_eq!(slip.read_frame(), Ok(payload));
+    assert_eq!(
+      slip.read_frame(),
+      Err(UsbSlipError::Decode(SlipError::InvalidEscapeByte {
+        found: 0x02
+      }))
+    );
+    assert_eq!(
+      slip.read_frame(),
+      Err(UsbSlipError::Usb(UsbError::WouldBlock))
+    );
+  }
+}
@@ -0,0 +1,180 @@
+use super::*;
+use crate::reader::READ_BUFFER_SIZE;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// Counters describing the work done by a `DecodeWorker` so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+  /// The number of bytes read.
+  pub bytes_read: u64,
+  /// The number of frames decoded and sent to the channel.
+  pub frames: u64,
+  /// The number of frames dropped because they weren't encoded correctly.
+  pub invalid_frames: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+  bytes_read: AtomicU64,
+  frames: AtomicU64,
+  invalid_frames: AtomicU64,
+}
+
+/// A handle to a task decoding frames in the background, created by
+/// `spawn_decoder`.
+///
+/// Dropping the handle also stops the task.
+#[derive(Debug)]
+pub struct DecodeWorker {
+  counters: Arc<Counters>,
+  shutdown: oneshot::Sender<()>,
+  task: JoinHandle<io::Result<()>>,
+}
+
+impl DecodeWorker {
+  /// Returns a snapshot of the worker's counters.
+  pub fn stats(&self) -> DecodeStats {
+    DecodeStats {
+      bytes_read: self.counters.bytes_read.load(Ordering::Relaxed),
+      frames: self.counters.frames.load(Ordering::Relaxed),
+      invalid_frames: self.counters.invalid_frames.load(Ordering::Relaxed),
+    }
+  }
+
+  /// Returns `true` once the task has stopped, whether because the reader
+  /// ended, the receiver was dropped, or an error occurred.
+  pub fn is_finished(&self) -> bool {
+    self.task.is_finished()
+  }
+
+  /// Stops the task and waits for it to finish.
+  ///
+  /// Returns the error that stopped the task, if reading failed before it
+  /// was asked to shut down.
+  pub async fn shutdown(self) -> io::Result<()> {
+    let _ = self.shutdown.send(());
+
+    self.task.await.map_err(io::Error::other)?
+  }
+}
+
+/// Spawns a task onto the current tokio runtime that decodes frames read
+/// from `reader`, sending them to the returned channel.
+///
+/// The channel holds up to `capacity` frames, and the task stops reading
+/// while it's full. Frames that aren't encoded correctly are dropped and
+/// counted in `DecodeStats::invalid_frames`. The task stops when the reader
+/// ends, the receiver is dropped, or `DecodeWorker::shutdown` is called.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::spawn_decoder;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let input: &[u8] = &[0xC0, 0x01, 0xC0, 0xC0, 0x02, 0xC0];
+/// let (mut frames, worker) = spawn_decoder(input, 8);
+///
+/// assert_eq!(frames.recv().await, Some(vec![0x01]));
+/// assert_eq!(frames.recv().await, Some(vec![0x02]));
+/// assert_eq!(frames.recv().await, None);
+///
+/// assert_eq!(worker.stats().frames, 2);
+/// worker.shutdown().await.unwrap();
+/// # });
+/// ```
+pub fn spawn_decoder<R>(reader: R, capacity: usize) -> (mpsc::Receiver<Vec<u8>>, DecodeWorker)
+where
+  R: AsyncRead + Unpin + Send + 'static,
+{
+  let (sender, receiver) = mpsc::channel(capacity);
+  let (shutdown, shutdown_receiver) = oneshot::channel();
+  let counters = Arc::new(Counters::default());
+  let task = tokio::spawn(run(reader, sender, counters.clone(), shutdown_receiver));
+
+  let worker = DecodeWorker {
+    counters,
+    shutdown,
+    task,
+  };
+
+  (receiver, worker)
+}
+
+async fn run<R: AsyncRead + Unpin>(
+  mut reader: R,
+  sender: mpsc::Sender<Vec<u8>>,
+  counters: Arc<Counters>,
+  mut shutdown: oneshot::Receiver<()>,
+) -> io::Result<()> {
+  let mut decoder = SlipDecoder::new();
+  let mut buffer = vec![0; READ_BUFFER_SIZE];
+
+  loop {
+    while let Some(frame) = decoder.next_frame() {
+      let frame = match frame {
+        Ok(frame) => frame,
+        Err(_) => {
+          counters.invalid_frames.fetch_add(1, Ordering::Relaxed);
+          continue;
+        }
+      };
+
+      tokio::select! {
+        sent = sender.send(frame) => match sent {
+          Ok(()) => counters.frames.fetch_add(1, Ordering::Relaxed),
+          Err(_) => return Ok(()),
+        },
+        _ = &mut shutdown => return Ok(()),
+      };
+    }
+
+    let read = tokio::select! {
+      read = reader.read(&mut buffer) => read?,
+      _ = &mut shutdown => return Ok(()),
+    };
+
+    if read == 0 {
+      return Ok(());
+    }
+
+    counters
+      .bytes_read
+      .fetch_add(read as u64, Ordering::Relaxed);
+    decoder.push(&buffer[..read]);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::io::AsyncWriteExt;
+
+  #[tokio::test]
+  async fn decodes_frames_until_shutdown() {
+    let (mut client, server) = tokio::io::duplex(64);
+    let (mut frames, worker) = spawn_decoder(server, 1);
+
+    client
+      .write_all(&[
+        END, 0x01, END, 0x02, ESC, 0x03, END, 0x04, ESC, ESC_END, END,
+      ])
+      .await
+      .unwrap();
+
+    assert_eq!(frames.recv().await, Some(vec![0x01]));
+    assert_eq!(frames.recv().await, Some(vec![0x04, END]));
+
+    let stats = worker.stats();
+    assert_eq!(stats.bytes_read, 11);
+    assert_eq!(stats.invalid_frames, 1);
+
+    worker.shutdown().await.unwrap();
+    assert_eq!(frames.recv().await, None);
+  }
+}
@@ -0,0 +1,488 @@
+use super::*;
+use crate::encoder::{for_each_encoded_run, for_each_escaped_run};
+use std::io::{self, IoSlice, Write};
+
+/// Writes frames following the SLIP protocol to any `std::io::Write`.
+///
+/// Frames are encoded straight into the inner writer with vectored writes,
+/// using slices like those produced by `encode_io_slices`, rather than
+/// encoding each frame into an intermediate buffer first. Any other
+/// `Framer`, such as `Cobs` or `SlipConfig::hdlc`, can be given to
+/// `with_framer` to write frames in that framing instead. Every
+/// `SlipConfig` is split into slices, length prefix and padding included,
+/// while framings that can't be, like `Cobs`, are encoded into a buffer
+/// that's kept between writes.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::SlipWriter;
+///
+/// let mut writer = SlipWriter::new(Vec::new());
+/// writer.write_frame(&[0x01, 0xDB, 0x49, 0xC0, 0x15]).unwrap();
+/// writer.flush().unwrap();
+///
+/// assert_eq!(writer.get_ref(), &[0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0]);
+/// ```
+#[derive(Debug)]
+pub struct SlipWriter<W, F = SlipConfig> {
+  inner: W,
+  mtu: Option<usize>,
+  framer: F,
+  buffer: Vec<u8>,
+}
+
+impl<W: Write> SlipWriter<W> {
+  /// Creates a new writer over `inner`.
+  pub fn new(inner: W) -> Self {
+    Self::with_framer(inner, SlipConfig::new())
+  }
+
+  /// Creates a new writer over `inner` that rejects payloads longer than
+  /// `mtu` bytes, such as `RFC_MTU`.
+  ///
+  /// Writing an oversized payload fails with an
+  /// `io::ErrorKind::InvalidInput` error, and nothing is written.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipWriter;
+  ///
+  /// let mut writer = SlipWriter::with_mtu(Vec::new(), 2);
+  ///
+  /// assert!(writer.write_frame(&[0x01, 0x02, 0x03]).is_err());
+  /// assert!(writer.write_all_frames(&[&[0x01], &[0x01, 0x02, 0x03]]).is_err());
+  /// assert!(writer.get_ref().is_empty());
+  /// ```
+  pub fn with_mtu(inner: W, mtu: usize) -> Self {
+    Self {
+      mtu: Some(mtu),
+      ..Self::new(inner)
+    }
+  }
+}
+
+impl<W: Write, F: Framer> SlipWriter<W, F> {
+  /// Creates a new writer over `inner` that encodes with `framer`.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::{Cobs, SlipWriter};
+  ///
+  /// let mut writer = SlipWriter::with_framer(Vec::new(), Cobs);
+  /// writer.write_frame(&[0xC0, 0x00]).unwrap();
+  ///
+  /// assert_eq!(writer.get_ref(), &[0x02, 0xC0, 0x01, 0x00]);
+  /// ```
+  pub fn with_framer(inner: W, framer: F) -> Self {
+    Self {
+      inner,
+      mtu: None,
+      framer,
+      buffer: Vec::new(),
+    }
+  }
+
+  /// Encodes `payload` as a single frame and writes it to the inner writer.
+  pub fn write_frame(&mut self, payload: &[u8]) -> io::Result<()> {
+    self.write_all_frames(&[payload])
+  }
+
+  /// Encodes each payload as its own frame and writes them all at once.
+  ///
+  /// Every frame is gathered into a single write, so a burst of small
+  /// frames costs one call to the inner writer rather than one each, as
+  /// long as it supports vectored writes.
+  ///
+  /// # Example:
+  ///
+  /// ```rust
+  /// use simple_slip::SlipWriter;
+  ///
+  /// let mut writer = SlipWriter::new(Vec::new());
+  /// writer.write_all_frames(&[&[0x01], &[0xC0]]).unwrap();
+  ///
+  /// assert_eq!(writer.get_ref(), &[0xC0, 0x01, 0xC0, 0xC0, 0xDB, 0xDC, 0xC0]);
+  /// ```
+  pub fn write_all_frames(&mut self, payloads: &[&[u8]]) -> io::Result<()> {
+    for payload in payloads {
+      self.check_mtu(payload)?;
+    }
+
+    let mut slices: Vec<IoSlice<'_>> = Vec::new();
+    let vectored = payloads.iter().all(|payload| {
+      self
+        .framer
+        .encode_runs(payload, |run| slices.push(IoSlice::new(run)))
+    });
+    if vectored {
+      return write_all_vectored(&mut self.inner, &mut slices);
+    }
+
+    self.buffer.clear();
+    for payload in payloads {
+      let len = payload.len() + self.framer.max_overhead(payload.len());
+      self.buffer.reserve(len);
+      self.framer.encode_frame(payload, &mut self.buffer)?;
+    }
+
+    self.inner.write_all(&self.buffer)
+  }
+
+  /// Flushes the inner writer.
+  pub fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+
+  /// Returns a reference to the inner writer.
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner writer.
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.inner
+  }
+
+  /// Consumes the `SlipWriter`, returning the inner writer.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+
+  fn check_mtu(&self, payload: &[u8]) -> io::Result<()> {
+    match self.mtu {
+      Some(mtu) if payload.len() > mtu => Err(SlipError::MtuExceeded.into()),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// An adapter that SLIP encodes everything written through it.
+///
+/// The bytes of each `write` call are treated as raw payload and encoded
+/// into the inner writer, all belonging to the same frame. Calling `flush`
+/// terminates the current frame, so the next write starts a new one. This
+/// allows SLIP framing to be slotted underneath existing code that only
+/// knows about `std::io::Write`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::EncodingWriter;
+/// use std::io::Write;
+///
+/// let mut writer = EncodingWriter::new(Vec::new());
+/// write!(writer, "hi").unwrap();
+/// writer.write_all(&[0xC0]).unwrap();
+/// writer.flush().unwrap();
+///
+/// assert_eq!(writer.get_ref(), &[0xC0, b'h', b'i', 0xDB, 0xDC, 0xC0]);
+/// ```
+#[derive(Debug)]
+pub struct EncodingWriter<W> {
+  inner: W,
+  in_frame: bool,
+}
+
+impl<W: Write> EncodingWriter<W> {
+  /// Creates a new adapter over `inner`.
+  pub fn new(inner: W) -> Self {
+    Self {
+      inner,
+      in_frame: false,
+    }
+  }
+
+  /// Returns a reference to the inner writer.
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner writer.
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.inner
+  }
+
+  /// Consumes the adapter, returning the inner writer.
+  ///
+  /// A frame that hasn't been terminated with `flush` is left unterminated.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let mut slices: Vec<IoSlice<'_>> = Vec::new();
+    if !self.in_frame {
+      slices.push(IoSlice::new(&[END]));
+    }
+    for_each_escaped_run(&SlipConfig::new(), buf, |run| {
+      slices.push(IoSlice::new(run))
+    });
+
+    write_all_vectored(&mut self.inner, &mut slices)?;
+    self.in_frame = true;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    if self.in_frame {
+      self.inner.write_all(&[END])?;
+      self.in_frame = false;
+    }
+
+    self.inner.flush()
+  }
+}
+
+/// A `ByteSink` that writes straight to any `std::io::Write`.
+///
+/// Each run of bytes is passed to `write_all` as it's produced, so frames
+/// are encoded into the writer without building them up in a buffer
+/// first. Errors from the writer are returned as is, and decoding errors
+/// are converted into an `io::Error`.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::{decode_to, encode_to, IoSink};
+///
+/// let mut sink = IoSink::new(Vec::new());
+/// encode_to(&[0x01, 0xC0], &mut sink).unwrap();
+///
+/// assert_eq!(sink.into_inner(), [0xC0, 0x01, 0xDB, 0xDC, 0xC0]);
+///
+/// let mut sink = IoSink::new(Vec::new());
+/// decode_to(&[0xC0, 0x01, 0xDB, 0xDC, 0xC0], &mut sink).unwrap();
+///
+/// assert_eq!(sink.into_inner(), [0x01, 0xC0]);
+/// ```
+#[derive(Debug)]
+pub struct IoSink<W> {
+  inner: W,
+}
+
+impl<W: Write> IoSink<W> {
+  /// Creates a new sink over `inner`.
+  pub fn new(inner: W) -> Self {
+    Self { inner }
+  }
+
+  /// Consumes the sink, returning the inner writer.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+}
+
+impl<W: Write> ByteSink for IoSink<W> {
+  type Error = io::Error;
+
+  fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+    self.inner.write_all(bytes)
+  }
+}
+
+/// Encodes data following the SLIP protocol into a list of `IoSlice`s.
+///
+/// Rather than copying the payload into a new buffer, the slices borrow
+/// runs of ordinary bytes straight from `raw_buffer`, with the delimiters
+/// and escape pairs in between. They can then be handed to
+/// `Write::write_vectored` to be written out without any copying.
+///
+/// # Example:
+///
+/// ```rust
+/// use simple_slip::encode_io_slices;
+///
+/// let input: Vec<u8> = vec![0x01, 0xDB, 0x49, 0xC0, 0x15];
+/// let expected: Vec<u8> = vec![0xC0, 0x01, 0xDB, 0xDD, 0x49, 0xDB, 0xDC, 0x15, 0xC0];
+///
+/// let slices = encode_io_slices(&input);
+/// let result: Vec<u8> = slices.iter().flat_map(|slice| slice.iter().copied()).collect();
+///
+/// assert_eq!(slices.len(), 7);
+/// assert_eq!(result, expected);
+/// ```
+pub fn encode_io_slices(raw_buffer: &[u8]) -> Vec<IoSlice<'_>> {
+  let mut slices: Vec<IoSlice<'_>> = Vec::new();
+  for_each_encoded_run(&SlipConfig::new(), raw_buffer, |run| {
+    slices.push(IoSlice::new(run))
+  });
+
+  slices
+}
+
+fn write_all_vectored<W: Write>(inner: &mut W, mut slices: &mut [IoSlice<'_>]) -> io::Result<()> {
+  while !slices.is_empty() {
+    match inner.write_vectored(slices) {
+      Ok(0) => {
+        return Err(io::Error::new(
+          io::ErrorKind::WriteZero,
+          "failed to write whole frame",
+        ))
+      }
+      Ok(written) => IoSlice::advance_slices(&mut slices, written),
+      Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+      Err(error) => return Err(error),
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn writes_the_same_bytes_as_encode() {
+    let payloads: [&[u8]; 4] = [
+      &[0x01, ESC, 0x49, END, 0x15],
+      &[END, END, ESC],
+      &[0x02],
+      &[],
+    ];
+    let mut writer = SlipWriter::new(Vec::new());
+    let mut expected: Vec<u8> = Vec::new();
+
+    for payload in payloads {
+      writer.write_frame(payload).unwrap();
+      expected.extend(encode(payload).unwrap());
+    }
+
+    assert_eq!(writer.into_inner(), expected);
+  }
+
+  #[test]
+  fn handles_partial_writes() {
+    struct Stingy(Vec<u8>);
+
+    impl Write for Stingy {
+      fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(2);
+        self.0.extend_from_slice(&buf[..len]);
+        Ok(len)
+      }
+
+      fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+      }
+    }
+
+    let payload: [u8; 7] = [0x01, 0x02, 0x03, ESC, END, 0x04, 0x05];
+    let mut writer = SlipWriter::new(Stingy(Vec::new()));
+    writer.write_frame(&payload).unwrap();
+
+    assert_eq!(writer.into_inner().0, encode(payload).unwrap());
+  }
+
+  #[test]
+  fn handles_partial_vectored_writes() {
+    struct Stingy(Vec<u8>, usize, usize);
+
+    impl Write for Stingy {
+      fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_vectored(&[IoSlice::new(buf)])
+      }
+
+      fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.1 += 1;
+        self.2 = self.2.max(bufs.len());
+        let bytes: Vec<u8> = bufs.iter().flat_map(|buf| buf.iter().copied()).collect();
+        let len = bytes.len().min(3);
+        self.0.extend_from_slice(&bytes[..len]);
+        Ok(len)
+      }
+
+      fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+      }
+    }
+
+    let payloads: [&[u8]; 2] = [&[0x01, 0x02, 0x03, ESC, END, 0x04, 0x05], &[END]];
+    let mut writer = SlipWriter::new(Stingy(Vec::new(), 0, 0));
+    writer.write_all_frames(&payloads).unwrap();
+
+    let inner = writer.into_inner();
+    let expected: Vec<u8> = payloads
+      .iter()
+      .flat_map(|payload| encode(payload).unwrap())
+      .collect();
+    assert_eq!(inner.0, expected);
+    assert_eq!(inner.1, expected.len().div_ceil(3));
+
+    // Every SLIP profile is split into slices, prefixes and padding included.
+    let configs = [
+      SlipConfig::new().pad_to(4),
+      SlipConfig::new().length_prefix(true),
+      SlipConfig::new().flush_end(true),
+      SlipConfig::hdlc(),
+    ];
+    for config in configs {
+      let mut writer = SlipWriter::with_framer(Stingy(Vec::new(), 0, 0), config);
+      writer.write_frame(payloads[0]).unwrap();
+      let inner = writer.into_inner();
+      assert_eq!(inner.0, config.encode(payloads[0]).unwrap());
+      assert!(inner.2 > 1);
+    }
+
+    // Framings that can't be split into slices fall back to the buffer.
+    let mut writer = SlipWriter::with_framer(Stingy(Vec::new(), 0, 0), Cobs);
+    writer.write_frame(payloads[0]).unwrap();
+    let inner = writer.into_inner();
+    assert_eq!(inner.0, cobs_encode(payloads[0]));
+    assert_eq!(inner.2, 1);
+  }
+
+  #[test]
+  fn writes_all_frames_in_one_call() {
+    struct Counting(Vec<u8>, usize);
+
+    impl Write for Counting {
+      fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_vectored(&[IoSlice::new(buf)])
+      }
+
+      fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.1 += 1;
+        bufs.iter().for_each(|buf| self.0.extend_from_slice(buf));
+        Ok(bufs.iter().map(|buf| buf.len()).sum())
+      }
+
+      fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+      }
+    }
+
+    let payloads: [&[u8]; 3] = [&[0x01, END], &[ESC], &[0x02, 0x03]];
+    let mut writer = SlipWriter::new(Counting(Vec::new(), 0));
+    writer.write_all_frames(&payloads).unwrap();
+
+    let inner = writer.into_inner();
+    let expected: Vec<u8> = payloads
+      .iter()
+      .flat_map(|payload| encode(payload).unwrap())
+      .collect();
+    assert_eq!(inner.0, expected);
+    assert_eq!(inner.1, 1);
+  }
+
+  #[test]
+  fn flush_terminates_frames() {
+    let mut writer = EncodingWriter::new(Vec::new());
+
+    writer.write_all(&[0x01, END]).unwrap();
+    writer.write_all(&[ESC]).unwrap();
+    writer.flush().unwrap();
+    writer.flush().unwrap();
+    writer.write_all(&[0x02]).unwrap();
+    writer.flush().unwrap();
+
+    let mut expected = encode([0x01, END, ESC]).unwrap();
+    expected.extend(encode([0x02]).unwrap());
+    assert_eq!(writer.into_inner(), expected);
+  }
+}